@@ -1,69 +1,294 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount, Transfer};
+use anchor_lang::system_program;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount};
 
 
-declare_id!("5jdU5SpLxidhessiSTiAeuATxh7sSHnWKvvVVDK7");
+declare_id!("51jdU5SpLxidhessiSTiAe3uATxh7sSHn1WKvvVVDK74");
+
+use meraki_core::{invest_split, MathError};
+pub use meraki_core::{MAX_INVESTOR_RETURN_BPS, SHARE_DECIMALS, TOTAL_SHARE_SUPPLY};
+
+pub const MAX_RETURN_TIERS: usize = 4;
+// How long an invested contract may go without a revenue report before anyone
+// can mark it as defaulted.
+pub const REPORTING_GRACE_PERIOD: i64 = 90 * 24 * 60 * 60;
+pub const MAX_METADATA_URI_LEN: usize = 200;
+// `Delegate::permissions` bits.
+pub const PERMISSION_REPORT_REVENUE: u8 = 1 << 0;
+pub const PERMISSION_WITHDRAW_TRANCHE: u8 = 1 << 1;
+pub const ALL_PERMISSIONS: u8 = PERMISSION_REPORT_REVENUE | PERMISSION_WITHDRAW_TRANCHE;
 
 #[program]
 pub mod meraki_contract {
     use super::*;
 
 
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize_contract(
         ctx: Context<InitializeContract>,
-        amount: u6,
-        investor_return_percent: u8,
-        duration_days: u6,
+        amount: u64,
+        investor_return_bps: u16,
+        duration_days: u64,
+        is_native: bool,
+        equity_offered_bps: u16,
+        allowlist: AllowlistMode,
+        return_tiers: Vec<ReturnTier>,
+        collateral_amount: u64,
+        terms_hash: [u8; 32],
+        metadata_uri: String,
     ) -> Result<()> {
+        require!(amount > 0, MerakiError::InvalidAmount);
+        require!(
+            metadata_uri.len() <= MAX_METADATA_URI_LEN,
+            MerakiError::MetadataUriTooLong
+        );
+        require!(equity_offered_bps <= 10_000, MerakiError::InvalidEquity);
+        require!(
+            investor_return_bps <= MAX_INVESTOR_RETURN_BPS,
+            MerakiError::InvalidReturnBps
+        );
+        require!(
+            return_tiers.len() <= MAX_RETURN_TIERS,
+            MerakiError::TooManyReturnTiers
+        );
+        for tier in &return_tiers {
+            require!(
+                tier.rate_bps <= MAX_INVESTOR_RETURN_BPS,
+                MerakiError::InvalidReturnBps
+            );
+        }
+
+        let mint = match (is_native, &ctx.accounts.mint) {
+            (true, _) => Pubkey::default(),
+            (false, Some(mint)) => mint.key(),
+            (false, None) => return err!(MerakiError::MissingMint),
+        };
+
+        // Native vaults hold bare lamports, so they are seeded with the rent
+        // exempt minimum up front and never drop below it on later refunds.
+        if is_native {
+            let rent_exempt_minimum = Rent::get()?.minimum_balance(0);
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.investor.to_account_info(),
+                        to: ctx.accounts.vault.to_account_info(),
+                    },
+                ),
+                rent_exempt_minimum,
+            )?;
+        }
+
+        // The startup also covers the rent exempt minimum of the collateral
+        // PDA, so payouts never leave it below that and it can be drained to
+        // zero once the collateral is released or slashed.
+        if collateral_amount > 0 {
+            require!(ctx.accounts.startup.is_signer, MerakiError::Unauthorized);
+            let deposit = collateral_amount
+                .checked_add(Rent::get()?.minimum_balance(0))
+                .ok_or(MerakiError::Overflow)?;
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.startup.to_account_info(),
+                        to: ctx.accounts.collateral.to_account_info(),
+                    },
+                ),
+                deposit,
+            )?;
+        }
+
         let contract = &mut ctx.accounts.investment_contract;
 
         contract.investor = ctx.accounts.investor.key();
         contract.startup = ctx.accounts.startup.key();
         contract.amount = amount;
-        contract.investor_return_percent = investor_return_percent;
+        contract.investor_return_bps = investor_return_bps;
         contract.duration_days = duration_days;
         contract.start_time = Clock::get()?.unix_timestamp;
         contract.is_active = true;
         contract.total_revenue = 0;
         contract.total_distributed = 0;
         contract.is_invested = false;
+        contract.amendment_count = 0;
+        contract.is_native = is_native;
+        contract.mint = mint;
+        contract.is_refunded = false;
+        contract.equity_offered_bps = equity_offered_bps;
+        contract.total_raised = 0;
+        contract.total_shares = 0;
+        contract.allowlist = allowlist;
+        contract.return_tiers = return_tiers;
+        contract.collateral_amount = collateral_amount;
+        contract.is_defaulted = false;
+        contract.is_completed = false;
+        contract.last_report_at = contract.start_time;
+        contract.terms_hash = terms_hash;
+        contract.metadata_uri = metadata_uri;
+        contract.total_withdrawn = 0;
+
+        emit!(ContractInitialized {
+            contract: contract.key(),
+            investor: contract.investor,
+            startup: contract.startup,
+            amount: contract.amount,
+            terms_hash: contract.terms_hash,
+            metadata_uri: contract.metadata_uri.clone(),
+        });
 
         Ok(())
     }
 
-    
-    pub fn invest(ctx: Context<Invest>) -> Result<()> {
-        let total = ctx.accounts.investment_contract.amount;
-        let meraki_fee = total / 00; // 0.5%
-        let startup_amount = total - meraki_fee;
+    // SPL token contracts keep invested tokens in the PDA token account
+    // `["token_vault", contract]`, owned by the vault PDA. The investor pays
+    // its rent, like the contract's own, and gets it back in `close_contract`.
+    pub fn initialize_token_vault(_ctx: Context<InitializeTokenVault>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn invest(ctx: Context<Invest>, amount: u64) -> Result<()> {
+        require!(
+            !ctx.accounts.investment_contract.is_native,
+            MerakiError::WrongDenomination
+        );
 
-        token::transfer(ctx.accounts.transfer_to_meraki_ctx(), meraki_fee)?;
+        check_attestation(
+            &ctx.accounts.investment_contract,
+            &ctx.accounts.config,
+            ctx.accounts.investor.key(),
+            ctx.accounts.attestation.as_ref(),
+        )?;
+
+        let position = &mut ctx.accounts.position;
+        position.contract = ctx.accounts.investment_contract.key();
+        position.investor = ctx.accounts.investor.key();
+        position.bump = ctx.bumps.position;
+        let shares = record_contribution(&mut ctx.accounts.investment_contract, position, amount)?;
+
+        let (meraki_fee, startup_amount) = invest_split(amount);
+
+        token::transfer(ctx.accounts.transfer_to_treasury_ctx(), meraki_fee)?;
         token::transfer(ctx.accounts.transfer_to_contract_vault_ctx(), startup_amount)?;
 
-        ctx.accounts.investment_contract.is_invested = true;
+        let contract_key = ctx.accounts.investment_contract.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", contract_key.as_ref(), &[ctx.bumps.vault]]];
+        token::mint_to(
+            ctx.accounts.mint_shares_ctx().with_signer(signer_seeds),
+            shares,
+        )?;
+
         Ok(())
     }
 
+    pub fn record_revenue(ctx: Context<RecordRevenue>, revenue_amount: u64) -> Result<()> {
+        require!(
+            !ctx.accounts.investment_contract.is_native,
+            MerakiError::WrongDenomination
+        );
+        check_startup_authority(
+            &ctx.accounts.investment_contract,
+            ctx.accounts.payer.key(),
+            ctx.accounts.delegate.as_ref(),
+            PERMISSION_REPORT_REVENUE,
+        )?;
+        // The whole investor share goes to one account, which is only right
+        // while the creator's position is the only one.
+        require!(
+            ctx.accounts.position.contributed == ctx.accounts.investment_contract.total_raised,
+            MerakiError::MultiplePositions
+        );
 
-    pub fn record_revenue(ctx: Context<RecordRevenue>, revenue_amount: u6) -> Result<()> {
-        let meraki_fee = revenue_amount / 00;
-        let investor_share = (revenue_amount
-            * ctx.accounts.investment_contract.investor_return_percent as u6)
-            / 00;
-        let startup_share = revenue_amount - meraki_fee - investor_share;
+        let return_bps = ctx
+            .accounts
+            .investment_contract
+            .current_return_bps(Clock::get()?.unix_timestamp);
+        let (meraki_fee, investor_share, startup_share) =
+            revenue_split(revenue_amount, return_bps)?;
 
-        token::transfer(ctx.accounts.transfer_to_meraki_ctx(), meraki_fee)?;
+        token::transfer(ctx.accounts.transfer_to_treasury_ctx(), meraki_fee)?;
         token::transfer(ctx.accounts.transfer_to_investor_ctx(), investor_share)?;
         token::transfer(ctx.accounts.transfer_to_startup_ctx(), startup_share)?;
 
         let contract = &mut ctx.accounts.investment_contract;
-        contract.total_revenue += revenue_amount;
-        contract.total_distributed += investor_share;
+        contract.total_revenue = contract
+            .total_revenue
+            .checked_add(revenue_amount)
+            .ok_or(MerakiError::Overflow)?;
+        contract.total_distributed = contract
+            .total_distributed
+            .checked_add(investor_share)
+            .ok_or(MerakiError::Overflow)?;
+        contract.last_report_at = Clock::get()?.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Same split as `record_revenue`, but the investor share is paid to every
+    // position at once. `remaining_accounts` holds one
+    // `(position, investor_token_account)` pair per position, ordered by
+    // investor key, and must cover the whole contract.
+    pub fn record_revenue_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RecordRevenueBatch<'info>>,
+        revenue_amount: u64,
+    ) -> Result<()> {
+        let contract = &ctx.accounts.investment_contract;
+        require!(!contract.is_native, MerakiError::WrongDenomination);
+        check_startup_authority(
+            contract,
+            ctx.accounts.payer.key(),
+            ctx.accounts.delegate.as_ref(),
+            PERMISSION_REPORT_REVENUE,
+        )?;
+
+        let positions = load_positions(contract, ctx.remaining_accounts)?;
+        for (position, investor_token_account) in &positions {
+            let token_account = Account::<TokenAccount>::try_from(investor_token_account)?;
+            require_keys_eq!(
+                token_account.mint,
+                contract.mint,
+                MerakiError::InvalidRecipient
+            );
+            require_keys_eq!(
+                token_account.owner,
+                position.investor,
+                MerakiError::InvalidRecipient
+            );
+        }
+
+        let return_bps = contract.current_return_bps(Clock::get()?.unix_timestamp);
+        let (meraki_fee, investor_share, startup_share) =
+            revenue_split(revenue_amount, return_bps)?;
+        let weights: Vec<u64> = positions.iter().map(|(p, _)| p.contributed).collect();
+        let payouts = pro_rata(investor_share, &weights, contract.total_raised)?;
+
+        token::transfer(ctx.accounts.transfer_to_treasury_ctx(), meraki_fee)?;
+        token::transfer(ctx.accounts.transfer_to_startup_ctx(), startup_share)?;
+        for ((_, investor_token_account), payout) in positions.iter().zip(payouts) {
+            if payout > 0 {
+                token::transfer(
+                    ctx.accounts.transfer_from_payer_ctx(investor_token_account),
+                    payout,
+                )?;
+            }
+        }
+
+        let contract = &mut ctx.accounts.investment_contract;
+        contract.total_revenue = contract
+            .total_revenue
+            .checked_add(revenue_amount)
+            .ok_or(MerakiError::Overflow)?;
+        contract.total_distributed = contract
+            .total_distributed
+            .checked_add(investor_share)
+            .ok_or(MerakiError::Overflow)?;
+        contract.last_report_at = Clock::get()?.unix_timestamp;
 
         Ok(())
     }
 
-    
     pub fn mint_investment_nft(ctx: Context<MintNFT>) -> Result<()> {
         let cpi_ctx = CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
@@ -73,7 +298,814 @@ pub mod meraki_contract {
                 authority: ctx.accounts.mint_authority.to_account_info(),
             },
         );
-        token::mint_to(cpi_ctx, )?;
+        token::mint_to(cpi_ctx, 1)?;
+        Ok(())
+    }
+
+    pub fn invest_native(ctx: Context<InvestNative>, amount: u64) -> Result<()> {
+        require!(
+            ctx.accounts.investment_contract.is_native,
+            MerakiError::WrongDenomination
+        );
+
+        check_attestation(
+            &ctx.accounts.investment_contract,
+            &ctx.accounts.config,
+            ctx.accounts.investor.key(),
+            ctx.accounts.attestation.as_ref(),
+        )?;
+
+        let position = &mut ctx.accounts.position;
+        position.contract = ctx.accounts.investment_contract.key();
+        position.investor = ctx.accounts.investor.key();
+        position.bump = ctx.bumps.position;
+        let shares = record_contribution(&mut ctx.accounts.investment_contract, position, amount)?;
+
+        let (meraki_fee, startup_amount) = invest_split(amount);
+
+        system_program::transfer(ctx.accounts.transfer_to_treasury_ctx(), meraki_fee)?;
+        system_program::transfer(ctx.accounts.transfer_to_vault_ctx(), startup_amount)?;
+
+        let contract_key = ctx.accounts.investment_contract.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", contract_key.as_ref(), &[ctx.bumps.vault]]];
+        token::mint_to(
+            ctx.accounts.mint_shares_ctx().with_signer(signer_seeds),
+            shares,
+        )?;
+
+        Ok(())
+    }
+
+    // Native counterpart of `record_revenue_batch`: `remaining_accounts` holds
+    // one `(position, investor)` pair per position, ordered by investor key,
+    // and the investor share is paid to every position pro rata.
+    pub fn record_revenue_native<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RecordRevenueNative<'info>>,
+        revenue_amount: u64,
+    ) -> Result<()> {
+        let contract = &ctx.accounts.investment_contract;
+        require!(contract.is_native, MerakiError::WrongDenomination);
+        check_startup_authority(
+            contract,
+            ctx.accounts.payer.key(),
+            ctx.accounts.delegate.as_ref(),
+            PERMISSION_REPORT_REVENUE,
+        )?;
+
+        let positions = load_positions(contract, ctx.remaining_accounts)?;
+        for (position, investor) in &positions {
+            require_keys_eq!(investor.key(), position.investor, MerakiError::InvalidRecipient);
+        }
+
+        let return_bps = contract.current_return_bps(Clock::get()?.unix_timestamp);
+        let (meraki_fee, investor_share, startup_share) =
+            revenue_split(revenue_amount, return_bps)?;
+        let weights: Vec<u64> = positions.iter().map(|(p, _)| p.contributed).collect();
+        let payouts = pro_rata(investor_share, &weights, contract.total_raised)?;
+
+        system_program::transfer(ctx.accounts.transfer_to_treasury_ctx(), meraki_fee)?;
+        system_program::transfer(ctx.accounts.transfer_to_startup_ctx(), startup_share)?;
+        for ((_, investor), payout) in positions.iter().zip(payouts) {
+            if payout > 0 {
+                system_program::transfer(ctx.accounts.transfer_from_payer_ctx(investor), payout)?;
+            }
+        }
+
+        let contract = &mut ctx.accounts.investment_contract;
+        contract.total_revenue = contract
+            .total_revenue
+            .checked_add(revenue_amount)
+            .ok_or(MerakiError::Overflow)?;
+        contract.total_distributed = contract
+            .total_distributed
+            .checked_add(investor_share)
+            .ok_or(MerakiError::Overflow)?;
+        contract.last_report_at = Clock::get()?.unix_timestamp;
+
+        Ok(())
+    }
+
+    // The startup hands a position's vaulted lamports back to its investor and
+    // the contract is wound down. The platform fee taken on `invest_native` is
+    // kept, so the refund is the contribution net of that fee, capped at the
+    // position's pro-rata slice of what tranche withdrawals left in the vault.
+    pub fn refund_native(ctx: Context<RefundNative>) -> Result<()> {
+        let contract = &ctx.accounts.investment_contract;
+        require!(contract.is_native, MerakiError::WrongDenomination);
+        require!(
+            contract.is_active || contract.is_refunded,
+            MerakiError::ContractInactive
+        );
+        require!(
+            !ctx.accounts.position.is_refunded,
+            MerakiError::AlreadyRefunded
+        );
+
+        let contributed = ctx.accounts.position.contributed;
+        let available = ctx
+            .accounts
+            .vault
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0));
+        let (_, net_contribution) = invest_split(contributed);
+        let refund_amount =
+            net_contribution.min(position_slice(available, &ctx.accounts.position, contract)?);
+        let contract_key = contract.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", contract_key.as_ref(), &[ctx.bumps.vault]]];
+        system_program::transfer(
+            ctx.accounts
+                .refund_to_investor_ctx()
+                .with_signer(signer_seeds),
+            refund_amount,
+        )?;
+
+        ctx.accounts.position.is_refunded = true;
+
+        let contract = &mut ctx.accounts.investment_contract;
+        contract.total_raised = contract
+            .total_raised
+            .checked_sub(contributed)
+            .ok_or(MerakiError::Overflow)?;
+        contract.is_active = false;
+        contract.is_refunded = true;
+
+        Ok(())
+    }
+
+    // Vaulted funds are released to the startup as they vest, linearly over
+    // the contract's duration from `start_time`. The startup or a delegate
+    // with `PERMISSION_WITHDRAW_TRANCHE` can withdraw, but the funds always
+    // go to the startup.
+    pub fn withdraw_tranche(ctx: Context<WithdrawTranche>, amount: u64) -> Result<()> {
+        let contract = &ctx.accounts.investment_contract;
+        require!(!contract.is_native, MerakiError::WrongDenomination);
+        check_startup_authority(
+            contract,
+            ctx.accounts.authority.key(),
+            ctx.accounts.delegate.as_ref(),
+            PERMISSION_WITHDRAW_TRANCHE,
+        )?;
+        let total_withdrawn = check_tranche(contract, ctx.accounts.token_vault.amount, amount)?;
+
+        let contract_key = contract.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", contract_key.as_ref(), &[ctx.bumps.vault]]];
+        token::transfer(
+            ctx.accounts
+                .transfer_to_startup_ctx()
+                .with_signer(signer_seeds),
+            amount,
+        )?;
+
+        ctx.accounts.investment_contract.total_withdrawn = total_withdrawn;
+
+        emit!(TrancheWithdrawn {
+            contract: contract_key,
+            authority: ctx.accounts.authority.key(),
+            amount,
+            total_withdrawn,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_tranche_native(ctx: Context<WithdrawTrancheNative>, amount: u64) -> Result<()> {
+        let contract = &ctx.accounts.investment_contract;
+        require!(contract.is_native, MerakiError::WrongDenomination);
+        check_startup_authority(
+            contract,
+            ctx.accounts.authority.key(),
+            ctx.accounts.delegate.as_ref(),
+            PERMISSION_WITHDRAW_TRANCHE,
+        )?;
+        let available = ctx
+            .accounts
+            .vault
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0));
+        let total_withdrawn = check_tranche(contract, available, amount)?;
+
+        let contract_key = contract.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", contract_key.as_ref(), &[ctx.bumps.vault]]];
+        system_program::transfer(
+            ctx.accounts
+                .transfer_to_startup_ctx()
+                .with_signer(signer_seeds),
+            amount,
+        )?;
+
+        ctx.accounts.investment_contract.total_withdrawn = total_withdrawn;
+
+        emit!(TrancheWithdrawn {
+            contract: contract_key,
+            authority: ctx.accounts.authority.key(),
+            amount,
+            total_withdrawn,
+        });
+
+        Ok(())
+    }
+
+    // The startup grants `authority` the given `PERMISSION_*` bits on this
+    // contract. Calling it again for the same key replaces them.
+    pub fn add_delegate(
+        ctx: Context<AddDelegate>,
+        authority: Pubkey,
+        permissions: u8,
+    ) -> Result<()> {
+        require!(
+            permissions != 0 && permissions & !ALL_PERMISSIONS == 0,
+            MerakiError::InvalidPermissions
+        );
+
+        let delegate = &mut ctx.accounts.delegate;
+        delegate.contract = ctx.accounts.investment_contract.key();
+        delegate.authority = authority;
+        delegate.permissions = permissions;
+        delegate.bump = ctx.bumps.delegate;
+
+        Ok(())
+    }
+
+    pub fn revoke_delegate(_ctx: Context<RevokeDelegate>) -> Result<()> {
+        Ok(())
+    }
+
+    // The arbiter can declare a default at any time; anyone can once an
+    // invested contract has gone `REPORTING_GRACE_PERIOD` without a revenue
+    // report.
+    pub fn mark_defaulted(ctx: Context<MarkDefaulted>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let contract = &mut ctx.accounts.investment_contract;
+        require!(contract.is_active, MerakiError::ContractInactive);
+
+        let is_arbiter = ctx.accounts.signer.key() == ctx.accounts.config.arbiter;
+        require!(
+            is_arbiter || contract.missed_reporting(now),
+            MerakiError::Unauthorized
+        );
+
+        contract.is_active = false;
+        contract.is_defaulted = true;
+
+        emit!(ContractDefaulted {
+            contract: contract.key(),
+            marked_by: ctx.accounts.signer.key(),
+            by_arbiter: is_arbiter,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // Pays a defaulted contract's collateral out to its investors pro rata to
+    // their contributions. `remaining_accounts` takes `(position, investor)`
+    // pairs with the same rules as `record_revenue_batch`.
+    pub fn slash_collateral<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleCollateral<'info>>,
+    ) -> Result<()> {
+        let contract = &ctx.accounts.investment_contract;
+        require!(contract.is_defaulted, MerakiError::NotDefaulted);
+        require!(contract.collateral_amount > 0, MerakiError::NoCollateral);
+
+        let positions = load_positions(contract, ctx.remaining_accounts)?;
+        for (position, investor) in &positions {
+            require_keys_eq!(
+                investor.key(),
+                position.investor,
+                MerakiError::InvalidRecipient
+            );
+        }
+
+        let weights: Vec<u64> = positions.iter().map(|(p, _)| p.contributed).collect();
+        let payouts = pro_rata(contract.collateral_amount, &weights, contract.total_raised)?;
+
+        let contract_key = contract.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"collateral",
+            contract_key.as_ref(),
+            &[ctx.bumps.collateral],
+        ]];
+        for ((_, investor), payout) in positions.iter().zip(payouts) {
+            if payout > 0 {
+                system_program::transfer(
+                    ctx.accounts
+                        .transfer_from_collateral_ctx(investor)
+                        .with_signer(signer_seeds),
+                    payout,
+                )?;
+            }
+        }
+        // Only the rent deposit is left; hand it back to the startup.
+        let leftover = ctx.accounts.collateral.lamports();
+        system_program::transfer(
+            ctx.accounts
+                .transfer_from_collateral_ctx(&ctx.accounts.startup.to_account_info())
+                .with_signer(signer_seeds),
+            leftover,
+        )?;
+
+        let contract = &mut ctx.accounts.investment_contract;
+        emit!(CollateralSlashed {
+            contract: contract_key,
+            amount: contract.collateral_amount,
+            positions: positions.len() as u32,
+        });
+        contract.collateral_amount = 0;
+
+        Ok(())
+    }
+
+    // Once the contract has run its full duration without defaulting it is
+    // completed and the startup gets its collateral back. A contract that
+    // is behind on its revenue reports can't be completed: it is up for
+    // `mark_defaulted` instead.
+    pub fn complete_contract(ctx: Context<ReturnCollateral>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let contract = &ctx.accounts.investment_contract;
+        require!(contract.is_active, MerakiError::ContractInactive);
+
+        let ends_at = contract
+            .start_time
+            .checked_add(contract.duration_seconds()?)
+            .ok_or(MerakiError::Overflow)?;
+        require!(now >= ends_at, MerakiError::ContractNotEnded);
+        require!(!contract.missed_reporting(now), MerakiError::ReportingOverdue);
+
+        ctx.accounts.return_collateral(ctx.bumps.collateral)?;
+
+        let contract = &mut ctx.accounts.investment_contract;
+        contract.is_active = false;
+        contract.is_completed = true;
+
+        Ok(())
+    }
+
+    // Hands the collateral back to the startup when there is no one left to
+    // slash it to: every position of a refunded contract has been refunded,
+    // or a defaulted contract never raised anything.
+    pub fn release_collateral(ctx: Context<ReturnCollateral>) -> Result<()> {
+        let contract = &ctx.accounts.investment_contract;
+        require!(
+            contract.is_refunded || contract.is_defaulted,
+            MerakiError::ContractNotFinished
+        );
+        require!(contract.collateral_amount > 0, MerakiError::NoCollateral);
+        require!(contract.total_raised == 0, MerakiError::PositionsOutstanding);
+
+        ctx.accounts.return_collateral(ctx.bumps.collateral)?;
+
+        Ok(())
+    }
+
+    // What is left in a defaulted contract's vault goes back to its
+    // investors pro rata to their contributions, one position at a time.
+    // Anyone can crank it, but the funds only go to the position's investor.
+    // The collateral is slashed first, while every position still counts.
+    pub fn refund_defaulted_native(ctx: Context<RefundDefaultedNative>) -> Result<()> {
+        let contract = &ctx.accounts.investment_contract;
+        require!(contract.is_native, MerakiError::WrongDenomination);
+        check_defaulted_refund(contract, &ctx.accounts.position)?;
+
+        let available = ctx
+            .accounts
+            .vault
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0));
+        let refund_amount = position_slice(available, &ctx.accounts.position, contract)?;
+        let contract_key = contract.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", contract_key.as_ref(), &[ctx.bumps.vault]]];
+        if refund_amount > 0 {
+            system_program::transfer(
+                ctx.accounts
+                    .refund_to_investor_ctx()
+                    .with_signer(signer_seeds),
+                refund_amount,
+            )?;
+        }
+
+        settle_defaulted_refund(
+            &mut ctx.accounts.investment_contract,
+            &mut ctx.accounts.position,
+            refund_amount,
+        )
+    }
+
+    pub fn refund_defaulted(ctx: Context<RefundDefaulted>) -> Result<()> {
+        let contract = &ctx.accounts.investment_contract;
+        require!(!contract.is_native, MerakiError::WrongDenomination);
+        check_defaulted_refund(contract, &ctx.accounts.position)?;
+
+        let refund_amount = position_slice(
+            ctx.accounts.token_vault.amount,
+            &ctx.accounts.position,
+            contract,
+        )?;
+        let contract_key = contract.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", contract_key.as_ref(), &[ctx.bumps.vault]]];
+        if refund_amount > 0 {
+            token::transfer(
+                ctx.accounts
+                    .refund_to_investor_ctx()
+                    .with_signer(signer_seeds),
+                refund_amount,
+            )?;
+        }
+
+        settle_defaulted_refund(
+            &mut ctx.accounts.investment_contract,
+            &mut ctx.accounts.position,
+            refund_amount,
+        )
+    }
+
+    // Reclaims the rent of a finished (completed, refunded or defaulted)
+    // contract. The collateral must be settled and the vaults must hold
+    // nothing but rent: it is drained back to the investor who paid for
+    // them, and the contract account is closed.
+    pub fn close_contract(ctx: Context<CloseContract>) -> Result<()> {
+        let contract = &ctx.accounts.investment_contract;
+        require!(
+            contract.is_completed || contract.is_refunded || contract.is_defaulted,
+            MerakiError::ContractNotFinished
+        );
+        require!(
+            contract.collateral_amount == 0,
+            MerakiError::CollateralOutstanding
+        );
+
+        let contract_key = contract.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", contract_key.as_ref(), &[ctx.bumps.vault]]];
+
+        if contract.is_native {
+            let vault_balance = ctx.accounts.vault.lamports();
+            require!(
+                vault_balance <= Rent::get()?.minimum_balance(0),
+                MerakiError::VaultNotEmpty
+            );
+            if vault_balance > 0 {
+                system_program::transfer(
+                    ctx.accounts.drain_vault_ctx().with_signer(signer_seeds),
+                    vault_balance,
+                )?;
+            }
+        } else {
+            let token_vault = ctx
+                .accounts
+                .token_vault
+                .as_ref()
+                .ok_or(MerakiError::MissingTokenVault)?;
+            require!(token_vault.amount == 0, MerakiError::VaultNotEmpty);
+            token::close_account(
+                ctx.accounts
+                    .close_token_vault_ctx(token_vault)
+                    .with_signer(signer_seeds),
+            )?;
+        }
+
+        emit!(ContractClosed {
+            contract: contract_key,
+            investor: contract.investor,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_arbiter(ctx: Context<UpdateConfig>, arbiter: Pubkey) -> Result<()> {
+        ctx.accounts.config.arbiter = arbiter;
+        Ok(())
+    }
+
+    pub fn propose_amendment(
+        ctx: Context<ProposeAmendment>,
+        investor_return_bps: Option<u16>,
+        duration_days: Option<u64>,
+        expires_in_seconds: i64,
+    ) -> Result<()> {
+        require!(
+            investor_return_bps.is_some() || duration_days.is_some(),
+            MerakiError::EmptyAmendment
+        );
+        if let Some(bps) = investor_return_bps {
+            require!(
+                bps <= MAX_INVESTOR_RETURN_BPS,
+                MerakiError::InvalidReturnBps
+            );
+        }
+        require!(expires_in_seconds > 0, MerakiError::InvalidExpiry);
+
+        let contract = &ctx.accounts.investment_contract;
+        require!(contract.is_active, MerakiError::ContractInactive);
+
+        let proposer = ctx.accounts.proposer.key();
+        require!(
+            proposer == contract.investor || proposer == contract.startup,
+            MerakiError::Unauthorized
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let amendment = &mut ctx.accounts.amendment;
+        amendment.contract = contract.key();
+        amendment.proposer = proposer;
+        amendment.investor_return_bps = investor_return_bps;
+        amendment.duration_days = duration_days;
+        amendment.investor_signed = proposer == contract.investor;
+        amendment.startup_signed = proposer == contract.startup;
+        amendment.created_at = now;
+        amendment.expires_at = now
+            .checked_add(expires_in_seconds)
+            .ok_or(MerakiError::Overflow)?;
+        amendment.bump = ctx.bumps.amendment;
+
+        Ok(())
+    }
+
+    pub fn accept_amendment(ctx: Context<AcceptAmendment>) -> Result<()> {
+        let signer = ctx.accounts.signer.key();
+        let contract = &mut ctx.accounts.investment_contract;
+        let amendment = &mut ctx.accounts.amendment;
+
+        require!(contract.is_active, MerakiError::ContractInactive);
+        require!(
+            Clock::get()?.unix_timestamp < amendment.expires_at,
+            MerakiError::AmendmentExpired
+        );
+        require!(
+            signer == contract.investor || signer == contract.startup,
+            MerakiError::Unauthorized
+        );
+
+        if signer == contract.investor {
+            amendment.investor_signed = true;
+        }
+        if signer == contract.startup {
+            amendment.startup_signed = true;
+        }
+        require!(
+            amendment.investor_signed && amendment.startup_signed,
+            MerakiError::AmendmentNotFullySigned
+        );
+
+        if let Some(bps) = amendment.investor_return_bps {
+            contract.investor_return_bps = bps;
+        }
+        if let Some(days) = amendment.duration_days {
+            contract.duration_days = days;
+        }
+        contract.amendment_count = contract
+            .amendment_count
+            .checked_add(1)
+            .ok_or(MerakiError::Overflow)?;
+
+        Ok(())
+    }
+
+    // Either party may withdraw a pending proposal; once it has expired anyone
+    // may close it so a fresh one can be proposed.
+    pub fn cancel_amendment(ctx: Context<CancelAmendment>) -> Result<()> {
+        let signer = ctx.accounts.signer.key();
+        let contract = &ctx.accounts.investment_contract;
+        let is_party = signer == contract.investor || signer == contract.startup;
+        let is_expired = Clock::get()?.unix_timestamp >= ctx.accounts.amendment.expires_at;
+
+        require!(is_party || is_expired, MerakiError::Unauthorized);
+        Ok(())
+    }
+
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        compliance_authority: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.compliance_authority = compliance_authority;
+        config.operations_wallet = ctx.accounts.admin.key();
+        config.reserve_wallet = ctx.accounts.admin.key();
+        config.reserve_bps = 0;
+        config.arbiter = ctx.accounts.admin.key();
+        config.bump = ctx.bumps.config;
+        Ok(())
+    }
+
+    pub fn set_fee_split(
+        ctx: Context<UpdateConfig>,
+        operations_wallet: Pubkey,
+        reserve_wallet: Pubkey,
+        reserve_bps: u16,
+    ) -> Result<()> {
+        require!(reserve_bps <= 10_000, MerakiError::InvalidBps);
+
+        let config = &mut ctx.accounts.config;
+        config.operations_wallet = operations_wallet;
+        config.reserve_wallet = reserve_wallet;
+        config.reserve_bps = reserve_bps;
+        Ok(())
+    }
+
+    pub fn initialize_treasury(_ctx: Context<InitializeTreasury>) -> Result<()> {
+        Ok(())
+    }
+
+    // The native treasury holds bare lamports, so like contract vaults it is
+    // seeded with the rent exempt minimum before it can receive fees.
+    pub fn initialize_native_treasury(ctx: Context<InitializeNativeTreasury>) -> Result<()> {
+        let rent_exempt_minimum = Rent::get()?.minimum_balance(0);
+        let top_up = rent_exempt_minimum.saturating_sub(ctx.accounts.treasury.lamports());
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.admin.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn withdraw_fees(ctx: Context<WithdrawFees>, amount: u64) -> Result<()> {
+        require!(amount > 0, MerakiError::InvalidAmount);
+        require!(
+            amount <= ctx.accounts.treasury.amount,
+            MerakiError::InsufficientTreasury
+        );
+
+        let (operations_amount, reserve_amount) =
+            fee_withdrawal_split(amount, ctx.accounts.config.reserve_bps)?;
+
+        let signer_seeds: &[&[&[u8]]] = &[&[b"config", &[ctx.accounts.config.bump]]];
+        token::transfer(
+            ctx.accounts
+                .transfer_to_operations_ctx()
+                .with_signer(signer_seeds),
+            operations_amount,
+        )?;
+        if reserve_amount > 0 {
+            token::transfer(
+                ctx.accounts
+                    .transfer_to_reserve_ctx()
+                    .with_signer(signer_seeds),
+                reserve_amount,
+            )?;
+        }
+
+        emit!(FeesWithdrawn {
+            mint: ctx.accounts.mint.key(),
+            admin: ctx.accounts.admin.key(),
+            amount,
+            operations_amount,
+            reserve_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_native_fees(ctx: Context<WithdrawNativeFees>, amount: u64) -> Result<()> {
+        require!(amount > 0, MerakiError::InvalidAmount);
+
+        let available = ctx
+            .accounts
+            .treasury
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0));
+        require!(amount <= available, MerakiError::InsufficientTreasury);
+
+        let (operations_amount, reserve_amount) =
+            fee_withdrawal_split(amount, ctx.accounts.config.reserve_bps)?;
+
+        let native_mint = Pubkey::default();
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[b"treasury", native_mint.as_ref(), &[ctx.bumps.treasury]]];
+        system_program::transfer(
+            ctx.accounts
+                .transfer_to_operations_ctx()
+                .with_signer(signer_seeds),
+            operations_amount,
+        )?;
+        if reserve_amount > 0 {
+            system_program::transfer(
+                ctx.accounts
+                    .transfer_to_reserve_ctx()
+                    .with_signer(signer_seeds),
+                reserve_amount,
+            )?;
+        }
+
+        emit!(FeesWithdrawn {
+            mint: native_mint,
+            admin: ctx.accounts.admin.key(),
+            amount,
+            operations_amount,
+            reserve_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_compliance_authority(
+        ctx: Context<UpdateConfig>,
+        compliance_authority: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts.config.compliance_authority = compliance_authority;
+        Ok(())
+    }
+
+    // `scope` is either a contract address or `Pubkey::default()` for the
+    // platform-wide allowlist. Re-issuing an attestation renews it.
+    pub fn issue_attestation(
+        ctx: Context<IssueAttestation>,
+        scope: Pubkey,
+        investor: Pubkey,
+        expires_at: i64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(expires_at > now, MerakiError::InvalidExpiry);
+
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.scope = scope;
+        attestation.investor = investor;
+        attestation.issuer = ctx.accounts.compliance_authority.key();
+        attestation.issued_at = now;
+        attestation.expires_at = expires_at;
+        attestation.bump = ctx.bumps.attestation;
+        Ok(())
+    }
+
+    // View instructions: they don't touch any state and hand their result back
+    // through return data, so clients can simulate them and other programs can
+    // CPI into them.
+
+    pub fn remaining_cap(ctx: Context<ViewContract>) -> Result<u64> {
+        let contract = &ctx.accounts.investment_contract;
+        Ok(meraki_core::remaining_cap(contract.amount, contract.total_raised))
+    }
+
+    // How a revenue report of `revenue_amount` made now would be split.
+    pub fn preview_revenue(
+        ctx: Context<ViewContract>,
+        revenue_amount: u64,
+    ) -> Result<RevenueSplitView> {
+        let contract = &ctx.accounts.investment_contract;
+        let rate_bps = contract.current_return_bps(Clock::get()?.unix_timestamp);
+        let (fee, investor_share, startup_share) = revenue_split(revenue_amount, rate_bps)?;
+
+        Ok(RevenueSplitView {
+            rate_bps,
+            fee,
+            investor_share,
+            startup_share,
+        })
+    }
+
+    pub fn position_view(ctx: Context<ViewPosition>) -> Result<PositionView> {
+        let contract = &ctx.accounts.investment_contract;
+        let position = &ctx.accounts.position;
+
+        // Collateral is only claimable once slashing is possible.
+        let collateral_claim = if contract.is_defaulted && !position.is_refunded {
+            position_slice(contract.collateral_amount, position, contract)?
+        } else {
+            0
+        };
+        // A refunded position is out of `total_raised` and owns nothing.
+        let ownership_bps = if position.is_refunded {
+            0
+        } else {
+            u16::try_from(position_slice(10_000, position, contract)?)
+                .map_err(|_| MerakiError::Overflow)?
+        };
+
+        Ok(PositionView {
+            contributed: position.contributed,
+            shares: position.shares,
+            ownership_bps,
+            collateral_claim,
+            is_refunded: position.is_refunded,
+        })
+    }
+
+    // What the position would receive from a revenue report of
+    // `revenue_amount` made now. Batch distributions round down per position
+    // and hand the dust to the first one, so the actual payout can be
+    // slightly higher.
+    pub fn position_payout(ctx: Context<ViewPosition>, revenue_amount: u64) -> Result<u64> {
+        let contract = &ctx.accounts.investment_contract;
+        let position = &ctx.accounts.position;
+        if position.is_refunded {
+            return Ok(0);
+        }
+
+        let rate_bps = contract.current_return_bps(Clock::get()?.unix_timestamp);
+        let (_, investor_share, _) = revenue_split(revenue_amount, rate_bps)?;
+        position_slice(investor_share, position, contract)
+    }
+
+    pub fn revoke_attestation(_ctx: Context<RevokeAttestation>) -> Result<()> {
         Ok(())
     }
 }
@@ -87,50 +1119,199 @@ pub struct InitializeContract<'info> {
     #[account(mut)]
     pub investor: Signer<'info>,
 
-    
+    /// CHECK: only has to sign when posting collateral.
+    #[account(mut)]
     pub startup: AccountInfo<'info>,
 
+    pub mint: Option<Account<'info, Mint>>,
+
+    #[account(mut, seeds = [b"collateral", investment_contract.key().as_ref()], bump)]
+    pub collateral: SystemAccount<'info>,
+
+    /// CHECK: PDA that signs for the contract's vault and share mint.
+    #[account(mut, seeds = [b"vault", investment_contract.key().as_ref()], bump)]
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = investor,
+        seeds = [b"share_mint", investment_contract.key().as_ref()],
+        bump,
+        mint::decimals = SHARE_DECIMALS,
+        mint::authority = vault,
+    )]
+    pub share_mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct Invest<'info> {
-    #[account(mut)]
+pub struct InitializeTokenVault<'info> {
+    #[account(
+        has_one = investor,
+        has_one = mint,
+        constraint = !investment_contract.is_native @ MerakiError::WrongDenomination
+    )]
     pub investment_contract: Account<'info, InvestmentContract>,
 
     #[account(mut)]
     pub investor: Signer<'info>,
 
-    
-    #[account(mut)]
-    pub vault_account: AccountInfo<'info>,
+    pub mint: Account<'info, Mint>,
 
-   
-    #[account(mut)]
-    pub meraki_token_account: AccountInfo<'info>,
+    /// CHECK: PDA that owns the token vault.
+    #[account(seeds = [b"vault", investment_contract.key().as_ref()], bump)]
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = investor,
+        seeds = [b"token_vault", investment_contract.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault,
+    )]
+    pub token_vault: Account<'info, TokenAccount>,
 
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct RecordRevenue<'info> {
+pub struct Invest<'info> {
     #[account(mut)]
     pub investment_contract: Account<'info, InvestmentContract>,
 
     #[account(mut)]
-    pub payer: Signer<'info>,
+    pub investor: Signer<'info>,
 
-    
-    #[account(mut)]
-    pub startup_token_account: AccountInfo<'info>,
+    #[account(
+        mut,
+        token::mint = investment_contract.mint,
+        token::authority = investor,
+    )]
+    pub investor_token_account: Account<'info, TokenAccount>,
 
-    
-    #[account(mut)]
-    pub investor_token_account: AccountInfo<'info>,
+    pub attestation: Option<Account<'info, Attestation>>,
 
-    
-    #[account(mut)]
-    pub meraki_token_account: AccountInfo<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = investor,
+        space = 8 + Position::LEN,
+        seeds = [b"position", investment_contract.key().as_ref(), investor.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(
+        mut,
+        seeds = [b"token_vault", investment_contract.key().as_ref()],
+        bump
+    )]
+    pub token_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"treasury", investment_contract.mint.as_ref()], bump)]
+    pub treasury: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA that signs for the share mint.
+    #[account(seeds = [b"vault", investment_contract.key().as_ref()], bump)]
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"share_mint", investment_contract.key().as_ref()],
+        bump
+    )]
+    pub share_mint: Account<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = investor,
+        associated_token::mint = share_mint,
+        associated_token::authority = investor,
+    )]
+    pub investor_share_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecordRevenue<'info> {
+    #[account(mut)]
+    pub investment_contract: Account<'info, InvestmentContract>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    // Required unless the payer is the startup.
+    pub delegate: Option<Account<'info, Delegate>>,
+
+    #[account(
+        seeds = [b"position", investment_contract.key().as_ref(), investment_contract.investor.as_ref()],
+        bump = position.bump,
+        constraint = !position.is_refunded @ MerakiError::AlreadyRefunded,
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(
+        mut,
+        token::mint = investment_contract.mint,
+        token::authority = payer,
+    )]
+    pub payer_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = investment_contract.mint,
+        token::authority = investment_contract.startup,
+    )]
+    pub startup_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = investment_contract.mint,
+        token::authority = investment_contract.investor,
+    )]
+    pub investor_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"treasury", investment_contract.mint.as_ref()], bump)]
+    pub treasury: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RecordRevenueBatch<'info> {
+    #[account(mut)]
+    pub investment_contract: Account<'info, InvestmentContract>,
+
+    pub payer: Signer<'info>,
+
+    // Required unless the payer is the startup.
+    pub delegate: Option<Account<'info, Delegate>>,
+
+    #[account(
+        mut,
+        token::mint = investment_contract.mint,
+        token::authority = payer,
+    )]
+    pub payer_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = investment_contract.mint,
+        token::authority = investment_contract.startup,
+    )]
+    pub startup_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"treasury", investment_contract.mint.as_ref()], bump)]
+    pub treasury: Account<'info, TokenAccount>,
 
     pub token_program: Program<'info, Token>,
 }
@@ -151,13 +1332,547 @@ pub struct MintNFT<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct InvestNative<'info> {
+    #[account(mut)]
+    pub investment_contract: Account<'info, InvestmentContract>,
+
+    #[account(mut)]
+    pub investor: Signer<'info>,
+
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = investor,
+        space = 8 + Position::LEN,
+        seeds = [b"position", investment_contract.key().as_ref(), investor.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", investment_contract.key().as_ref()],
+        bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    #[account(mut, seeds = [b"treasury", investment_contract.mint.as_ref()], bump)]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"share_mint", investment_contract.key().as_ref()],
+        bump
+    )]
+    pub share_mint: Account<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = investor,
+        associated_token::mint = share_mint,
+        associated_token::authority = investor,
+    )]
+    pub investor_share_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecordRevenueNative<'info> {
+    #[account(mut)]
+    pub investment_contract: Account<'info, InvestmentContract>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    // Required unless the payer is the startup.
+    pub delegate: Option<Account<'info, Delegate>>,
+
+    #[account(mut, address = investment_contract.startup)]
+    pub startup: SystemAccount<'info>,
+
+    #[account(mut, seeds = [b"treasury", investment_contract.mint.as_ref()], bump)]
+    pub treasury: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefundNative<'info> {
+    #[account(mut, has_one = startup)]
+    pub investment_contract: Account<'info, InvestmentContract>,
+
+    pub startup: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"position", investment_contract.key().as_ref(), investor.key().as_ref()],
+        bump = position.bump,
+        has_one = investor
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(mut)]
+    pub investor: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", investment_contract.key().as_ref()],
+        bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTranche<'info> {
+    #[account(mut)]
+    pub investment_contract: Account<'info, InvestmentContract>,
+
+    pub authority: Signer<'info>,
+
+    // Required unless the authority is the startup.
+    pub delegate: Option<Account<'info, Delegate>>,
+
+    /// CHECK: PDA that owns the token vault.
+    #[account(seeds = [b"vault", investment_contract.key().as_ref()], bump)]
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"token_vault", investment_contract.key().as_ref()],
+        bump
+    )]
+    pub token_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = investment_contract.mint,
+        token::authority = investment_contract.startup,
+    )]
+    pub startup_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTrancheNative<'info> {
+    #[account(mut)]
+    pub investment_contract: Account<'info, InvestmentContract>,
+
+    pub authority: Signer<'info>,
+
+    // Required unless the authority is the startup.
+    pub delegate: Option<Account<'info, Delegate>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", investment_contract.key().as_ref()],
+        bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    #[account(mut, address = investment_contract.startup)]
+    pub startup: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(authority: Pubkey)]
+pub struct AddDelegate<'info> {
+    #[account(has_one = startup)]
+    pub investment_contract: Account<'info, InvestmentContract>,
+
+    #[account(mut)]
+    pub startup: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = startup,
+        space = 8 + Delegate::LEN,
+        seeds = [b"delegate", investment_contract.key().as_ref(), authority.as_ref()],
+        bump
+    )]
+    pub delegate: Account<'info, Delegate>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeDelegate<'info> {
+    #[account(has_one = startup)]
+    pub investment_contract: Account<'info, InvestmentContract>,
+
+    #[account(mut)]
+    pub startup: Signer<'info>,
+
+    #[account(
+        mut,
+        close = startup,
+        seeds = [b"delegate", investment_contract.key().as_ref(), delegate.authority.as_ref()],
+        bump = delegate.bump
+    )]
+    pub delegate: Account<'info, Delegate>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Config::LEN,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"treasury", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = config,
+    )]
+    pub treasury: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeNativeTreasury<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(mut, seeds = [b"treasury", Pubkey::default().as_ref()], bump)]
+    pub treasury: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+
+    pub admin: Signer<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut, seeds = [b"treasury", mint.key().as_ref()], bump)]
+    pub treasury: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = config.operations_wallet,
+    )]
+    pub operations_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = config.reserve_wallet,
+    )]
+    pub reserve_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawNativeFees<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+
+    pub admin: Signer<'info>,
+
+    #[account(mut, seeds = [b"treasury", Pubkey::default().as_ref()], bump)]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(mut, address = config.operations_wallet)]
+    pub operations_wallet: SystemAccount<'info>,
+
+    #[account(mut, address = config.reserve_wallet)]
+    pub reserve_wallet: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(scope: Pubkey, investor: Pubkey)]
+pub struct IssueAttestation<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = compliance_authority)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = compliance_authority,
+        space = 8 + Attestation::LEN,
+        seeds = [b"attestation", scope.as_ref(), investor.as_ref()],
+        bump
+    )]
+    pub attestation: Account<'info, Attestation>,
+
+    #[account(mut)]
+    pub compliance_authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAttestation<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = compliance_authority)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"attestation", attestation.scope.as_ref(), attestation.investor.as_ref()],
+        bump = attestation.bump,
+        close = compliance_authority
+    )]
+    pub attestation: Account<'info, Attestation>,
+
+    #[account(mut)]
+    pub compliance_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ViewContract<'info> {
+    pub investment_contract: Account<'info, InvestmentContract>,
+}
+
+#[derive(Accounts)]
+pub struct ViewPosition<'info> {
+    pub investment_contract: Account<'info, InvestmentContract>,
+
+    #[account(
+        seeds = [b"position", investment_contract.key().as_ref(), position.investor.as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+}
+
+#[derive(Accounts)]
+pub struct MarkDefaulted<'info> {
+    #[account(mut)]
+    pub investment_contract: Account<'info, InvestmentContract>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleCollateral<'info> {
+    #[account(mut, has_one = startup)]
+    pub investment_contract: Account<'info, InvestmentContract>,
+
+    #[account(mut, seeds = [b"collateral", investment_contract.key().as_ref()], bump)]
+    pub collateral: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub startup: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReturnCollateral<'info> {
+    #[account(mut, has_one = startup)]
+    pub investment_contract: Account<'info, InvestmentContract>,
+
+    #[account(mut, seeds = [b"collateral", investment_contract.key().as_ref()], bump)]
+    pub collateral: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub startup: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefundDefaultedNative<'info> {
+    #[account(mut)]
+    pub investment_contract: Account<'info, InvestmentContract>,
+
+    #[account(
+        mut,
+        seeds = [b"position", investment_contract.key().as_ref(), investor.key().as_ref()],
+        bump = position.bump,
+        has_one = investor
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(mut)]
+    pub investor: SystemAccount<'info>,
+
+    #[account(mut, seeds = [b"vault", investment_contract.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefundDefaulted<'info> {
+    #[account(mut)]
+    pub investment_contract: Account<'info, InvestmentContract>,
+
+    #[account(
+        mut,
+        seeds = [b"position", investment_contract.key().as_ref(), position.investor.as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+
+    /// CHECK: PDA that owns the token vault.
+    #[account(seeds = [b"vault", investment_contract.key().as_ref()], bump)]
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"token_vault", investment_contract.key().as_ref()],
+        bump
+    )]
+    pub token_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = investment_contract.mint,
+        token::authority = position.investor,
+    )]
+    pub investor_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseContract<'info> {
+    #[account(mut, has_one = investor, close = investor)]
+    pub investment_contract: Account<'info, InvestmentContract>,
+
+    #[account(mut)]
+    pub investor: Signer<'info>,
+
+    #[account(mut, seeds = [b"vault", investment_contract.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+
+    // Only for SPL token contracts.
+    #[account(
+        mut,
+        seeds = [b"token_vault", investment_contract.key().as_ref()],
+        bump
+    )]
+    pub token_vault: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAmendment<'info> {
+    pub investment_contract: Account<'info, InvestmentContract>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Amendment::LEN,
+        seeds = [b"amendment", investment_contract.key().as_ref()],
+        bump
+    )]
+    pub amendment: Account<'info, Amendment>,
+
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAmendment<'info> {
+    #[account(mut)]
+    pub investment_contract: Account<'info, InvestmentContract>,
 
+    #[account(
+        mut,
+        seeds = [b"amendment", investment_contract.key().as_ref()],
+        bump = amendment.bump,
+        has_one = proposer,
+        close = proposer
+    )]
+    pub amendment: Account<'info, Amendment>,
+
+    pub signer: Signer<'info>,
+
+    /// CHECK: only receives the amendment rent; matched against `amendment.proposer`.
+    #[account(mut)]
+    pub proposer: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAmendment<'info> {
+    pub investment_contract: Account<'info, InvestmentContract>,
+
+    #[account(
+        mut,
+        seeds = [b"amendment", investment_contract.key().as_ref()],
+        bump = amendment.bump,
+        has_one = proposer,
+        close = proposer
+    )]
+    pub amendment: Account<'info, Amendment>,
+
+    pub signer: Signer<'info>,
+
+    /// CHECK: only receives the amendment rent; matched against `amendment.proposer`.
+    #[account(mut)]
+    pub proposer: AccountInfo<'info>,
+}
 
 impl<'info> Invest<'info> {
-    pub fn transfer_to_meraki_ctx(&self) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+    pub fn transfer_to_treasury_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
         let cpi_accounts = token::Transfer {
-            from: self.investor.to_account_info(),
-            to: self.meraki_token_account.to_account_info(),
+            from: self.investor_token_account.to_account_info(),
+            to: self.treasury.to_account_info(),
             authority: self.investor.to_account_info(),
         };
         CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
@@ -167,19 +1882,30 @@ impl<'info> Invest<'info> {
         &self,
     ) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
         let cpi_accounts = token::Transfer {
-            from: self.investor.to_account_info(),
-            to: self.vault_account.to_account_info(),
+            from: self.investor_token_account.to_account_info(),
+            to: self.token_vault.to_account_info(),
             authority: self.investor.to_account_info(),
         };
         CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
     }
+
+    pub fn mint_shares_ctx(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self.share_mint.to_account_info(),
+            to: self.investor_share_account.to_account_info(),
+            authority: self.vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
 }
 
 impl<'info> RecordRevenue<'info> {
-    pub fn transfer_to_meraki_ctx(&self) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+    pub fn transfer_to_treasury_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
         let cpi_accounts = token::Transfer {
-            from: self.payer.to_account_info(),
-            to: self.meraki_token_account.to_account_info(),
+            from: self.payer_token_account.to_account_info(),
+            to: self.treasury.to_account_info(),
             authority: self.payer.to_account_info(),
         };
         CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
@@ -187,7 +1913,7 @@ impl<'info> RecordRevenue<'info> {
 
     pub fn transfer_to_investor_ctx(&self) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
         let cpi_accounts = token::Transfer {
-            from: self.payer.to_account_info(),
+            from: self.payer_token_account.to_account_info(),
             to: self.investor_token_account.to_account_info(),
             authority: self.payer.to_account_info(),
         };
@@ -196,7 +1922,7 @@ impl<'info> RecordRevenue<'info> {
 
     pub fn transfer_to_startup_ctx(&self) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
         let cpi_accounts = token::Transfer {
-            from: self.payer.to_account_info(),
+            from: self.payer_token_account.to_account_info(),
             to: self.startup_token_account.to_account_info(),
             authority: self.payer.to_account_info(),
         };
@@ -204,21 +1930,879 @@ impl<'info> RecordRevenue<'info> {
     }
 }
 
+impl<'info> RecordRevenueBatch<'info> {
+    pub fn transfer_to_treasury_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+        self.transfer_from_payer_ctx(&self.treasury.to_account_info())
+    }
+
+    pub fn transfer_to_startup_ctx(&self) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+        self.transfer_from_payer_ctx(&self.startup_token_account.to_account_info())
+    }
+
+    pub fn transfer_from_payer_ctx(
+        &self,
+        to: &AccountInfo<'info>,
+    ) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+        let cpi_accounts = token::Transfer {
+            from: self.payer_token_account.to_account_info(),
+            to: to.clone(),
+            authority: self.payer.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+impl<'info> InvestNative<'info> {
+    pub fn transfer_to_treasury_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::Transfer<'info>> {
+        let cpi_accounts = system_program::Transfer {
+            from: self.investor.to_account_info(),
+            to: self.treasury.to_account_info(),
+        };
+        CpiContext::new(self.system_program.to_account_info(), cpi_accounts)
+    }
+
+    pub fn transfer_to_vault_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::Transfer<'info>> {
+        let cpi_accounts = system_program::Transfer {
+            from: self.investor.to_account_info(),
+            to: self.vault.to_account_info(),
+        };
+        CpiContext::new(self.system_program.to_account_info(), cpi_accounts)
+    }
+
+    pub fn mint_shares_ctx(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self.share_mint.to_account_info(),
+            to: self.investor_share_account.to_account_info(),
+            authority: self.vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+impl<'info> RecordRevenueNative<'info> {
+    pub fn transfer_to_treasury_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::Transfer<'info>> {
+        let cpi_accounts = system_program::Transfer {
+            from: self.payer.to_account_info(),
+            to: self.treasury.to_account_info(),
+        };
+        CpiContext::new(self.system_program.to_account_info(), cpi_accounts)
+    }
+
+    pub fn transfer_to_startup_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::Transfer<'info>> {
+        self.transfer_from_payer_ctx(&self.startup.to_account_info())
+    }
+
+    pub fn transfer_from_payer_ctx(
+        &self,
+        to: &AccountInfo<'info>,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::Transfer<'info>> {
+        let cpi_accounts = system_program::Transfer {
+            from: self.payer.to_account_info(),
+            to: to.clone(),
+        };
+        CpiContext::new(self.system_program.to_account_info(), cpi_accounts)
+    }
+}
+
+impl<'info> RefundNative<'info> {
+    pub fn refund_to_investor_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::Transfer<'info>> {
+        let cpi_accounts = system_program::Transfer {
+            from: self.vault.to_account_info(),
+            to: self.investor.to_account_info(),
+        };
+        CpiContext::new(self.system_program.to_account_info(), cpi_accounts)
+    }
+}
+
+impl<'info> RefundDefaultedNative<'info> {
+    pub fn refund_to_investor_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::Transfer<'info>> {
+        let cpi_accounts = system_program::Transfer {
+            from: self.vault.to_account_info(),
+            to: self.investor.to_account_info(),
+        };
+        CpiContext::new(self.system_program.to_account_info(), cpi_accounts)
+    }
+}
+
+impl<'info> RefundDefaulted<'info> {
+    pub fn refund_to_investor_ctx(&self) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+        let cpi_accounts = token::Transfer {
+            from: self.token_vault.to_account_info(),
+            to: self.investor_token_account.to_account_info(),
+            authority: self.vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+impl<'info> WithdrawTranche<'info> {
+    pub fn transfer_to_startup_ctx(&self) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+        let cpi_accounts = token::Transfer {
+            from: self.token_vault.to_account_info(),
+            to: self.startup_token_account.to_account_info(),
+            authority: self.vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+impl<'info> WithdrawTrancheNative<'info> {
+    pub fn transfer_to_startup_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::Transfer<'info>> {
+        let cpi_accounts = system_program::Transfer {
+            from: self.vault.to_account_info(),
+            to: self.startup.to_account_info(),
+        };
+        CpiContext::new(self.system_program.to_account_info(), cpi_accounts)
+    }
+}
+
+impl<'info> SettleCollateral<'info> {
+    pub fn transfer_from_collateral_ctx(
+        &self,
+        to: &AccountInfo<'info>,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::Transfer<'info>> {
+        let cpi_accounts = system_program::Transfer {
+            from: self.collateral.to_account_info(),
+            to: to.clone(),
+        };
+        CpiContext::new(self.system_program.to_account_info(), cpi_accounts)
+    }
+}
+
+impl<'info> ReturnCollateral<'info> {
+    // Moves everything in the collateral account, rent included, back to the
+    // startup and clears the contract's collateral.
+    pub fn return_collateral(&mut self, bump: u8) -> Result<()> {
+        let collateral_balance = self.collateral.lamports();
+        if collateral_balance > 0 {
+            let contract_key = self.investment_contract.key();
+            let signer_seeds: &[&[&[u8]]] = &[&[b"collateral", contract_key.as_ref(), &[bump]]];
+            let cpi_accounts = system_program::Transfer {
+                from: self.collateral.to_account_info(),
+                to: self.startup.to_account_info(),
+            };
+            system_program::transfer(
+                CpiContext::new(self.system_program.to_account_info(), cpi_accounts)
+                    .with_signer(signer_seeds),
+                collateral_balance,
+            )?;
+        }
+        self.investment_contract.collateral_amount = 0;
+        Ok(())
+    }
+}
+
+impl<'info> CloseContract<'info> {
+    pub fn drain_vault_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::Transfer<'info>> {
+        let cpi_accounts = system_program::Transfer {
+            from: self.vault.to_account_info(),
+            to: self.investor.to_account_info(),
+        };
+        CpiContext::new(self.system_program.to_account_info(), cpi_accounts)
+    }
+
+    pub fn close_token_vault_ctx(
+        &self,
+        token_vault: &Account<'info, TokenAccount>,
+    ) -> CpiContext<'_, '_, '_, 'info, token::CloseAccount<'info>> {
+        let cpi_accounts = token::CloseAccount {
+            account: token_vault.to_account_info(),
+            destination: self.investor.to_account_info(),
+            authority: self.vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+impl<'info> WithdrawFees<'info> {
+    pub fn transfer_to_operations_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+        let cpi_accounts = token::Transfer {
+            from: self.treasury.to_account_info(),
+            to: self.operations_token_account.to_account_info(),
+            authority: self.config.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+
+    pub fn transfer_to_reserve_ctx(&self) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+        let cpi_accounts = token::Transfer {
+            from: self.treasury.to_account_info(),
+            to: self.reserve_token_account.to_account_info(),
+            authority: self.config.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+impl<'info> WithdrawNativeFees<'info> {
+    pub fn transfer_to_operations_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::Transfer<'info>> {
+        let cpi_accounts = system_program::Transfer {
+            from: self.treasury.to_account_info(),
+            to: self.operations_wallet.to_account_info(),
+        };
+        CpiContext::new(self.system_program.to_account_info(), cpi_accounts)
+    }
+
+    pub fn transfer_to_reserve_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::Transfer<'info>> {
+        let cpi_accounts = system_program::Transfer {
+            from: self.treasury.to_account_info(),
+            to: self.reserve_wallet.to_account_info(),
+        };
+        CpiContext::new(self.system_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// The split math lives in `meraki_core` so the API quotes exactly what the
+// program charges; these wrappers only map its errors to `MerakiError`.
+fn revenue_split(revenue_amount: u64, investor_return_bps: u16) -> Result<(u64, u64, u64)> {
+    let split = meraki_core::revenue_split(revenue_amount, investor_return_bps)
+        .map_err(MerakiError::from)?;
+    Ok((split.fee, split.investor_share, split.startup_share))
+}
+
+// Reads the `(position, recipient)` pairs handed to batch instructions through
+// `remaining_accounts`. Every position must be a genuine, unrefunded position
+// PDA of `contract`, listed in ascending investor order (which rules out
+// duplicates), and together they must account for everything raised so the
+// whole investor share is distributed in one go. Recipients are returned
+// unchecked for the caller to validate.
+fn load_positions<'info>(
+    contract: &Account<'info, InvestmentContract>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+) -> Result<Vec<(Account<'info, Position>, &'info AccountInfo<'info>)>> {
+    let pairs = remaining_accounts.chunks_exact(2);
+    require!(
+        !remaining_accounts.is_empty() && pairs.remainder().is_empty(),
+        MerakiError::InvalidPositionList
+    );
+
+    let contract_key = contract.key();
+    let mut positions = Vec::with_capacity(remaining_accounts.len() / 2);
+    let mut previous_investor: Option<Pubkey> = None;
+    let mut covered: u64 = 0;
+
+    for pair in pairs {
+        let position = Account::<Position>::try_from(&pair[0])?;
+        let expected = Pubkey::create_program_address(
+            &[
+                b"position",
+                contract_key.as_ref(),
+                position.investor.as_ref(),
+                &[position.bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| error!(MerakiError::InvalidPositionList))?;
+        require_keys_eq!(pair[0].key(), expected, MerakiError::InvalidPositionList);
+        require_keys_eq!(
+            position.contract,
+            contract_key,
+            MerakiError::InvalidPositionList
+        );
+        require!(!position.is_refunded, MerakiError::AlreadyRefunded);
+        require!(pair[1].is_writable, MerakiError::InvalidRecipient);
+
+        if let Some(previous) = previous_investor {
+            require!(
+                previous.to_bytes() < position.investor.to_bytes(),
+                MerakiError::InvalidPositionList
+            );
+        }
+        previous_investor = Some(position.investor);
+        covered = covered
+            .checked_add(position.contributed)
+            .ok_or(MerakiError::Overflow)?;
+
+        positions.push((position, &pair[1]));
+    }
+
+    require!(
+        covered == contract.total_raised,
+        MerakiError::IncompletePositionList
+    );
+    Ok(positions)
+}
+
+// Splits `total` pro rata to `weights`; see `meraki_core::pro_rata`. The
+// rounding remainder goes to the first entry (the lowest investor key).
+fn pro_rata(total: u64, weights: &[u64], weight_sum: u64) -> Result<Vec<u64>> {
+    let slices = meraki_core::pro_rata(total, weights, weight_sum).map_err(MerakiError::from)?;
+    Ok(slices)
+}
+
+// A position's floored share of `total`, weighted by contribution.
+fn position_slice(total: u64, position: &Position, contract: &InvestmentContract) -> Result<u64> {
+    let slice = meraki_core::share_of(total, position.contributed, contract.total_raised)
+        .map_err(MerakiError::from)?;
+    Ok(slice)
+}
+
+fn check_defaulted_refund(contract: &InvestmentContract, position: &Position) -> Result<()> {
+    require!(contract.is_defaulted, MerakiError::NotDefaulted);
+    require!(
+        contract.collateral_amount == 0,
+        MerakiError::CollateralOutstanding
+    );
+    require!(!position.is_refunded, MerakiError::AlreadyRefunded);
+    Ok(())
+}
+
+// Takes a refunded position out of `total_raised`, so the next position's
+// slice is taken from what is left in the vault against what is left owed.
+fn settle_defaulted_refund(
+    contract: &mut Account<InvestmentContract>,
+    position: &mut Account<Position>,
+    amount: u64,
+) -> Result<()> {
+    position.is_refunded = true;
+    contract.total_raised = contract
+        .total_raised
+        .checked_sub(position.contributed)
+        .ok_or(MerakiError::Overflow)?;
+
+    emit!(DefaultRefunded {
+        contract: contract.key(),
+        investor: position.investor,
+        amount,
+    });
+
+    Ok(())
+}
+
+// Portion of a fee withdrawal that goes to the reserve wallet; the rest goes
+// to operations.
+fn fee_withdrawal_split(amount: u64, reserve_bps: u16) -> Result<(u64, u64)> {
+    let reserve_amount =
+        meraki_core::bps_of(amount, reserve_bps.into()).map_err(MerakiError::from)?;
+    Ok((amount - reserve_amount, reserve_amount))
+}
+
+// Contracts with an allowlist only accept investors holding an unexpired
+// attestation for the contract itself or for the global scope, issued by the
+// current compliance authority.
+fn check_attestation(
+    contract: &Account<InvestmentContract>,
+    config: &Config,
+    investor: Pubkey,
+    attestation: Option<&Account<Attestation>>,
+) -> Result<()> {
+    let scope = match contract.allowlist {
+        AllowlistMode::Open => return Ok(()),
+        AllowlistMode::Global => Pubkey::default(),
+        AllowlistMode::Contract => contract.key(),
+    };
+
+    let attestation = attestation.ok_or(MerakiError::MissingAttestation)?;
+    let (expected, _) = Pubkey::find_program_address(
+        &[b"attestation", scope.as_ref(), investor.as_ref()],
+        &crate::ID,
+    );
+    require_keys_eq!(attestation.key(), expected, MerakiError::InvalidAttestation);
+    require_keys_eq!(
+        attestation.issuer,
+        config.compliance_authority,
+        MerakiError::UnknownAttestationIssuer
+    );
+    require!(
+        attestation.expires_at > Clock::get()?.unix_timestamp,
+        MerakiError::AttestationExpired
+    );
+
+    Ok(())
+}
+
+// Startup-side instructions accept the startup itself, or one of its
+// delegates on this contract holding `permission`.
+fn check_startup_authority(
+    contract: &Account<InvestmentContract>,
+    authority: Pubkey,
+    delegate: Option<&Account<Delegate>>,
+    permission: u8,
+) -> Result<()> {
+    if authority == contract.startup {
+        return Ok(());
+    }
+
+    let delegate = delegate.ok_or(MerakiError::Unauthorized)?;
+    let (expected, _) = Pubkey::find_program_address(
+        &[b"delegate", contract.key().as_ref(), authority.as_ref()],
+        &crate::ID,
+    );
+    require_keys_eq!(delegate.key(), expected, MerakiError::Unauthorized);
+    require!(
+        delegate.permissions & permission == permission,
+        MerakiError::MissingPermission
+    );
+
+    Ok(())
+}
+
+// Checks a tranche withdrawal of `amount` against what has vested so far,
+// given what the vault still holds beyond rent, and returns the contract's
+// new `total_withdrawn`.
+fn check_tranche(contract: &InvestmentContract, available: u64, amount: u64) -> Result<u64> {
+    require!(amount > 0, MerakiError::InvalidAmount);
+    require!(
+        contract.is_active || contract.is_completed,
+        MerakiError::ContractInactive
+    );
+
+    let total_vaulted = available
+        .checked_add(contract.total_withdrawn)
+        .ok_or(MerakiError::Overflow)?;
+    let vested = meraki_core::vested_amount(
+        total_vaulted,
+        contract.start_time,
+        0,
+        contract.duration_seconds()?,
+        Clock::get()?.unix_timestamp,
+    );
+    let total_withdrawn = contract
+        .total_withdrawn
+        .checked_add(amount)
+        .ok_or(MerakiError::Overflow)?;
+    require!(total_withdrawn <= vested, MerakiError::TrancheNotVested);
+
+    Ok(total_withdrawn)
+}
+
+// Books a contribution against the contract cap and the investor's position
+// and returns the number of share tokens to mint for it.
+fn record_contribution(
+    contract: &mut InvestmentContract,
+    position: &mut Position,
+    amount: u64,
+) -> Result<u64> {
+    require!(contract.is_active, MerakiError::ContractInactive);
+    require!(amount > 0, MerakiError::InvalidAmount);
+
+    let total_raised =
+        meraki_core::add_contribution(contract.total_raised, amount, contract.amount)
+            .map_err(MerakiError::from)?;
+    let shares =
+        meraki_core::shares_for_contribution(amount, contract.amount, contract.equity_offered_bps)
+            .map_err(MerakiError::from)?;
+
+    contract.total_raised = total_raised;
+    contract.total_shares = contract
+        .total_shares
+        .checked_add(shares)
+        .ok_or(MerakiError::Overflow)?;
+    let filled = total_raised == contract.amount;
+    if filled && !contract.is_invested {
+        // Reports are due from the day there is money to report on, not
+        // from `start_time`, which may be long past by the time it fills.
+        contract.last_report_at = Clock::get()?.unix_timestamp;
+    }
+    contract.is_invested = filled;
+
+    position.contributed = position
+        .contributed
+        .checked_add(amount)
+        .ok_or(MerakiError::Overflow)?;
+    position.shares = position
+        .shares
+        .checked_add(shares)
+        .ok_or(MerakiError::Overflow)?;
+
+    Ok(shares)
+}
 
 #[account]
 pub struct InvestmentContract {
     pub investor: Pubkey,
     pub startup: Pubkey,
-    pub amount: u6,
-    pub investor_return_percent: u8,
-    pub duration_days: u6,
-    pub start_time: i6,
-    pub total_revenue: u6,
-    pub total_distributed: u6,
+    pub amount: u64,
+    pub investor_return_bps: u16,
+    pub duration_days: u64,
+    pub start_time: i64,
+    pub total_revenue: u64,
+    pub total_distributed: u64,
     pub is_active: bool,
     pub is_invested: bool,
+    pub amendment_count: u32,
+    pub is_native: bool,
+    pub mint: Pubkey,
+    pub is_refunded: bool,
+    pub equity_offered_bps: u16,
+    pub total_raised: u64,
+    pub total_shares: u64,
+    pub allowlist: AllowlistMode,
+    pub return_tiers: Vec<ReturnTier>,
+    pub collateral_amount: u64,
+    pub is_defaulted: bool,
+    pub is_completed: bool,
+    pub last_report_at: i64,
+    // SHA-256 of the signed terms document, and where its metadata lives.
+    pub terms_hash: [u8; 32],
+    pub metadata_uri: String,
+    // Vaulted funds released to the startup so far.
+    pub total_withdrawn: u64,
 }
 
 impl InvestmentContract {
-    pub const LEN: usize =  +  + 8 +  + 8 + 8 + 8 + 8 +  + ;
+    pub const LEN: usize = 32
+        + 32
+        + 8
+        + 2
+        + 8
+        + 8
+        + 8
+        + 8
+        + 1
+        + 1
+        + 4
+        + 1
+        + 32
+        + 1
+        + 2
+        + 8
+        + 8
+        + 1
+        + 4
+        + MAX_RETURN_TIERS * ReturnTier::LEN
+        + 8
+        + 1
+        + 1
+        + 8
+        + 32
+        + 4
+        + MAX_METADATA_URI_LEN
+        + 8;
+
+    // An invested contract that has gone `REPORTING_GRACE_PERIOD` without a
+    // revenue report.
+    pub fn missed_reporting(&self, now: i64) -> bool {
+        self.is_invested
+            && now
+                > self
+                    .last_report_at
+                    .saturating_add(REPORTING_GRACE_PERIOD)
+    }
+
+    pub fn duration_seconds(&self) -> Result<i64> {
+        let duration = i64::try_from(self.duration_days)
+            .ok()
+            .and_then(|days| days.checked_mul(24 * 60 * 60))
+            .ok_or(MerakiError::Overflow)?;
+        Ok(duration)
+    }
+
+    // The investor share for a report made at `now`: the last tier whose
+    // conditions are met, or the base rate if none are.
+    pub fn current_return_bps(&self, now: i64) -> u16 {
+        meraki_core::current_return_bps(
+            self.investor_return_bps,
+            self.return_tiers.iter().map(meraki_core::Tier::from),
+            self.total_distributed,
+            self.total_raised,
+            now,
+        )
+    }
+}
+
+/// A step in a tiered revenue-share schedule. The tier applies once investors
+/// have been paid `returned_multiple_bps` of what they put in (10_000 = 1x)
+/// and `starts_at` has passed; use 0 for either to ignore that condition.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ReturnTier {
+    pub returned_multiple_bps: u32,
+    pub starts_at: i64,
+    pub rate_bps: u16,
+}
+
+impl ReturnTier {
+    pub const LEN: usize = 4 + 8 + 2;
+}
+
+impl From<&ReturnTier> for meraki_core::Tier {
+    fn from(tier: &ReturnTier) -> Self {
+        meraki_core::Tier {
+            returned_multiple_bps: tier.returned_multiple_bps,
+            starts_at: tier.starts_at,
+            rate_bps: tier.rate_bps,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AllowlistMode {
+    Open,
+    Global,
+    Contract,
+}
+
+/// Return value of `preview_revenue`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RevenueSplitView {
+    pub rate_bps: u16,
+    pub fee: u64,
+    pub investor_share: u64,
+    pub startup_share: u64,
+}
+
+/// Return value of `position_view`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PositionView {
+    pub contributed: u64,
+    pub shares: u64,
+    /// Share of the contract's raised amount held by this position.
+    pub ownership_bps: u16,
+    /// Lamports the position would get from `slash_collateral`.
+    pub collateral_claim: u64,
+    pub is_refunded: bool,
+}
+
+/// One investor's stake in a contract: what they put in and the share tokens
+/// minted to them for it.
+#[account]
+pub struct Position {
+    pub contract: Pubkey,
+    pub investor: Pubkey,
+    pub contributed: u64,
+    pub shares: u64,
+    pub is_refunded: bool,
+    pub bump: u8,
+}
+
+impl Position {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 1 + 1;
+}
+
+/// Pending change to the terms of an `InvestmentContract`. Only applied once
+/// both the investor and the startup have signed it, before `expires_at`.
+#[account]
+pub struct Amendment {
+    pub contract: Pubkey,
+    pub proposer: Pubkey,
+    pub investor_return_bps: Option<u16>,
+    pub duration_days: Option<u64>,
+    pub investor_signed: bool,
+    pub startup_signed: bool,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl Amendment {
+    pub const LEN: usize = 32 + 32 + 3 + 9 + 1 + 1 + 8 + 8 + 1;
+}
+
+/// Platform-wide settings, stored at the `["config"]` PDA.
+#[account]
+pub struct Config {
+    pub admin: Pubkey,
+    pub compliance_authority: Pubkey,
+    pub operations_wallet: Pubkey,
+    pub reserve_wallet: Pubkey,
+    pub reserve_bps: u16,
+    pub arbiter: Pubkey,
+    pub bump: u8,
+}
+
+impl Config {
+    pub const LEN: usize = 32 + 32 + 32 + 32 + 2 + 32 + 1;
+}
+
+/// Proof that `investor` passed KYC for `scope` (a contract, or
+/// `Pubkey::default()` for every allowlisted contract) until `expires_at`.
+#[account]
+pub struct Attestation {
+    pub scope: Pubkey,
+    pub investor: Pubkey,
+    pub issuer: Pubkey,
+    pub issued_at: i64,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl Attestation {
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 8 + 1;
+}
+
+/// Lets `authority` act for the startup of `contract`, limited to the
+/// `PERMISSION_*` bits in `permissions`. Stored at
+/// `["delegate", contract, authority]`.
+#[account]
+pub struct Delegate {
+    pub contract: Pubkey,
+    pub authority: Pubkey,
+    pub permissions: u8,
+    pub bump: u8,
+}
+
+impl Delegate {
+    pub const LEN: usize = 32 + 32 + 1 + 1;
+}
+
+#[event]
+pub struct FeesWithdrawn {
+    /// `Pubkey::default()` for the native SOL treasury.
+    pub mint: Pubkey,
+    pub admin: Pubkey,
+    pub amount: u64,
+    pub operations_amount: u64,
+    pub reserve_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ContractInitialized {
+    pub contract: Pubkey,
+    pub investor: Pubkey,
+    pub startup: Pubkey,
+    pub amount: u64,
+    pub terms_hash: [u8; 32],
+    pub metadata_uri: String,
+}
+
+#[event]
+pub struct ContractDefaulted {
+    pub contract: Pubkey,
+    pub marked_by: Pubkey,
+    pub by_arbiter: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CollateralSlashed {
+    pub contract: Pubkey,
+    pub amount: u64,
+    pub positions: u32,
+}
+
+#[event]
+pub struct TrancheWithdrawn {
+    pub contract: Pubkey,
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub total_withdrawn: u64,
+}
+
+#[event]
+pub struct DefaultRefunded {
+    pub contract: Pubkey,
+    pub investor: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ContractClosed {
+    pub contract: Pubkey,
+    pub investor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[error_code]
+pub enum MerakiError {
+    #[msg("Signer is not allowed to perform this action")]
+    Unauthorized,
+    #[msg("Contract is no longer active")]
+    ContractInactive,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Amendment does not change any term")]
+    EmptyAmendment,
+    #[msg("Investor return must be at most 9950 basis points")]
+    InvalidReturnBps,
+    #[msg("Too many return tiers")]
+    TooManyReturnTiers,
+    #[msg("Expiry must be in the future")]
+    InvalidExpiry,
+    #[msg("Amendment has expired")]
+    AmendmentExpired,
+    #[msg("Amendment must be signed by both investor and startup")]
+    AmendmentNotFullySigned,
+    #[msg("Token contracts require a mint account")]
+    MissingMint,
+    #[msg("Instruction does not match the contract denomination")]
+    WrongDenomination,
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Equity offered must be at most 10000 basis points")]
+    InvalidEquity,
+    #[msg("Investment would exceed the contract amount")]
+    CapExceeded,
+    #[msg("Position has already been refunded")]
+    AlreadyRefunded,
+    #[msg("This contract requires an investor attestation")]
+    MissingAttestation,
+    #[msg("Attestation does not belong to this investor or contract")]
+    InvalidAttestation,
+    #[msg("Attestation has expired")]
+    AttestationExpired,
+    #[msg("Basis points must be at most 10000")]
+    InvalidBps,
+    #[msg("Treasury balance is too low for this withdrawal")]
+    InsufficientTreasury,
+    #[msg("Positions must be valid position PDAs of this contract, paired with a recipient and ordered by investor")]
+    InvalidPositionList,
+    #[msg("Positions do not cover the full amount raised")]
+    IncompletePositionList,
+    #[msg("Recipient account does not belong to the position's investor")]
+    InvalidRecipient,
+    #[msg("Contract has not been marked as defaulted")]
+    NotDefaulted,
+    #[msg("Contract has no collateral to settle")]
+    NoCollateral,
+    #[msg("Contract duration has not ended yet")]
+    ContractNotEnded,
+    #[msg("Metadata URI is too long")]
+    MetadataUriTooLong,
+    #[msg("Contract has not finished yet")]
+    ContractNotFinished,
+    #[msg("Collateral must be settled first")]
+    CollateralOutstanding,
+    #[msg("Contract vault still holds funds")]
+    VaultNotEmpty,
+    #[msg("Token contracts require their token vault")]
+    MissingTokenVault,
+    #[msg("Permissions must be a non-empty set of known permission bits")]
+    InvalidPermissions,
+    #[msg("Delegate lacks the permission for this instruction")]
+    MissingPermission,
+    #[msg("Amount exceeds the vested funds not yet withdrawn")]
+    TrancheNotVested,
+    #[msg("Contract has several positions; use record_revenue_batch")]
+    MultiplePositions,
+    #[msg("Revenue reporting is overdue")]
+    ReportingOverdue,
+    #[msg("Collateral can only be released once no positions remain")]
+    PositionsOutstanding,
+    #[msg("Attestation was not issued by the current compliance authority")]
+    UnknownAttestationIssuer,
+}
+
+impl From<MathError> for MerakiError {
+    fn from(error: MathError) -> Self {
+        match error {
+            MathError::Overflow => MerakiError::Overflow,
+            MathError::CapExceeded => MerakiError::CapExceeded,
+            MathError::ZeroWeight => MerakiError::InvalidPositionList,
+        }
+    }
 }
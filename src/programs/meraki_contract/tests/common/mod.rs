@@ -0,0 +1,440 @@
+#![allow(dead_code)]
+
+use anchor_lang::prelude::{Clock, Pubkey};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::system_instruction;
+use anchor_lang::{AccountDeserialize, AnchorDeserialize};
+use anchor_spl::associated_token::{get_associated_token_address, spl_associated_token_account};
+use anchor_spl::token::{spl_token, TokenAccount};
+use meraki_contract::{AllowlistMode, PERMISSION_REPORT_REVENUE};
+pub use meraki_sdk::instructions::*;
+pub use meraki_sdk::pda;
+use solana_program_test::{BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+pub const SOL: u64 = 1_000_000_000;
+pub const DAY: i64 = 24 * 60 * 60;
+
+pub struct TestEnv {
+    pub ctx: ProgramTestContext,
+}
+
+impl TestEnv {
+    pub async fn new() -> Self {
+        // Anchor 0.32 performs CPIs through `solana-invoke`, which only works
+        // on-chain, so the suite runs the compiled program (`cargo test-sbf`).
+        let program_test = ProgramTest::new("meraki_contract", meraki_contract::ID, None);
+        Self {
+            ctx: program_test.start_with_context().await,
+        }
+    }
+
+    pub fn payer(&self) -> Keypair {
+        self.ctx.payer.insecure_clone()
+    }
+
+    pub async fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let tx = self.transaction(instructions, signers).await;
+        self.ctx.banks_client.process_transaction(tx).await
+    }
+
+    // Runs a view instruction and decodes what it put in return data.
+    pub async fn view<T: AnchorDeserialize>(&mut self, instruction: Instruction) -> T {
+        let payer = self.payer();
+        let tx = self.transaction(&[instruction], &[&payer]).await;
+        let simulation = self
+            .ctx
+            .banks_client
+            .simulate_transaction(tx)
+            .await
+            .unwrap();
+        simulation.result.unwrap().unwrap();
+        let return_data = simulation
+            .simulation_details
+            .and_then(|details| details.return_data)
+            .expect("view instruction should set return data");
+        assert_eq!(return_data.program_id, meraki_contract::ID);
+        T::try_from_slice(&return_data.data).unwrap()
+    }
+
+    async fn transaction(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Transaction {
+        let payer = self.payer();
+        let blockhash = self.ctx.banks_client.get_latest_blockhash().await.unwrap();
+        let mut all_signers = vec![&payer];
+        all_signers.extend(
+            signers
+                .iter()
+                .copied()
+                .filter(|s| s.pubkey() != payer.pubkey()),
+        );
+        Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &all_signers,
+            blockhash,
+        )
+    }
+
+    pub async fn funded_keypair(&mut self, lamports: u64) -> Keypair {
+        let keypair = Keypair::new();
+        self.transfer(&keypair.pubkey(), lamports).await;
+        keypair
+    }
+
+    pub async fn transfer(&mut self, to: &Pubkey, lamports: u64) {
+        let payer = self.payer();
+        self.send(
+            &[system_instruction::transfer(&payer.pubkey(), to, lamports)],
+            &[],
+        )
+        .await
+        .unwrap();
+    }
+
+    pub async fn lamports(&mut self, address: &Pubkey) -> u64 {
+        self.ctx.banks_client.get_balance(*address).await.unwrap()
+    }
+
+    pub async fn account<T: AccountDeserialize>(&mut self, address: &Pubkey) -> T {
+        let account = self
+            .ctx
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .expect("account should exist");
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    pub async fn exists(&mut self, address: &Pubkey) -> bool {
+        self.ctx
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .is_some()
+    }
+
+    pub async fn token_balance(&mut self, address: &Pubkey) -> u64 {
+        self.account::<TokenAccount>(address).await.amount
+    }
+
+    pub async fn now(&mut self) -> i64 {
+        self.clock().await.unix_timestamp
+    }
+
+    pub async fn warp_forward(&mut self, seconds: i64) {
+        let mut clock = self.clock().await;
+        clock.unix_timestamp += seconds;
+        self.ctx.set_sysvar(&clock);
+    }
+
+    async fn clock(&mut self) -> Clock {
+        self.ctx.banks_client.get_sysvar::<Clock>().await.unwrap()
+    }
+
+    pub async fn rent_exempt_minimum(&mut self, data_len: usize) -> u64 {
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        rent.minimum_balance(data_len)
+    }
+
+    pub async fn create_mint(&mut self, authority: &Pubkey) -> Pubkey {
+        let payer = self.payer();
+        let mint = Keypair::new();
+        let lamports = self.rent_exempt_minimum(spl_token::state::Mint::LEN).await;
+        self.send(
+            &[
+                system_instruction::create_account(
+                    &payer.pubkey(),
+                    &mint.pubkey(),
+                    lamports,
+                    spl_token::state::Mint::LEN as u64,
+                    &spl_token::ID,
+                ),
+                spl_token::instruction::initialize_mint2(
+                    &spl_token::ID,
+                    &mint.pubkey(),
+                    authority,
+                    None,
+                    6,
+                )
+                .unwrap(),
+            ],
+            &[&mint],
+        )
+        .await
+        .unwrap();
+        mint.pubkey()
+    }
+
+    // Creates `owner`'s associated token account for `mint`, minting
+    // `amount` into it when `mint_authority` is given.
+    pub async fn token_account(
+        &mut self,
+        mint: &Pubkey,
+        owner: &Pubkey,
+        mint_authority: Option<&Keypair>,
+        amount: u64,
+    ) -> Pubkey {
+        let payer = self.payer();
+        let address = get_associated_token_address(owner, mint);
+        let mut instructions = vec![
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &payer.pubkey(),
+                owner,
+                mint,
+                &spl_token::ID,
+            ),
+        ];
+        let mut signers = vec![];
+        if let Some(authority) = mint_authority {
+            instructions.push(
+                spl_token::instruction::mint_to(
+                    &spl_token::ID,
+                    mint,
+                    &address,
+                    &authority.pubkey(),
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+            );
+            signers.push(authority);
+        }
+        self.send(&instructions, &signers).await.unwrap();
+        address
+    }
+}
+
+// Asserts that a transaction failed with the given Anchor or program error.
+pub fn assert_error(result: Result<(), BanksClientError>, expected: impl Into<u32>) {
+    let expected = expected.into();
+    match result.expect_err("transaction should have failed").unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+            assert_eq!(code, expected, "expected error {expected}, got {code}")
+        }
+        other => panic!("expected error {expected}, got {other:?}"),
+    }
+}
+
+/// Arguments for a small open native contract, which tests override field by
+/// field.
+pub fn default_terms() -> ContractTerms {
+    ContractTerms {
+        amount: 10 * SOL,
+        investor_return_bps: 1_000,
+        duration_days: 365,
+        is_native: true,
+        equity_offered_bps: 1_000,
+        allowlist: AllowlistMode::Open,
+        return_tiers: vec![],
+        collateral_amount: 0,
+        terms_hash: [7; 32],
+        metadata_uri: "https://meraki.example/contracts/1.json".to_string(),
+    }
+}
+
+// A funded keypair the startup has made a revenue-reporting delegate.
+async fn add_reporter(
+    env: &mut TestEnv,
+    contract: &Pubkey,
+    startup: &Keypair,
+    lamports: u64,
+) -> Keypair {
+    let reporter = env.funded_keypair(lamports).await;
+    env.send(
+        &[add_delegate(
+            contract,
+            &startup.pubkey(),
+            &reporter.pubkey(),
+            PERMISSION_REPORT_REVENUE,
+        )],
+        &[startup],
+    )
+    .await
+    .unwrap();
+    reporter
+}
+
+/// A native contract with the platform config and SOL treasury in place,
+/// which is what most tests start from.
+pub struct NativeDeal {
+    pub env: TestEnv,
+    pub admin: Keypair,
+    pub investor: Keypair,
+    pub startup: Keypair,
+    pub contract: Pubkey,
+}
+
+impl NativeDeal {
+    pub async fn new(terms: ContractTerms) -> Self {
+        Self::with_env(TestEnv::new().await, terms).await
+    }
+
+    pub async fn with_env(mut env: TestEnv, terms: ContractTerms) -> Self {
+        let admin = env.payer();
+        let investor = env.funded_keypair(100 * SOL).await;
+        let startup = env.funded_keypair(100 * SOL).await;
+
+        env.send(
+            &[
+                initialize_config(&admin.pubkey(), &admin.pubkey()),
+                initialize_native_treasury(&admin.pubkey()),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let contract = Keypair::new();
+        let mut signers = vec![&contract, &investor];
+        if terms.collateral_amount > 0 {
+            signers.push(&startup);
+        }
+        env.send(
+            &[initialize_contract(
+                &contract.pubkey(),
+                &investor.pubkey(),
+                &startup.pubkey(),
+                None,
+                terms,
+            )],
+            &signers,
+        )
+        .await
+        .unwrap();
+
+        Self {
+            env,
+            admin,
+            investor,
+            startup,
+            contract: contract.pubkey(),
+        }
+    }
+
+    pub async fn reporter(&mut self, lamports: u64) -> Keypair {
+        add_reporter(&mut self.env, &self.contract, &self.startup, lamports).await
+    }
+
+    /// Invests `lamports` from `investor`, which must hold enough SOL.
+    pub async fn invest_from(&mut self, investor: &Keypair, lamports: u64) {
+        self.env
+            .send(
+                &[invest_native(
+                    &self.contract,
+                    &investor.pubkey(),
+                    None,
+                    lamports,
+                )],
+                &[investor],
+            )
+            .await
+            .unwrap();
+    }
+
+    pub async fn invest(&mut self, lamports: u64) {
+        let investor = self.investor.insecure_clone();
+        self.invest_from(&investor, lamports).await;
+    }
+}
+
+/// A token-denominated contract with its mint, treasury and a funded
+/// investor token account. The payer is admin and mint authority; invested
+/// tokens go to the contract's token vault.
+pub struct TokenDeal {
+    pub env: TestEnv,
+    pub admin: Keypair,
+    pub investor: Keypair,
+    pub startup: Keypair,
+    pub contract: Pubkey,
+    pub mint: Pubkey,
+    pub startup_token_account: Pubkey,
+    pub investor_token_account: Pubkey,
+}
+
+impl TokenDeal {
+    pub async fn new(terms: ContractTerms) -> Self {
+        let mut env = TestEnv::new().await;
+        let admin = env.payer();
+        let investor = env.funded_keypair(10 * SOL).await;
+        let startup = env.funded_keypair(10 * SOL).await;
+
+        let mint = env.create_mint(&admin.pubkey()).await;
+        env.send(
+            &[
+                initialize_config(&admin.pubkey(), &admin.pubkey()),
+                initialize_treasury(&admin.pubkey(), &mint),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        let investor_token_account = env
+            .token_account(&mint, &investor.pubkey(), Some(&admin), terms.amount)
+            .await;
+        let startup_token_account = env.token_account(&mint, &startup.pubkey(), None, 0).await;
+
+        let contract = Keypair::new();
+        env.send(
+            &[
+                initialize_contract(
+                    &contract.pubkey(),
+                    &investor.pubkey(),
+                    &startup.pubkey(),
+                    Some(mint),
+                    ContractTerms {
+                        is_native: false,
+                        ..terms
+                    },
+                ),
+                initialize_token_vault(&contract.pubkey(), &investor.pubkey(), &mint),
+            ],
+            &[&contract, &investor],
+        )
+        .await
+        .unwrap();
+
+        Self {
+            env,
+            admin,
+            investor,
+            startup,
+            contract: contract.pubkey(),
+            mint,
+            startup_token_account,
+            investor_token_account,
+        }
+    }
+
+    pub async fn reporter(&mut self, lamports: u64) -> Keypair {
+        add_reporter(&mut self.env, &self.contract, &self.startup, lamports).await
+    }
+
+    pub fn invest_accounts(&self, investor_token_account: Pubkey) -> TokenInvest {
+        TokenInvest {
+            mint: self.mint,
+            investor_token_account,
+            attestation: None,
+        }
+    }
+
+    pub fn revenue_accounts(&self, payer_token_account: Pubkey) -> TokenRevenue {
+        TokenRevenue {
+            mint: self.mint,
+            payer_token_account,
+            startup_token_account: self.startup_token_account,
+        }
+    }
+}
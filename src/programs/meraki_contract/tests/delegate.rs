@@ -0,0 +1,320 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::solana_program::instruction::Instruction;
+use common::*;
+use meraki_contract::{
+    Delegate, InvestmentContract, MerakiError, ALL_PERMISSIONS, PERMISSION_REPORT_REVENUE,
+    PERMISSION_WITHDRAW_TRANCHE,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+fn report(deal: &NativeDeal, payer: &Keypair, as_delegate: bool) -> Vec<Instruction> {
+    vec![record_revenue_native(
+        &deal.contract,
+        &payer.pubkey(),
+        &deal.startup.pubkey(),
+        &[deal.investor.pubkey()],
+        SOL,
+        as_delegate,
+    )]
+}
+
+// A 10 day contract with 10 SOL invested, so 9.95 SOL sits in the vault.
+async fn funded_deal() -> NativeDeal {
+    let mut deal = NativeDeal::new(ContractTerms {
+        duration_days: 10,
+        ..default_terms()
+    })
+    .await;
+    deal.env
+        .send(
+            &[invest_native(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                None,
+                10 * SOL,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+    deal
+}
+
+#[tokio::test]
+async fn record_revenue_rejects_strangers() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+    let stranger = deal.env.funded_keypair(10 * SOL).await;
+
+    let instructions = report(&deal, &stranger, false);
+    let result = deal.env.send(&instructions, &[&stranger]).await;
+    assert_error(result, MerakiError::Unauthorized);
+
+    // Someone else's delegate account doesn't count either.
+    let reporter = deal.reporter(SOL).await;
+    let mut instructions = report(&deal, &stranger, false);
+    instructions[0].accounts[2].pubkey = pda::delegate(&deal.contract, &reporter.pubkey());
+    let result = deal.env.send(&instructions, &[&stranger]).await;
+    assert_error(result, MerakiError::Unauthorized);
+}
+
+#[tokio::test]
+async fn record_revenue_accepts_the_startup_or_a_delegate() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+    deal.invest(SOL).await;
+    let startup = deal.startup.insecure_clone();
+
+    let instructions = report(&deal, &startup, false);
+    deal.env.send(&instructions, &[&startup]).await.unwrap();
+
+    let reporter = deal.reporter(10 * SOL).await;
+    let delegate: Delegate = deal
+        .env
+        .account(&pda::delegate(&deal.contract, &reporter.pubkey()))
+        .await;
+    assert_eq!(delegate.contract, deal.contract);
+    assert_eq!(delegate.permissions, PERMISSION_REPORT_REVENUE);
+
+    let instructions = report(&deal, &reporter, true);
+    deal.env.send(&instructions, &[&reporter]).await.unwrap();
+    let contract: InvestmentContract = deal.env.account(&deal.contract).await;
+    assert_eq!(contract.total_revenue, 2 * SOL);
+}
+
+#[tokio::test]
+async fn delegates_are_limited_to_their_permissions() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+    let startup = deal.startup.insecure_clone();
+    let treasurer = deal.env.funded_keypair(10 * SOL).await;
+
+    for permissions in [0, ALL_PERMISSIONS + 1] {
+        let result = deal
+            .env
+            .send(
+                &[add_delegate(
+                    &deal.contract,
+                    &startup.pubkey(),
+                    &treasurer.pubkey(),
+                    permissions,
+                )],
+                &[&startup],
+            )
+            .await;
+        assert_error(result, MerakiError::InvalidPermissions);
+    }
+
+    deal.env
+        .send(
+            &[add_delegate(
+                &deal.contract,
+                &startup.pubkey(),
+                &treasurer.pubkey(),
+                PERMISSION_WITHDRAW_TRANCHE,
+            )],
+            &[&startup],
+        )
+        .await
+        .unwrap();
+    let instructions = report(&deal, &treasurer, true);
+    let result = deal.env.send(&instructions, &[&treasurer]).await;
+    assert_error(result, MerakiError::MissingPermission);
+
+    // Only the startup manages delegates.
+    let result = deal
+        .env
+        .send(
+            &[add_delegate(
+                &deal.contract,
+                &treasurer.pubkey(),
+                &treasurer.pubkey(),
+                ALL_PERMISSIONS,
+            )],
+            &[&treasurer],
+        )
+        .await;
+    assert_error(result, ErrorCode::ConstraintHasOne);
+}
+
+#[tokio::test]
+async fn revoked_delegates_can_no_longer_report() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+    let startup = deal.startup.insecure_clone();
+    let reporter = deal.reporter(10 * SOL).await;
+    let delegate = pda::delegate(&deal.contract, &reporter.pubkey());
+    let startup_before = deal.env.lamports(&startup.pubkey()).await;
+
+    deal.env
+        .send(
+            &[revoke_delegate(
+                &deal.contract,
+                &startup.pubkey(),
+                &reporter.pubkey(),
+            )],
+            &[&startup],
+        )
+        .await
+        .unwrap();
+    assert!(!deal.env.exists(&delegate).await);
+    assert!(deal.env.lamports(&startup.pubkey()).await > startup_before);
+
+    let instructions = report(&deal, &reporter, true);
+    let result = deal.env.send(&instructions, &[&reporter]).await;
+    assert_error(result, ErrorCode::AccountNotInitialized);
+}
+
+#[tokio::test]
+async fn withdraw_tranche_native_releases_vested_funds() {
+    let mut deal = funded_deal().await;
+    let startup = deal.startup.insecure_clone();
+    let treasurer = deal.env.funded_keypair(SOL).await;
+    deal.env
+        .send(
+            &[add_delegate(
+                &deal.contract,
+                &startup.pubkey(),
+                &treasurer.pubkey(),
+                PERMISSION_WITHDRAW_TRANCHE,
+            )],
+            &[&startup],
+        )
+        .await
+        .unwrap();
+
+    let contract = deal.contract;
+    let withdraw = |amount| {
+        withdraw_tranche_native(
+            &contract,
+            &treasurer.pubkey(),
+            &startup.pubkey(),
+            amount,
+            true,
+        )
+    };
+    // Nothing has vested when the contract starts...
+    let result = deal.env.send(&[withdraw(1)], &[&treasurer]).await;
+    assert_error(result, MerakiError::TrancheNotVested);
+
+    // ...and 4.975 SOL half way through.
+    deal.env.warp_forward(5 * DAY).await;
+    let result = deal.env.send(&[withdraw(5 * SOL)], &[&treasurer]).await;
+    assert_error(result, MerakiError::TrancheNotVested);
+
+    let startup_before = deal.env.lamports(&startup.pubkey()).await;
+    deal.env
+        .send(&[withdraw(4 * SOL)], &[&treasurer])
+        .await
+        .unwrap();
+    assert_eq!(
+        deal.env.lamports(&startup.pubkey()).await - startup_before,
+        4 * SOL
+    );
+    let result = deal.env.send(&[withdraw(SOL)], &[&treasurer]).await;
+    assert_error(result, MerakiError::TrancheNotVested);
+
+    // Everything has vested once the term is over.
+    deal.env.warp_forward(5 * DAY).await;
+    deal.env
+        .send(&[withdraw(5 * SOL + SOL / 100 * 95)], &[&treasurer])
+        .await
+        .unwrap();
+    let state: InvestmentContract = deal.env.account(&contract).await;
+    assert_eq!(state.total_withdrawn, 9 * SOL + SOL / 100 * 95);
+    let rent = deal.env.rent_exempt_minimum(0).await;
+    assert_eq!(deal.env.lamports(&pda::vault(&deal.contract)).await, rent);
+}
+
+#[tokio::test]
+async fn withdraw_tranche_native_requires_the_permission() {
+    let mut deal = funded_deal().await;
+    deal.env.warp_forward(10 * DAY).await;
+    let reporter = deal.reporter(SOL).await;
+
+    let result = deal
+        .env
+        .send(
+            &[withdraw_tranche_native(
+                &deal.contract,
+                &reporter.pubkey(),
+                &deal.startup.pubkey(),
+                SOL,
+                true,
+            )],
+            &[&reporter],
+        )
+        .await;
+    assert_error(result, MerakiError::MissingPermission);
+}
+
+#[tokio::test]
+async fn withdraw_tranche_pays_the_startup_token_account() {
+    let mut deal = TokenDeal::new(ContractTerms {
+        amount: 1_000,
+        duration_days: 10,
+        is_native: false,
+        ..default_terms()
+    })
+    .await;
+    let accounts = deal.invest_accounts(deal.investor_token_account);
+    deal.env
+        .send(
+            &[invest(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                &accounts,
+                1_000,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+    deal.env.warp_forward(10 * DAY).await;
+    let startup = deal.startup.insecure_clone();
+
+    deal.env
+        .send(
+            &[withdraw_tranche(
+                &deal.contract,
+                &startup.pubkey(),
+                &deal.startup_token_account,
+                995,
+                false,
+            )],
+            &[&startup],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        deal.env.token_balance(&deal.startup_token_account).await,
+        995
+    );
+    assert_eq!(
+        deal.env
+            .token_balance(&pda::token_vault(&deal.contract))
+            .await,
+        0
+    );
+
+    // Withdrawals never go to the delegate's own account.
+    let admin = deal.admin.insecure_clone();
+    let foreign = deal
+        .env
+        .token_account(&deal.mint, &admin.pubkey(), None, 0)
+        .await;
+    let result = deal
+        .env
+        .send(
+            &[withdraw_tranche(
+                &deal.contract,
+                &startup.pubkey(),
+                &foreign,
+                1,
+                false,
+            )],
+            &[&startup],
+        )
+        .await;
+    assert_error(result, ErrorCode::ConstraintTokenOwner);
+}
@@ -0,0 +1,998 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use meraki_contract::{
+    Amendment, InvestmentContract, MerakiError, PositionView, REPORTING_GRACE_PERIOD,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+fn collateralised() -> ContractTerms {
+    ContractTerms {
+        collateral_amount: 2 * SOL,
+        duration_days: 30,
+        ..default_terms()
+    }
+}
+
+#[tokio::test]
+async fn collateral_is_posted_by_the_startup() {
+    let mut deal = NativeDeal::new(collateralised()).await;
+
+    let rent = deal.env.rent_exempt_minimum(0).await;
+    assert_eq!(
+        deal.env.lamports(&pda::collateral(&deal.contract)).await,
+        2 * SOL + rent
+    );
+    let contract: InvestmentContract = deal.env.account(&deal.contract).await;
+    assert_eq!(contract.collateral_amount, 2 * SOL);
+}
+
+#[tokio::test]
+async fn collateral_requires_the_startup_signature() {
+    let mut env = TestEnv::new().await;
+    let investor = env.payer();
+    let startup = env.funded_keypair(10 * SOL).await;
+    let contract = Keypair::new();
+
+    let mut instruction = initialize_contract(
+        &contract.pubkey(),
+        &investor.pubkey(),
+        &startup.pubkey(),
+        None,
+        collateralised(),
+    );
+    for meta in instruction.accounts.iter_mut() {
+        if meta.pubkey == startup.pubkey() {
+            meta.is_signer = false;
+        }
+    }
+    let result = env.send(&[instruction], &[&contract]).await;
+    assert_error(result, MerakiError::Unauthorized);
+}
+
+#[tokio::test]
+async fn collateral_deposit_detects_overflow() {
+    let mut env = TestEnv::new().await;
+    let investor = env.payer();
+    let startup = env.funded_keypair(10 * SOL).await;
+    let contract = Keypair::new();
+
+    let result = env
+        .send(
+            &[initialize_contract(
+                &contract.pubkey(),
+                &investor.pubkey(),
+                &startup.pubkey(),
+                None,
+                ContractTerms {
+                    collateral_amount: u64::MAX,
+                    ..default_terms()
+                },
+            )],
+            &[&contract, &startup],
+        )
+        .await;
+    assert_error(result, MerakiError::Overflow);
+}
+
+#[tokio::test]
+async fn mark_defaulted_is_limited_to_the_arbiter_before_the_grace_period() {
+    let mut deal = NativeDeal::new(collateralised()).await;
+    deal.env
+        .send(
+            &[invest_native(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                None,
+                10 * SOL,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+    let stranger = deal.env.funded_keypair(SOL).await;
+
+    let result = deal
+        .env
+        .send(
+            &[mark_defaulted(&deal.contract, &stranger.pubkey())],
+            &[&stranger],
+        )
+        .await;
+    assert_error(result, MerakiError::Unauthorized);
+
+    // The admin is the arbiter until told otherwise.
+    deal.env
+        .send(&[mark_defaulted(&deal.contract, &deal.admin.pubkey())], &[])
+        .await
+        .unwrap();
+    let contract: InvestmentContract = deal.env.account(&deal.contract).await;
+    assert!(contract.is_defaulted);
+    assert!(!contract.is_active);
+}
+
+#[tokio::test]
+async fn mark_defaulted_is_open_to_anyone_after_missed_reports() {
+    let mut deal = NativeDeal::new(collateralised()).await;
+    deal.env
+        .send(
+            &[invest_native(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                None,
+                10 * SOL,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+    let stranger = deal.env.funded_keypair(SOL).await;
+
+    deal.env.warp_forward(REPORTING_GRACE_PERIOD + 1).await;
+    deal.env
+        .send(
+            &[mark_defaulted(&deal.contract, &stranger.pubkey())],
+            &[&stranger],
+        )
+        .await
+        .unwrap();
+    let contract: InvestmentContract = deal.env.account(&deal.contract).await;
+    assert!(contract.is_defaulted);
+}
+
+#[tokio::test]
+async fn reporting_grace_period_starts_when_the_contract_fills() {
+    let mut deal = NativeDeal::new(collateralised()).await;
+    deal.env
+        .send(
+            &[invest_native(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                None,
+                4 * SOL,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+    let stranger = deal.env.funded_keypair(SOL).await;
+
+    // Filling long after `start_time` still leaves a full period to report.
+    deal.env.warp_forward(REPORTING_GRACE_PERIOD + DAY).await;
+    deal.env
+        .send(
+            &[invest_native(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                None,
+                6 * SOL,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+    let contract: InvestmentContract = deal.env.account(&deal.contract).await;
+    assert!(contract.is_invested);
+    assert_eq!(contract.last_report_at, deal.env.now().await);
+
+    let result = deal
+        .env
+        .send(
+            &[mark_defaulted(&deal.contract, &stranger.pubkey())],
+            &[&stranger],
+        )
+        .await;
+    assert_error(result, MerakiError::Unauthorized);
+}
+
+#[tokio::test]
+async fn set_arbiter_hands_over_default_calls() {
+    let mut deal = NativeDeal::new(collateralised()).await;
+    let arbiter = deal.env.funded_keypair(SOL).await;
+
+    deal.env
+        .send(&[set_arbiter(&deal.admin.pubkey(), &arbiter.pubkey())], &[])
+        .await
+        .unwrap();
+
+    let result = deal
+        .env
+        .send(&[mark_defaulted(&deal.contract, &deal.admin.pubkey())], &[])
+        .await;
+    assert_error(result, MerakiError::Unauthorized);
+
+    deal.env
+        .send(
+            &[mark_defaulted(&deal.contract, &arbiter.pubkey())],
+            &[&arbiter],
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn slash_collateral_pays_investors_pro_rata() {
+    let mut deal = NativeDeal::new(collateralised()).await;
+    let second = deal.env.funded_keypair(10 * SOL).await;
+    deal.env
+        .send(
+            &[
+                invest_native(&deal.contract, &deal.investor.pubkey(), None, 6 * SOL),
+                invest_native(&deal.contract, &second.pubkey(), None, 4 * SOL),
+            ],
+            &[&deal.investor, &second],
+        )
+        .await
+        .unwrap();
+    deal.env
+        .send(&[mark_defaulted(&deal.contract, &deal.admin.pubkey())], &[])
+        .await
+        .unwrap();
+
+    let claim: PositionView = deal
+        .env
+        .view(position_view(&deal.contract, &second.pubkey()))
+        .await;
+    assert_eq!(claim.collateral_claim, 800_000_000);
+
+    let investor_before = deal.env.lamports(&deal.investor.pubkey()).await;
+    let second_before = deal.env.lamports(&second.pubkey()).await;
+    let startup_before = deal.env.lamports(&deal.startup.pubkey()).await;
+    deal.env
+        .send(
+            &[slash_collateral(
+                &deal.contract,
+                &deal.startup.pubkey(),
+                &[deal.investor.pubkey(), second.pubkey()],
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        deal.env.lamports(&deal.investor.pubkey()).await - investor_before,
+        1_200_000_000
+    );
+    assert_eq!(
+        deal.env.lamports(&second.pubkey()).await - second_before,
+        800_000_000
+    );
+    // Only the rent deposit goes back to the startup.
+    let rent = deal.env.rent_exempt_minimum(0).await;
+    assert_eq!(
+        deal.env.lamports(&deal.startup.pubkey()).await - startup_before,
+        rent
+    );
+    assert!(!deal.env.exists(&pda::collateral(&deal.contract)).await);
+
+    let contract: InvestmentContract = deal.env.account(&deal.contract).await;
+    assert_eq!(contract.collateral_amount, 0);
+}
+
+#[tokio::test]
+async fn slash_collateral_requires_a_default_and_every_position() {
+    let mut deal = NativeDeal::new(collateralised()).await;
+    let second = deal.env.funded_keypair(10 * SOL).await;
+    deal.env
+        .send(
+            &[
+                invest_native(&deal.contract, &deal.investor.pubkey(), None, 6 * SOL),
+                invest_native(&deal.contract, &second.pubkey(), None, 4 * SOL),
+            ],
+            &[&deal.investor, &second],
+        )
+        .await
+        .unwrap();
+    let everyone = [deal.investor.pubkey(), second.pubkey()];
+
+    let result = deal
+        .env
+        .send(
+            &[slash_collateral(
+                &deal.contract,
+                &deal.startup.pubkey(),
+                &everyone,
+            )],
+            &[],
+        )
+        .await;
+    assert_error(result, MerakiError::NotDefaulted);
+
+    deal.env
+        .send(&[mark_defaulted(&deal.contract, &deal.admin.pubkey())], &[])
+        .await
+        .unwrap();
+    let result = deal
+        .env
+        .send(
+            &[slash_collateral(
+                &deal.contract,
+                &deal.startup.pubkey(),
+                &[deal.investor.pubkey()],
+            )],
+            &[],
+        )
+        .await;
+    assert_error(result, MerakiError::IncompletePositionList);
+}
+
+#[tokio::test]
+async fn defaulted_vault_is_refunded_pro_rata() {
+    let mut deal = NativeDeal::new(collateralised()).await;
+    let second = deal.env.funded_keypair(10 * SOL).await;
+    let everyone = [deal.investor.pubkey(), second.pubkey()];
+    deal.env
+        .send(
+            &[
+                invest_native(&deal.contract, &deal.investor.pubkey(), None, 6 * SOL),
+                invest_native(&deal.contract, &second.pubkey(), None, 4 * SOL),
+            ],
+            &[&deal.investor, &second],
+        )
+        .await
+        .unwrap();
+
+    let result = deal
+        .env
+        .send(
+            &[refund_defaulted_native(&deal.contract, &second.pubkey())],
+            &[],
+        )
+        .await;
+    assert_error(result, MerakiError::NotDefaulted);
+
+    deal.env
+        .send(&[mark_defaulted(&deal.contract, &deal.admin.pubkey())], &[])
+        .await
+        .unwrap();
+    // The collateral goes first, while both positions still count.
+    let result = deal
+        .env
+        .send(
+            &[refund_defaulted_native(&deal.contract, &second.pubkey())],
+            &[],
+        )
+        .await;
+    assert_error(result, MerakiError::CollateralOutstanding);
+    deal.env
+        .send(
+            &[slash_collateral(
+                &deal.contract,
+                &deal.startup.pubkey(),
+                &everyone,
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+
+    // The vault holds 9.95 SOL once the platform fee is taken.
+    let investor_before = deal.env.lamports(&deal.investor.pubkey()).await;
+    let second_before = deal.env.lamports(&second.pubkey()).await;
+    deal.env
+        .send(
+            &[
+                refund_defaulted_native(&deal.contract, &second.pubkey()),
+                refund_defaulted_native(&deal.contract, &deal.investor.pubkey()),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        deal.env.lamports(&second.pubkey()).await - second_before,
+        3_980_000_000
+    );
+    assert_eq!(
+        deal.env.lamports(&deal.investor.pubkey()).await - investor_before,
+        5_970_000_000
+    );
+    let contract: InvestmentContract = deal.env.account(&deal.contract).await;
+    assert_eq!(contract.total_raised, 0);
+
+    let result = deal
+        .env
+        .send(
+            &[refund_defaulted_native(&deal.contract, &second.pubkey())],
+            &[],
+        )
+        .await;
+    assert_error(result, MerakiError::AlreadyRefunded);
+
+    deal.env
+        .send(
+            &[close_contract(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                true,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+    assert!(!deal.env.exists(&deal.contract).await);
+}
+
+#[tokio::test]
+async fn complete_contract_returns_collateral_after_the_term() {
+    let mut deal = NativeDeal::new(collateralised()).await;
+
+    let result = deal
+        .env
+        .send(
+            &[complete_contract(&deal.contract, &deal.startup.pubkey())],
+            &[&deal.startup],
+        )
+        .await;
+    assert_error(result, MerakiError::ContractNotEnded);
+
+    deal.env.warp_forward(30 * DAY).await;
+    let startup_before = deal.env.lamports(&deal.startup.pubkey()).await;
+    deal.env
+        .send(
+            &[complete_contract(&deal.contract, &deal.startup.pubkey())],
+            &[&deal.startup],
+        )
+        .await
+        .unwrap();
+
+    let rent = deal.env.rent_exempt_minimum(0).await;
+    assert_eq!(
+        deal.env.lamports(&deal.startup.pubkey()).await - startup_before,
+        2 * SOL + rent
+    );
+    let contract: InvestmentContract = deal.env.account(&deal.contract).await;
+    assert!(contract.is_completed);
+    assert!(!contract.is_active);
+    assert_eq!(contract.collateral_amount, 0);
+}
+
+#[tokio::test]
+async fn complete_contract_detects_duration_overflow() {
+    let mut deal = NativeDeal::new(ContractTerms {
+        duration_days: u64::MAX,
+        ..default_terms()
+    })
+    .await;
+
+    let result = deal
+        .env
+        .send(
+            &[complete_contract(&deal.contract, &deal.startup.pubkey())],
+            &[&deal.startup],
+        )
+        .await;
+    assert_error(result, MerakiError::Overflow);
+}
+
+#[tokio::test]
+async fn complete_contract_requires_the_startup_and_current_reports() {
+    let mut deal = NativeDeal::new(ContractTerms {
+        duration_days: 120,
+        ..collateralised()
+    })
+    .await;
+    deal.invest(SOL).await;
+    deal.env.warp_forward(120 * DAY).await;
+
+    let stranger = deal.env.funded_keypair(SOL).await;
+    let result = deal
+        .env
+        .send(
+            &[complete_contract(&deal.contract, &stranger.pubkey())],
+            &[&stranger],
+        )
+        .await;
+    assert_error(result, anchor_lang::error::ErrorCode::ConstraintHasOne);
+
+    // No report for 120 days: the contract is up for a default, not for
+    // completion.
+    let result = deal
+        .env
+        .send(
+            &[complete_contract(&deal.contract, &deal.startup.pubkey())],
+            &[&deal.startup],
+        )
+        .await;
+    assert_error(result, MerakiError::ReportingOverdue);
+    deal.env
+        .send(
+            &[mark_defaulted(&deal.contract, &stranger.pubkey())],
+            &[&stranger],
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn release_collateral_once_every_position_is_refunded() {
+    let mut deal = NativeDeal::new(collateralised()).await;
+    let other = deal.env.funded_keypair(10 * SOL).await;
+    deal.invest(SOL).await;
+    deal.invest_from(&other, SOL).await;
+    let startup = deal.startup.insecure_clone();
+
+    let result = deal
+        .env
+        .send(
+            &[release_collateral(&deal.contract, &startup.pubkey())],
+            &[&startup],
+        )
+        .await;
+    assert_error(result, MerakiError::ContractNotFinished);
+
+    deal.env
+        .send(
+            &[refund_native(
+                &deal.contract,
+                &startup.pubkey(),
+                &other.pubkey(),
+            )],
+            &[&startup],
+        )
+        .await
+        .unwrap();
+    let result = deal
+        .env
+        .send(
+            &[release_collateral(&deal.contract, &startup.pubkey())],
+            &[&startup],
+        )
+        .await;
+    assert_error(result, MerakiError::PositionsOutstanding);
+
+    deal.env
+        .send(
+            &[refund_native(
+                &deal.contract,
+                &startup.pubkey(),
+                &deal.investor.pubkey(),
+            )],
+            &[&startup],
+        )
+        .await
+        .unwrap();
+    let startup_before = deal.env.lamports(&startup.pubkey()).await;
+    deal.env
+        .send(
+            &[release_collateral(&deal.contract, &startup.pubkey())],
+            &[&startup],
+        )
+        .await
+        .unwrap();
+
+    let rent = deal.env.rent_exempt_minimum(0).await;
+    assert_eq!(
+        deal.env.lamports(&startup.pubkey()).await - startup_before,
+        2 * SOL + rent
+    );
+    let contract: InvestmentContract = deal.env.account(&deal.contract).await;
+    assert_eq!(contract.collateral_amount, 0);
+
+    let result = deal
+        .env
+        .send(
+            &[release_collateral(&deal.contract, &startup.pubkey())],
+            &[&startup],
+        )
+        .await;
+    assert_error(result, MerakiError::NoCollateral);
+}
+
+#[tokio::test]
+async fn release_collateral_of_a_defaulted_contract_without_positions() {
+    let mut deal = NativeDeal::new(collateralised()).await;
+    deal.env
+        .send(&[mark_defaulted(&deal.contract, &deal.admin.pubkey())], &[])
+        .await
+        .unwrap();
+
+    // There is no one to slash the collateral to.
+    let result = deal
+        .env
+        .send(
+            &[slash_collateral(
+                &deal.contract,
+                &deal.startup.pubkey(),
+                &[],
+            )],
+            &[],
+        )
+        .await;
+    assert_error(result, MerakiError::InvalidPositionList);
+
+    let startup_before = deal.env.lamports(&deal.startup.pubkey()).await;
+    deal.env
+        .send(
+            &[release_collateral(&deal.contract, &deal.startup.pubkey())],
+            &[&deal.startup],
+        )
+        .await
+        .unwrap();
+    let rent = deal.env.rent_exempt_minimum(0).await;
+    assert_eq!(
+        deal.env.lamports(&deal.startup.pubkey()).await - startup_before,
+        2 * SOL + rent
+    );
+}
+
+#[tokio::test]
+async fn close_contract_returns_rent_to_the_investor() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+    let investor = deal.investor.pubkey();
+    deal.env
+        .send(
+            &[
+                invest_native(&deal.contract, &investor, None, 4 * SOL),
+                refund_native(&deal.contract, &deal.startup.pubkey(), &investor),
+            ],
+            &[&deal.investor, &deal.startup],
+        )
+        .await
+        .unwrap();
+    let investor_before = deal.env.lamports(&investor).await;
+
+    deal.env
+        .send(
+            &[close_contract(&deal.contract, &investor, true)],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+
+    let contract_rent = deal
+        .env
+        .rent_exempt_minimum(8 + InvestmentContract::LEN)
+        .await;
+    let vault_rent = deal.env.rent_exempt_minimum(0).await;
+    assert_eq!(
+        deal.env.lamports(&investor).await - investor_before,
+        contract_rent + vault_rent
+    );
+    assert!(!deal.env.exists(&deal.contract).await);
+    assert!(!deal.env.exists(&pda::vault(&deal.contract)).await);
+}
+
+#[tokio::test]
+async fn close_contract_requires_a_finished_contract_with_empty_vaults() {
+    let mut deal = NativeDeal::new(collateralised()).await;
+    let investor = deal.investor.pubkey();
+    deal.env
+        .send(
+            &[invest_native(&deal.contract, &investor, None, SOL)],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+
+    let result = deal
+        .env
+        .send(
+            &[close_contract(&deal.contract, &investor, true)],
+            &[&deal.investor],
+        )
+        .await;
+    assert_error(result, MerakiError::ContractNotFinished);
+
+    // Completed, but the investment is still in the vault.
+    deal.env.warp_forward(30 * DAY).await;
+    deal.env
+        .send(
+            &[complete_contract(&deal.contract, &deal.startup.pubkey())],
+            &[&deal.startup],
+        )
+        .await
+        .unwrap();
+    let result = deal
+        .env
+        .send(
+            &[close_contract(&deal.contract, &investor, true)],
+            &[&deal.investor],
+        )
+        .await;
+    assert_error(result, MerakiError::VaultNotEmpty);
+
+    let stranger = deal.env.funded_keypair(SOL).await;
+    let result = deal
+        .env
+        .send(
+            &[close_contract(&deal.contract, &stranger.pubkey(), true)],
+            &[&stranger],
+        )
+        .await;
+    assert_error(result, anchor_lang::error::ErrorCode::ConstraintHasOne);
+}
+
+#[tokio::test]
+async fn close_contract_keeps_refunded_contracts_with_collateral() {
+    let mut deal = NativeDeal::new(collateralised()).await;
+    let investor = deal.investor.pubkey();
+    deal.env
+        .send(
+            &[
+                invest_native(&deal.contract, &investor, None, SOL),
+                refund_native(&deal.contract, &deal.startup.pubkey(), &investor),
+            ],
+            &[&deal.investor, &deal.startup],
+        )
+        .await
+        .unwrap();
+
+    let result = deal
+        .env
+        .send(
+            &[close_contract(&deal.contract, &investor, true)],
+            &[&deal.investor],
+        )
+        .await;
+    assert_error(result, MerakiError::CollateralOutstanding);
+}
+
+#[tokio::test]
+async fn close_contract_accepts_defaulted_contracts_once_settled() {
+    let mut deal = NativeDeal::new(collateralised()).await;
+    let investor = deal.investor.pubkey();
+    deal.env
+        .send(&[mark_defaulted(&deal.contract, &deal.admin.pubkey())], &[])
+        .await
+        .unwrap();
+
+    let result = deal
+        .env
+        .send(
+            &[close_contract(&deal.contract, &investor, true)],
+            &[&deal.investor],
+        )
+        .await;
+    assert_error(result, MerakiError::CollateralOutstanding);
+
+    deal.env
+        .send(
+            &[release_collateral(&deal.contract, &deal.startup.pubkey())],
+            &[&deal.startup],
+        )
+        .await
+        .unwrap();
+    deal.env
+        .send(
+            &[close_contract(&deal.contract, &investor, true)],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+    assert!(!deal.env.exists(&deal.contract).await);
+}
+
+#[tokio::test]
+async fn close_contract_closes_the_token_vault() {
+    let mut deal = TokenDeal::new(ContractTerms {
+        amount: 1_000,
+        duration_days: 30,
+        ..default_terms()
+    })
+    .await;
+    let investor = deal.investor.pubkey();
+    let token_vault = pda::token_vault(&deal.contract);
+    assert!(deal.env.exists(&token_vault).await);
+
+    deal.env.warp_forward(30 * DAY).await;
+    deal.env
+        .send(
+            &[complete_contract(&deal.contract, &deal.startup.pubkey())],
+            &[&deal.startup],
+        )
+        .await
+        .unwrap();
+
+    // The token vault is required for token contracts.
+    let result = deal
+        .env
+        .send(
+            &[close_contract(&deal.contract, &investor, true)],
+            &[&deal.investor],
+        )
+        .await;
+    assert_error(result, MerakiError::MissingTokenVault);
+
+    deal.env
+        .send(
+            &[close_contract(&deal.contract, &investor, false)],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+    assert!(!deal.env.exists(&token_vault).await);
+    assert!(!deal.env.exists(&deal.contract).await);
+}
+
+#[tokio::test]
+async fn amendment_applies_once_both_parties_sign() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+    let investor = deal.investor.pubkey();
+
+    deal.env
+        .send(
+            &[propose_amendment(
+                &deal.contract,
+                &investor,
+                Some(1_500),
+                Some(720),
+                DAY,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+    let amendment: Amendment = deal.env.account(&pda::amendment(&deal.contract)).await;
+    assert!(amendment.investor_signed);
+    assert!(!amendment.startup_signed);
+
+    deal.env
+        .send(
+            &[accept_amendment(
+                &deal.contract,
+                &deal.startup.pubkey(),
+                &investor,
+            )],
+            &[&deal.startup],
+        )
+        .await
+        .unwrap();
+
+    let contract: InvestmentContract = deal.env.account(&deal.contract).await;
+    assert_eq!(contract.investor_return_bps, 1_500);
+    assert_eq!(contract.duration_days, 720);
+    assert_eq!(contract.amendment_count, 1);
+    assert!(!deal.env.exists(&pda::amendment(&deal.contract)).await);
+}
+
+#[tokio::test]
+async fn amendment_rejects_outsiders_and_bad_proposals() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+    let investor = deal.investor.pubkey();
+    let stranger = deal.env.funded_keypair(SOL).await;
+
+    let result = deal
+        .env
+        .send(
+            &[propose_amendment(
+                &deal.contract,
+                &stranger.pubkey(),
+                Some(1_500),
+                None,
+                DAY,
+            )],
+            &[&stranger],
+        )
+        .await;
+    assert_error(result, MerakiError::Unauthorized);
+
+    let result = deal
+        .env
+        .send(
+            &[propose_amendment(
+                &deal.contract,
+                &investor,
+                None,
+                None,
+                DAY,
+            )],
+            &[&deal.investor],
+        )
+        .await;
+    assert_error(result, MerakiError::EmptyAmendment);
+
+    let result = deal
+        .env
+        .send(
+            &[propose_amendment(
+                &deal.contract,
+                &investor,
+                Some(1_500),
+                None,
+                i64::MAX,
+            )],
+            &[&deal.investor],
+        )
+        .await;
+    assert_error(result, MerakiError::Overflow);
+
+    deal.env
+        .send(
+            &[propose_amendment(
+                &deal.contract,
+                &investor,
+                Some(1_500),
+                None,
+                DAY,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+    let result = deal
+        .env
+        .send(
+            &[accept_amendment(
+                &deal.contract,
+                &stranger.pubkey(),
+                &investor,
+            )],
+            &[&stranger],
+        )
+        .await;
+    assert_error(result, MerakiError::Unauthorized);
+
+    // The proposer signing again doesn't complete it.
+    let result = deal
+        .env
+        .send(
+            &[accept_amendment(&deal.contract, &investor, &investor)],
+            &[&deal.investor],
+        )
+        .await;
+    assert_error(result, MerakiError::AmendmentNotFullySigned);
+}
+
+#[tokio::test]
+async fn expired_amendment_can_only_be_cancelled() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+    let investor = deal.investor.pubkey();
+    let stranger = deal.env.funded_keypair(SOL).await;
+    deal.env
+        .send(
+            &[propose_amendment(
+                &deal.contract,
+                &investor,
+                Some(1_500),
+                None,
+                DAY,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+
+    let result = deal
+        .env
+        .send(
+            &[cancel_amendment(
+                &deal.contract,
+                &stranger.pubkey(),
+                &investor,
+            )],
+            &[&stranger],
+        )
+        .await;
+    assert_error(result, MerakiError::Unauthorized);
+
+    deal.env.warp_forward(DAY).await;
+    let result = deal
+        .env
+        .send(
+            &[accept_amendment(
+                &deal.contract,
+                &deal.startup.pubkey(),
+                &investor,
+            )],
+            &[&deal.startup],
+        )
+        .await;
+    assert_error(result, MerakiError::AmendmentExpired);
+
+    deal.env
+        .send(
+            &[cancel_amendment(
+                &deal.contract,
+                &stranger.pubkey(),
+                &investor,
+            )],
+            &[&stranger],
+        )
+        .await
+        .unwrap();
+    assert!(!deal.env.exists(&pda::amendment(&deal.contract)).await);
+}
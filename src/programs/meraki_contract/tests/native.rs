@@ -0,0 +1,776 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use meraki_contract::{
+    InvestmentContract, MerakiError, Position, PositionView, ReturnTier, RevenueSplitView,
+    TOTAL_SHARE_SUPPLY,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+#[tokio::test]
+async fn initialize_contract_stores_terms() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+
+    let contract: InvestmentContract = deal.env.account(&deal.contract).await;
+    assert_eq!(contract.investor, deal.investor.pubkey());
+    assert_eq!(contract.startup, deal.startup.pubkey());
+    assert_eq!(contract.amount, 10 * SOL);
+    assert_eq!(contract.investor_return_bps, 1_000);
+    assert!(contract.is_native);
+    assert!(contract.is_active);
+    assert_eq!(contract.mint, Pubkey::default());
+    assert_eq!(contract.terms_hash, [7; 32]);
+    assert_eq!(
+        contract.metadata_uri,
+        "https://meraki.example/contracts/1.json"
+    );
+
+    // The native vault is seeded with its rent exempt minimum.
+    let rent = deal.env.rent_exempt_minimum(0).await;
+    assert_eq!(deal.env.lamports(&pda::vault(&deal.contract)).await, rent);
+}
+
+#[tokio::test]
+async fn initialize_contract_rejects_invalid_terms() {
+    let mut env = TestEnv::new().await;
+    let investor = env.payer();
+    let startup = Keypair::new();
+
+    let cases = [
+        (
+            ContractTerms {
+                amount: 0,
+                ..default_terms()
+            },
+            MerakiError::InvalidAmount,
+        ),
+        (
+            ContractTerms {
+                investor_return_bps: 9_951,
+                ..default_terms()
+            },
+            MerakiError::InvalidReturnBps,
+        ),
+        (
+            ContractTerms {
+                equity_offered_bps: 10_001,
+                ..default_terms()
+            },
+            MerakiError::InvalidEquity,
+        ),
+        (
+            ContractTerms {
+                return_tiers: vec![
+                    ReturnTier {
+                        returned_multiple_bps: 0,
+                        starts_at: 0,
+                        rate_bps: 100,
+                    };
+                    5
+                ],
+                ..default_terms()
+            },
+            MerakiError::TooManyReturnTiers,
+        ),
+        (
+            ContractTerms {
+                metadata_uri: "x".repeat(201),
+                ..default_terms()
+            },
+            MerakiError::MetadataUriTooLong,
+        ),
+        (
+            ContractTerms {
+                is_native: false,
+                ..default_terms()
+            },
+            MerakiError::MissingMint,
+        ),
+    ];
+
+    for (terms, expected) in cases {
+        let contract = Keypair::new();
+        let result = env
+            .send(
+                &[initialize_contract(
+                    &contract.pubkey(),
+                    &investor.pubkey(),
+                    &startup.pubkey(),
+                    None,
+                    terms,
+                )],
+                &[&contract],
+            )
+            .await;
+        assert_error(result, expected);
+    }
+}
+
+#[tokio::test]
+async fn invest_native_takes_fee_and_mints_shares() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+    let treasury = pda::treasury(&Pubkey::default());
+    let vault = pda::vault(&deal.contract);
+    let treasury_before = deal.env.lamports(&treasury).await;
+    let vault_before = deal.env.lamports(&vault).await;
+
+    deal.env
+        .send(
+            &[invest_native(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                None,
+                10 * SOL,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+
+    // 0.5% to the treasury, the rest to the vault.
+    assert_eq!(
+        deal.env.lamports(&treasury).await - treasury_before,
+        50_000_000
+    );
+    assert_eq!(
+        deal.env.lamports(&vault).await - vault_before,
+        9_950_000_000
+    );
+
+    let contract: InvestmentContract = deal.env.account(&deal.contract).await;
+    assert_eq!(contract.total_raised, 10 * SOL);
+    assert!(contract.is_invested);
+
+    // The full cap buys the 10% of equity on offer.
+    let position: Position = deal
+        .env
+        .account(&pda::position(&deal.contract, &deal.investor.pubkey()))
+        .await;
+    assert_eq!(position.contributed, 10 * SOL);
+    assert_eq!(position.shares, TOTAL_SHARE_SUPPLY / 10);
+    let share_account = anchor_spl::associated_token::get_associated_token_address(
+        &deal.investor.pubkey(),
+        &pda::share_mint(&deal.contract),
+    );
+    assert_eq!(
+        deal.env.token_balance(&share_account).await,
+        TOTAL_SHARE_SUPPLY / 10
+    );
+}
+
+#[tokio::test]
+async fn invest_native_fee_rounds_down() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+    let treasury = pda::treasury(&Pubkey::default());
+    let treasury_before = deal.env.lamports(&treasury).await;
+
+    // 199 lamports is below the smallest amount that carries a fee.
+    deal.env
+        .send(
+            &[invest_native(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                None,
+                199,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+    assert_eq!(deal.env.lamports(&treasury).await, treasury_before);
+
+    deal.env
+        .send(
+            &[invest_native(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                None,
+                200,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+    assert_eq!(deal.env.lamports(&treasury).await, treasury_before + 1);
+
+    let position: Position = deal
+        .env
+        .account(&pda::position(&deal.contract, &deal.investor.pubkey()))
+        .await;
+    assert_eq!(position.contributed, 399);
+}
+
+#[tokio::test]
+async fn invest_native_rejects_amounts_over_the_cap() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+
+    let result = deal
+        .env
+        .send(
+            &[invest_native(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                None,
+                10 * SOL + 1,
+            )],
+            &[&deal.investor],
+        )
+        .await;
+    assert_error(result, MerakiError::CapExceeded);
+
+    let result = deal
+        .env
+        .send(
+            &[invest_native(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                None,
+                0,
+            )],
+            &[&deal.investor],
+        )
+        .await;
+    assert_error(result, MerakiError::InvalidAmount);
+}
+
+#[tokio::test]
+async fn invest_native_detects_contribution_overflow() {
+    let mut deal = NativeDeal::new(ContractTerms {
+        amount: u64::MAX,
+        ..default_terms()
+    })
+    .await;
+    deal.env
+        .send(
+            &[invest_native(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                None,
+                SOL,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+
+    let result = deal
+        .env
+        .send(
+            &[invest_native(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                None,
+                u64::MAX,
+            )],
+            &[&deal.investor],
+        )
+        .await;
+    assert_error(result, MerakiError::Overflow);
+}
+
+#[tokio::test]
+async fn record_revenue_native_splits_revenue() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+    deal.invest(SOL).await;
+    let payer = deal.reporter(10 * SOL).await;
+    let treasury = pda::treasury(&Pubkey::default());
+    let treasury_before = deal.env.lamports(&treasury).await;
+    let investor_before = deal.env.lamports(&deal.investor.pubkey()).await;
+    let startup_before = deal.env.lamports(&deal.startup.pubkey()).await;
+
+    deal.env
+        .send(
+            &[record_revenue_native(
+                &deal.contract,
+                &payer.pubkey(),
+                &deal.startup.pubkey(),
+                &[deal.investor.pubkey()],
+                SOL,
+                true,
+            )],
+            &[&payer],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        deal.env.lamports(&treasury).await - treasury_before,
+        5_000_000
+    );
+    assert_eq!(
+        deal.env.lamports(&deal.investor.pubkey()).await - investor_before,
+        100_000_000
+    );
+    assert_eq!(
+        deal.env.lamports(&deal.startup.pubkey()).await - startup_before,
+        895_000_000
+    );
+
+    let contract: InvestmentContract = deal.env.account(&deal.contract).await;
+    assert_eq!(contract.total_revenue, SOL);
+    assert_eq!(contract.total_distributed, 100_000_000);
+}
+
+#[tokio::test]
+async fn record_revenue_native_rounds_small_reports_in_favour_of_the_startup() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+    deal.invest(SOL).await;
+    let payer = deal.reporter(SOL).await;
+    let treasury = pda::treasury(&Pubkey::default());
+    let treasury_before = deal.env.lamports(&treasury).await;
+    let investor_before = deal.env.lamports(&deal.investor.pubkey()).await;
+    let startup_before = deal.env.lamports(&deal.startup.pubkey()).await;
+
+    deal.env
+        .send(
+            &[record_revenue_native(
+                &deal.contract,
+                &payer.pubkey(),
+                &deal.startup.pubkey(),
+                &[deal.investor.pubkey()],
+                199,
+                true,
+            )],
+            &[&payer],
+        )
+        .await
+        .unwrap();
+
+    // No fee below 200 lamports, and 10% of 199 floors to 19.
+    assert_eq!(deal.env.lamports(&treasury).await, treasury_before);
+    assert_eq!(
+        deal.env.lamports(&deal.investor.pubkey()).await - investor_before,
+        19
+    );
+    assert_eq!(
+        deal.env.lamports(&deal.startup.pubkey()).await - startup_before,
+        180
+    );
+}
+
+#[tokio::test]
+async fn record_revenue_native_switches_tier_after_its_start() {
+    let mut env = TestEnv::new().await;
+    let now = env.now().await;
+    let mut deal = NativeDeal::with_env(
+        env,
+        ContractTerms {
+            return_tiers: vec![ReturnTier {
+                returned_multiple_bps: 0,
+                starts_at: now + 30 * DAY,
+                rate_bps: 500,
+            }],
+            ..default_terms()
+        },
+    )
+    .await;
+
+    let split: RevenueSplitView = deal.env.view(preview_revenue(&deal.contract, SOL)).await;
+    assert_eq!(split.rate_bps, 1_000);
+
+    deal.env.warp_forward(31 * DAY).await;
+    let split: RevenueSplitView = deal.env.view(preview_revenue(&deal.contract, SOL)).await;
+    assert_eq!(split.rate_bps, 500);
+    assert_eq!(split.investor_share, 50_000_000);
+}
+
+#[tokio::test]
+async fn record_revenue_native_switches_tier_once_investors_are_repaid() {
+    let mut deal = NativeDeal::new(ContractTerms {
+        return_tiers: vec![ReturnTier {
+            returned_multiple_bps: 10_000,
+            starts_at: 0,
+            rate_bps: 500,
+        }],
+        ..default_terms()
+    })
+    .await;
+    deal.env
+        .send(
+            &[invest_native(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                None,
+                SOL,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+    let payer = deal.reporter(20 * SOL).await;
+
+    // 10% of 10 SOL pays back the whole 1 SOL raised...
+    let investor_before = deal.env.lamports(&deal.investor.pubkey()).await;
+    deal.env
+        .send(
+            &[record_revenue_native(
+                &deal.contract,
+                &payer.pubkey(),
+                &deal.startup.pubkey(),
+                &[deal.investor.pubkey()],
+                10 * SOL,
+                true,
+            )],
+            &[&payer],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        deal.env.lamports(&deal.investor.pubkey()).await - investor_before,
+        SOL
+    );
+
+    // ...after which the investor gets 5%.
+    let investor_before = deal.env.lamports(&deal.investor.pubkey()).await;
+    deal.env
+        .send(
+            &[record_revenue_native(
+                &deal.contract,
+                &payer.pubkey(),
+                &deal.startup.pubkey(),
+                &[deal.investor.pubkey()],
+                SOL,
+                true,
+            )],
+            &[&payer],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        deal.env.lamports(&deal.investor.pubkey()).await - investor_before,
+        50_000_000
+    );
+}
+
+#[tokio::test]
+async fn record_revenue_native_rejects_token_contracts() {
+    let mut deal = TokenDeal::new(default_terms()).await;
+    let payer = deal.env.payer();
+
+    let result = deal
+        .env
+        .send(
+            &[record_revenue_native(
+                &deal.contract,
+                &payer.pubkey(),
+                &deal.startup.pubkey(),
+                &[deal.investor.pubkey()],
+                SOL,
+                false,
+            )],
+            &[],
+        )
+        .await;
+    assert_error(result, MerakiError::WrongDenomination);
+}
+
+#[tokio::test]
+async fn refund_native_returns_contribution_net_of_fee() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+    deal.env
+        .send(
+            &[invest_native(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                None,
+                4 * SOL,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+    let investor_before = deal.env.lamports(&deal.investor.pubkey()).await;
+
+    deal.env
+        .send(
+            &[refund_native(
+                &deal.contract,
+                &deal.startup.pubkey(),
+                &deal.investor.pubkey(),
+            )],
+            &[&deal.startup],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        deal.env.lamports(&deal.investor.pubkey()).await - investor_before,
+        3_980_000_000
+    );
+    let rent = deal.env.rent_exempt_minimum(0).await;
+    assert_eq!(deal.env.lamports(&pda::vault(&deal.contract)).await, rent);
+
+    let contract: InvestmentContract = deal.env.account(&deal.contract).await;
+    assert!(!contract.is_active);
+    assert!(contract.is_refunded);
+
+    let result = deal
+        .env
+        .send(
+            &[refund_native(
+                &deal.contract,
+                &deal.startup.pubkey(),
+                &deal.investor.pubkey(),
+            )],
+            &[&deal.startup],
+        )
+        .await;
+    assert_error(result, MerakiError::AlreadyRefunded);
+}
+
+#[tokio::test]
+async fn refund_native_requires_the_startup() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+    deal.env
+        .send(
+            &[invest_native(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                None,
+                SOL,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+
+    let result = deal
+        .env
+        .send(
+            &[refund_native(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                &deal.investor.pubkey(),
+            )],
+            &[&deal.investor],
+        )
+        .await;
+    assert_error(result, anchor_lang::error::ErrorCode::ConstraintHasOne);
+}
+
+#[tokio::test]
+async fn record_revenue_native_pays_every_position_pro_rata() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+    let other = deal.env.funded_keypair(10 * SOL).await;
+    deal.invest(SOL).await;
+    deal.invest_from(&other, 3 * SOL).await;
+    let payer = deal.reporter(10 * SOL).await;
+    let investors = [deal.investor.pubkey(), other.pubkey()];
+    let investor_before = deal.env.lamports(&deal.investor.pubkey()).await;
+    let other_before = deal.env.lamports(&other.pubkey()).await;
+
+    // Leaving a position out would pay the others more than their share.
+    let result = deal
+        .env
+        .send(
+            &[record_revenue_native(
+                &deal.contract,
+                &payer.pubkey(),
+                &deal.startup.pubkey(),
+                &investors[..1],
+                SOL,
+                true,
+            )],
+            &[&payer],
+        )
+        .await;
+    assert_error(result, MerakiError::IncompletePositionList);
+
+    deal.env
+        .send(
+            &[record_revenue_native(
+                &deal.contract,
+                &payer.pubkey(),
+                &deal.startup.pubkey(),
+                &investors,
+                SOL,
+                true,
+            )],
+            &[&payer],
+        )
+        .await
+        .unwrap();
+
+    // The 0.1 SOL investor share splits 1:3.
+    assert_eq!(
+        deal.env.lamports(&deal.investor.pubkey()).await - investor_before,
+        25_000_000
+    );
+    assert_eq!(
+        deal.env.lamports(&other.pubkey()).await - other_before,
+        75_000_000
+    );
+}
+
+#[tokio::test]
+async fn refund_native_takes_the_position_out_of_the_total_raised() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+    let other = deal.env.funded_keypair(10 * SOL).await;
+    deal.invest(SOL).await;
+    deal.invest_from(&other, 3 * SOL).await;
+
+    deal.env
+        .send(
+            &[refund_native(
+                &deal.contract,
+                &deal.startup.pubkey(),
+                &other.pubkey(),
+            )],
+            &[&deal.startup],
+        )
+        .await
+        .unwrap();
+    let contract: InvestmentContract = deal.env.account(&deal.contract).await;
+    assert_eq!(contract.total_raised, SOL);
+    let refunded: PositionView = deal
+        .env
+        .view(position_view(&deal.contract, &other.pubkey()))
+        .await;
+    assert!(refunded.is_refunded);
+    assert_eq!(refunded.ownership_bps, 0);
+
+    // The remaining position alone now covers the contract.
+    let payer = deal.reporter(10 * SOL).await;
+    let investor_before = deal.env.lamports(&deal.investor.pubkey()).await;
+    deal.env
+        .send(
+            &[record_revenue_native(
+                &deal.contract,
+                &payer.pubkey(),
+                &deal.startup.pubkey(),
+                &[deal.investor.pubkey()],
+                SOL,
+                true,
+            )],
+            &[&payer],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        deal.env.lamports(&deal.investor.pubkey()).await - investor_before,
+        100_000_000
+    );
+}
+
+#[tokio::test]
+async fn refund_native_is_capped_by_what_is_left_in_the_vault() {
+    let mut deal = NativeDeal::new(ContractTerms {
+        duration_days: 10,
+        ..default_terms()
+    })
+    .await;
+    let other = deal.env.funded_keypair(10 * SOL).await;
+    deal.invest(5 * SOL).await;
+    deal.invest_from(&other, 5 * SOL).await;
+
+    // Half way through the startup takes 4 of the 4.975 SOL that vested.
+    deal.env.warp_forward(5 * DAY).await;
+    let startup = deal.startup.insecure_clone();
+    deal.env
+        .send(
+            &[withdraw_tranche_native(
+                &deal.contract,
+                &startup.pubkey(),
+                &startup.pubkey(),
+                4 * SOL,
+                false,
+            )],
+            &[&startup],
+        )
+        .await
+        .unwrap();
+
+    // Each position gets half of the 5.95 SOL left, not its 4.975 SOL net
+    // contribution, so the second refund is still fully covered.
+    for investor in [deal.investor.pubkey(), other.pubkey()] {
+        let before = deal.env.lamports(&investor).await;
+        deal.env
+            .send(
+                &[refund_native(&deal.contract, &startup.pubkey(), &investor)],
+                &[&startup],
+            )
+            .await
+            .unwrap();
+        assert_eq!(deal.env.lamports(&investor).await - before, 2_975_000_000);
+    }
+    let rent = deal.env.rent_exempt_minimum(0).await;
+    assert_eq!(deal.env.lamports(&pda::vault(&deal.contract)).await, rent);
+    let contract: InvestmentContract = deal.env.account(&deal.contract).await;
+    assert_eq!(contract.total_raised, 0);
+}
+
+#[tokio::test]
+async fn views_report_cap_split_and_position() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+    deal.env
+        .send(
+            &[invest_native(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                None,
+                4 * SOL,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+
+    let remaining: u64 = deal.env.view(remaining_cap(&deal.contract)).await;
+    assert_eq!(remaining, 6 * SOL);
+
+    let split: RevenueSplitView = deal.env.view(preview_revenue(&deal.contract, SOL)).await;
+    assert_eq!(
+        split,
+        RevenueSplitView {
+            rate_bps: 1_000,
+            fee: 5_000_000,
+            investor_share: 100_000_000,
+            startup_share: 895_000_000,
+        }
+    );
+
+    let position: PositionView = deal
+        .env
+        .view(position_view(&deal.contract, &deal.investor.pubkey()))
+        .await;
+    assert_eq!(position.contributed, 4 * SOL);
+    assert_eq!(position.shares, TOTAL_SHARE_SUPPLY / 25);
+    assert_eq!(position.ownership_bps, 10_000);
+    assert_eq!(position.collateral_claim, 0);
+    assert!(!position.is_refunded);
+
+    let payout: u64 = deal
+        .env
+        .view(position_payout(
+            &deal.contract,
+            &deal.investor.pubkey(),
+            SOL,
+        ))
+        .await;
+    assert_eq!(payout, 100_000_000);
+}
+
+#[tokio::test]
+async fn mint_investment_nft_mints_one_token() {
+    let mut env = TestEnv::new().await;
+    let authority = env.funded_keypair(SOL).await;
+    let owner = Keypair::new();
+    let mint = env.create_mint(&authority.pubkey()).await;
+    let token_account = env.token_account(&mint, &owner.pubkey(), None, 0).await;
+
+    env.send(
+        &[mint_investment_nft(
+            &mint,
+            &token_account,
+            &authority.pubkey(),
+        )],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(env.token_balance(&token_account).await, 1);
+}
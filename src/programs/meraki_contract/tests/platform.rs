@@ -0,0 +1,377 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use meraki_contract::{AllowlistMode, Attestation, Config, MerakiError};
+use solana_sdk::signature::{Keypair, Signer};
+
+#[tokio::test]
+async fn initialize_config_defaults_to_the_admin() {
+    let mut env = TestEnv::new().await;
+    let admin = env.payer();
+    let compliance = Keypair::new();
+
+    env.send(
+        &[initialize_config(&admin.pubkey(), &compliance.pubkey())],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let config: Config = env.account(&pda::config()).await;
+    assert_eq!(config.admin, admin.pubkey());
+    assert_eq!(config.compliance_authority, compliance.pubkey());
+    assert_eq!(config.operations_wallet, admin.pubkey());
+    assert_eq!(config.reserve_wallet, admin.pubkey());
+    assert_eq!(config.arbiter, admin.pubkey());
+    assert_eq!(config.reserve_bps, 0);
+}
+
+#[tokio::test]
+async fn config_setters_are_admin_only() {
+    let mut env = TestEnv::new().await;
+    let admin = env.payer();
+    let stranger = env.funded_keypair(SOL).await;
+    env.send(&[initialize_config(&admin.pubkey(), &admin.pubkey())], &[])
+        .await
+        .unwrap();
+
+    let result = env
+        .send(
+            &[set_fee_split(
+                &stranger.pubkey(),
+                &stranger.pubkey(),
+                &stranger.pubkey(),
+                0,
+            )],
+            &[&stranger],
+        )
+        .await;
+    assert_error(result, ErrorCode::ConstraintHasOne);
+
+    let result = env
+        .send(
+            &[set_compliance_authority(
+                &stranger.pubkey(),
+                &stranger.pubkey(),
+            )],
+            &[&stranger],
+        )
+        .await;
+    assert_error(result, ErrorCode::ConstraintHasOne);
+
+    let result = env
+        .send(
+            &[set_arbiter(&stranger.pubkey(), &stranger.pubkey())],
+            &[&stranger],
+        )
+        .await;
+    assert_error(result, ErrorCode::ConstraintHasOne);
+
+    let result = env
+        .send(
+            &[set_fee_split(
+                &admin.pubkey(),
+                &admin.pubkey(),
+                &admin.pubkey(),
+                10_001,
+            )],
+            &[],
+        )
+        .await;
+    assert_error(result, MerakiError::InvalidBps);
+}
+
+#[tokio::test]
+async fn withdraw_native_fees_splits_between_wallets() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+    let operations = Keypair::new();
+    let reserve = Keypair::new();
+    deal.env
+        .send(
+            &[
+                set_fee_split(
+                    &deal.admin.pubkey(),
+                    &operations.pubkey(),
+                    &reserve.pubkey(),
+                    2_500,
+                ),
+                invest_native(&deal.contract, &deal.investor.pubkey(), None, 10 * SOL),
+            ],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+
+    // The 0.05 SOL fee is all that's available above rent.
+    let result = deal
+        .env
+        .send(
+            &[withdraw_native_fees(
+                &deal.admin.pubkey(),
+                &operations.pubkey(),
+                &reserve.pubkey(),
+                50_000_001,
+            )],
+            &[],
+        )
+        .await;
+    assert_error(result, MerakiError::InsufficientTreasury);
+
+    deal.env
+        .send(
+            &[withdraw_native_fees(
+                &deal.admin.pubkey(),
+                &operations.pubkey(),
+                &reserve.pubkey(),
+                40_000_000,
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(deal.env.lamports(&operations.pubkey()).await, 30_000_000);
+    assert_eq!(deal.env.lamports(&reserve.pubkey()).await, 10_000_000);
+
+    let rent = deal.env.rent_exempt_minimum(0).await;
+    assert_eq!(
+        deal.env.lamports(&pda::treasury(&Pubkey::default())).await,
+        rent + 10_000_000
+    );
+}
+
+#[tokio::test]
+async fn withdraw_native_fees_is_admin_only() {
+    let mut deal = NativeDeal::new(default_terms()).await;
+    let stranger = deal.env.funded_keypair(SOL).await;
+    let admin = deal.admin.pubkey();
+
+    let result = deal
+        .env
+        .send(
+            &[withdraw_native_fees(&stranger.pubkey(), &admin, &admin, 1)],
+            &[&stranger],
+        )
+        .await;
+    assert_error(result, ErrorCode::ConstraintHasOne);
+}
+
+#[tokio::test]
+async fn allowlisted_contract_requires_an_attestation() {
+    let mut deal = NativeDeal::new(ContractTerms {
+        allowlist: AllowlistMode::Global,
+        ..default_terms()
+    })
+    .await;
+    let investor = deal.investor.pubkey();
+    let scope = Pubkey::default();
+    let attestation = pda::attestation(&scope, &investor);
+
+    let result = deal
+        .env
+        .send(
+            &[invest_native(&deal.contract, &investor, None, SOL)],
+            &[&deal.investor],
+        )
+        .await;
+    assert_error(result, MerakiError::MissingAttestation);
+
+    let now = deal.env.now().await;
+    deal.env
+        .send(
+            &[issue_attestation(
+                &deal.admin.pubkey(),
+                &scope,
+                &investor,
+                now + DAY,
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+    let issued: Attestation = deal.env.account(&attestation).await;
+    assert_eq!(issued.investor, investor);
+    assert_eq!(issued.expires_at, now + DAY);
+
+    deal.env
+        .send(
+            &[invest_native(
+                &deal.contract,
+                &investor,
+                Some(attestation),
+                SOL,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+
+    deal.env.warp_forward(DAY).await;
+    let result = deal
+        .env
+        .send(
+            &[invest_native(
+                &deal.contract,
+                &investor,
+                Some(attestation),
+                2 * SOL,
+            )],
+            &[&deal.investor],
+        )
+        .await;
+    assert_error(result, MerakiError::AttestationExpired);
+}
+
+#[tokio::test]
+async fn attestation_for_another_scope_is_rejected() {
+    let mut deal = NativeDeal::new(ContractTerms {
+        allowlist: AllowlistMode::Contract,
+        ..default_terms()
+    })
+    .await;
+    let investor = deal.investor.pubkey();
+    let global = pda::attestation(&Pubkey::default(), &investor);
+
+    let now = deal.env.now().await;
+    deal.env
+        .send(
+            &[issue_attestation(
+                &deal.admin.pubkey(),
+                &Pubkey::default(),
+                &investor,
+                now + DAY,
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let result = deal
+        .env
+        .send(
+            &[invest_native(&deal.contract, &investor, Some(global), SOL)],
+            &[&deal.investor],
+        )
+        .await;
+    assert_error(result, MerakiError::InvalidAttestation);
+}
+
+#[tokio::test]
+async fn attestations_from_a_replaced_authority_are_rejected() {
+    let mut deal = NativeDeal::new(ContractTerms {
+        allowlist: AllowlistMode::Global,
+        ..default_terms()
+    })
+    .await;
+    let investor = deal.investor.pubkey();
+    let attestation = pda::attestation(&Pubkey::default(), &investor);
+    let now = deal.env.now().await;
+    deal.env
+        .send(
+            &[issue_attestation(
+                &deal.admin.pubkey(),
+                &Pubkey::default(),
+                &investor,
+                now + DAY,
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+
+    // Rotating the authority retires everything the old one issued.
+    let compliance = Keypair::new();
+    deal.env
+        .send(
+            &[set_compliance_authority(
+                &deal.admin.pubkey(),
+                &compliance.pubkey(),
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+    let result = deal
+        .env
+        .send(
+            &[invest_native(
+                &deal.contract,
+                &investor,
+                Some(attestation),
+                SOL,
+            )],
+            &[&deal.investor],
+        )
+        .await;
+    assert_error(result, MerakiError::UnknownAttestationIssuer);
+}
+
+#[tokio::test]
+async fn attestations_are_managed_by_the_compliance_authority() {
+    let mut env = TestEnv::new().await;
+    let admin = env.payer();
+    let compliance = env.funded_keypair(SOL).await;
+    let investor = Keypair::new().pubkey();
+    let scope = Pubkey::default();
+    env.send(&[initialize_config(&admin.pubkey(), &admin.pubkey())], &[])
+        .await
+        .unwrap();
+    let now = env.now().await;
+
+    env.send(
+        &[set_compliance_authority(
+            &admin.pubkey(),
+            &compliance.pubkey(),
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let result = env
+        .send(
+            &[issue_attestation(
+                &admin.pubkey(),
+                &scope,
+                &investor,
+                now + DAY,
+            )],
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::ConstraintHasOne);
+
+    let result = env
+        .send(
+            &[issue_attestation(
+                &compliance.pubkey(),
+                &scope,
+                &investor,
+                now,
+            )],
+            &[&compliance],
+        )
+        .await;
+    assert_error(result, MerakiError::InvalidExpiry);
+
+    env.send(
+        &[issue_attestation(
+            &compliance.pubkey(),
+            &scope,
+            &investor,
+            now + DAY,
+        )],
+        &[&compliance],
+    )
+    .await
+    .unwrap();
+    env.send(
+        &[revoke_attestation(&compliance.pubkey(), &scope, &investor)],
+        &[&compliance],
+    )
+    .await
+    .unwrap();
+    assert!(!env.exists(&pda::attestation(&scope, &investor)).await);
+}
@@ -0,0 +1,534 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use meraki_contract::{InvestmentContract, MerakiError, Position, TOTAL_SHARE_SUPPLY};
+use solana_sdk::signature::{Keypair, Signer};
+
+const TOKEN: u64 = 1_000_000;
+
+fn token_terms() -> ContractTerms {
+    ContractTerms {
+        amount: 1_000 * TOKEN,
+        is_native: false,
+        ..default_terms()
+    }
+}
+
+// `record_revenue` pays the creator's position, so it has to exist.
+async fn invested_deal(terms: ContractTerms) -> TokenDeal {
+    let mut deal = TokenDeal::new(terms).await;
+    let investor = deal.investor.insecure_clone();
+    let accounts = deal.invest_accounts(deal.investor_token_account);
+    deal.env
+        .send(
+            &[invest(
+                &deal.contract,
+                &investor.pubkey(),
+                &accounts,
+                1_000 * TOKEN,
+            )],
+            &[&investor],
+        )
+        .await
+        .unwrap();
+    deal
+}
+
+#[tokio::test]
+async fn invest_moves_tokens_and_mints_shares() {
+    let mut deal = TokenDeal::new(token_terms()).await;
+    let accounts = deal.invest_accounts(deal.investor_token_account);
+
+    deal.env
+        .send(
+            &[invest(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                &accounts,
+                1_000 * TOKEN,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        deal.env.token_balance(&deal.investor_token_account).await,
+        0
+    );
+    assert_eq!(
+        deal.env.token_balance(&pda::treasury(&deal.mint)).await,
+        5 * TOKEN
+    );
+    assert_eq!(
+        deal.env
+            .token_balance(&pda::token_vault(&deal.contract))
+            .await,
+        995 * TOKEN
+    );
+
+    let position: Position = deal
+        .env
+        .account(&pda::position(&deal.contract, &deal.investor.pubkey()))
+        .await;
+    assert_eq!(position.shares, TOTAL_SHARE_SUPPLY / 10);
+    let contract: InvestmentContract = deal.env.account(&deal.contract).await;
+    assert!(contract.is_invested);
+}
+
+#[tokio::test]
+async fn invest_rejects_foreign_mints() {
+    let mut deal = TokenDeal::new(token_terms()).await;
+    let other_mint = deal.env.create_mint(&deal.admin.pubkey()).await;
+    let admin = deal.admin.insecure_clone();
+    let foreign = deal
+        .env
+        .token_account(&other_mint, &deal.investor.pubkey(), Some(&admin), TOKEN)
+        .await;
+
+    let accounts = deal.invest_accounts(foreign);
+    let result = deal
+        .env
+        .send(
+            &[invest(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                &accounts,
+                TOKEN,
+            )],
+            &[&deal.investor],
+        )
+        .await;
+    assert_error(result, ErrorCode::ConstraintTokenMint);
+}
+
+#[tokio::test]
+async fn record_revenue_splits_tokens() {
+    let mut deal = invested_deal(token_terms()).await;
+    let admin = deal.admin.insecure_clone();
+    let payer = deal.reporter(SOL).await;
+    let payer_token_account = deal
+        .env
+        .token_account(&deal.mint, &payer.pubkey(), Some(&admin), 100 * TOKEN)
+        .await;
+
+    deal.env
+        .send(
+            &[record_revenue(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                &payer.pubkey(),
+                &deal.revenue_accounts(payer_token_account),
+                &deal.investor_token_account,
+                100 * TOKEN,
+                true,
+            )],
+            &[&payer],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(deal.env.token_balance(&payer_token_account).await, 0);
+    // 5 tokens from the investment fee, half a token from the revenue.
+    assert_eq!(
+        deal.env.token_balance(&pda::treasury(&deal.mint)).await,
+        5 * TOKEN + TOKEN / 2
+    );
+    assert_eq!(
+        deal.env.token_balance(&deal.investor_token_account).await,
+        10 * TOKEN
+    );
+    assert_eq!(
+        deal.env.token_balance(&deal.startup_token_account).await,
+        89_500_000
+    );
+
+    let contract: InvestmentContract = deal.env.account(&deal.contract).await;
+    assert_eq!(contract.total_revenue, 100 * TOKEN);
+    assert_eq!(contract.total_distributed, 10 * TOKEN);
+}
+
+#[tokio::test]
+async fn record_revenue_requires_the_payers_token_account() {
+    let mut deal = invested_deal(token_terms()).await;
+    let payer = deal.reporter(SOL).await;
+
+    // The investor's account can't be spent by someone else.
+    let result = deal
+        .env
+        .send(
+            &[record_revenue(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                &payer.pubkey(),
+                &deal.revenue_accounts(deal.investor_token_account),
+                &deal.investor_token_account,
+                TOKEN,
+                true,
+            )],
+            &[&payer],
+        )
+        .await;
+    assert_error(result, ErrorCode::ConstraintTokenOwner);
+}
+
+#[tokio::test]
+async fn record_revenue_pays_only_the_parties_token_accounts() {
+    let mut deal = invested_deal(token_terms()).await;
+    let admin = deal.admin.insecure_clone();
+    let payer = deal.reporter(SOL).await;
+    let payer_token_account = deal
+        .env
+        .token_account(&deal.mint, &payer.pubkey(), Some(&admin), 100 * TOKEN)
+        .await;
+    let stranger = Keypair::new();
+    let stranger_token_account = deal
+        .env
+        .token_account(&deal.mint, &stranger.pubkey(), None, 0)
+        .await;
+
+    for (startup_token_account, investor_token_account) in [
+        (&deal.startup_token_account, &stranger_token_account),
+        (&stranger_token_account, &deal.investor_token_account),
+    ] {
+        let result = deal
+            .env
+            .send(
+                &[record_revenue(
+                    &deal.contract,
+                    &deal.investor.pubkey(),
+                    &payer.pubkey(),
+                    &TokenRevenue {
+                        startup_token_account: *startup_token_account,
+                        ..deal.revenue_accounts(payer_token_account)
+                    },
+                    investor_token_account,
+                    TOKEN,
+                    true,
+                )],
+                &[&payer],
+            )
+            .await;
+        assert_error(result, ErrorCode::ConstraintTokenOwner);
+    }
+}
+
+#[tokio::test]
+async fn record_revenue_rejects_contracts_with_several_positions() {
+    let mut deal = invested_deal(ContractTerms {
+        amount: 2_000 * TOKEN,
+        ..token_terms()
+    })
+    .await;
+    let admin = deal.admin.insecure_clone();
+    let other = deal.env.funded_keypair(SOL).await;
+    let other_token_account = deal
+        .env
+        .token_account(&deal.mint, &other.pubkey(), Some(&admin), 1_000 * TOKEN)
+        .await;
+    let accounts = deal.invest_accounts(other_token_account);
+    deal.env
+        .send(
+            &[invest(
+                &deal.contract,
+                &other.pubkey(),
+                &accounts,
+                1_000 * TOKEN,
+            )],
+            &[&other],
+        )
+        .await
+        .unwrap();
+    let payer = deal.reporter(SOL).await;
+    let payer_token_account = deal
+        .env
+        .token_account(&deal.mint, &payer.pubkey(), Some(&admin), 100 * TOKEN)
+        .await;
+
+    let result = deal
+        .env
+        .send(
+            &[record_revenue(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                &payer.pubkey(),
+                &deal.revenue_accounts(payer_token_account),
+                &deal.investor_token_account,
+                100 * TOKEN,
+                true,
+            )],
+            &[&payer],
+        )
+        .await;
+    assert_error(result, MerakiError::MultiplePositions);
+}
+
+// Three equal positions sharing an investor share of 10 units: each gets 3
+// and the unit left over goes to the lowest investor key.
+struct BatchSetup {
+    deal: TokenDeal,
+    payer: Keypair,
+    payer_token_account: Pubkey,
+    recipients: Vec<(Pubkey, Pubkey)>,
+}
+
+async fn batch_setup() -> BatchSetup {
+    let mut deal = TokenDeal::new(ContractTerms {
+        amount: 300,
+        ..token_terms()
+    })
+    .await;
+    let admin = deal.admin.insecure_clone();
+    let mut recipients = vec![];
+    for _ in 0..3 {
+        let investor = deal.env.funded_keypair(SOL).await;
+        let token_account = deal
+            .env
+            .token_account(&deal.mint, &investor.pubkey(), Some(&admin), 100)
+            .await;
+        let accounts = deal.invest_accounts(token_account);
+        deal.env
+            .send(
+                &[invest(&deal.contract, &investor.pubkey(), &accounts, 100)],
+                &[&investor],
+            )
+            .await
+            .unwrap();
+        recipients.push((investor.pubkey(), token_account));
+    }
+
+    let payer = deal.reporter(SOL).await;
+    let payer_token_account = deal
+        .env
+        .token_account(&deal.mint, &payer.pubkey(), Some(&admin), 1_000)
+        .await;
+    BatchSetup {
+        deal,
+        payer,
+        payer_token_account,
+        recipients,
+    }
+}
+
+#[tokio::test]
+async fn record_revenue_batch_pays_every_position() {
+    let BatchSetup {
+        mut deal,
+        payer,
+        payer_token_account,
+        mut recipients,
+    } = batch_setup().await;
+    let startup_before = deal.env.token_balance(&deal.startup_token_account).await;
+
+    deal.env
+        .send(
+            &[record_revenue_batch(
+                &deal.contract,
+                &payer.pubkey(),
+                &deal.revenue_accounts(payer_token_account),
+                &recipients,
+                100,
+                true,
+            )],
+            &[&payer],
+        )
+        .await
+        .unwrap();
+
+    // 100 units: no fee, 10 to investors, 90 to the startup.
+    assert_eq!(
+        deal.env.token_balance(&deal.startup_token_account).await - startup_before,
+        90
+    );
+    recipients.sort_by_key(|(investor, _)| investor.to_bytes());
+    let mut payouts = vec![];
+    for (_, token_account) in &recipients {
+        payouts.push(deal.env.token_balance(token_account).await);
+    }
+    assert_eq!(payouts, vec![4, 3, 3]);
+}
+
+#[tokio::test]
+async fn record_revenue_batch_rejects_partial_or_misdirected_lists() {
+    let BatchSetup {
+        mut deal,
+        payer,
+        payer_token_account,
+        recipients,
+    } = batch_setup().await;
+
+    let result = deal
+        .env
+        .send(
+            &[record_revenue_batch(
+                &deal.contract,
+                &payer.pubkey(),
+                &deal.revenue_accounts(payer_token_account),
+                &recipients[..2],
+                100,
+                true,
+            )],
+            &[&payer],
+        )
+        .await;
+    assert_error(result, MerakiError::IncompletePositionList);
+
+    // Every position paid into the first investor's account.
+    let misdirected: Vec<_> = recipients
+        .iter()
+        .map(|(investor, _)| (*investor, recipients[0].1))
+        .collect();
+    let result = deal
+        .env
+        .send(
+            &[record_revenue_batch(
+                &deal.contract,
+                &payer.pubkey(),
+                &deal.revenue_accounts(payer_token_account),
+                &misdirected,
+                101,
+                true,
+            )],
+            &[&payer],
+        )
+        .await;
+    assert_error(result, MerakiError::InvalidRecipient);
+}
+
+#[tokio::test]
+async fn defaulted_token_vault_is_refunded_pro_rata() {
+    let BatchSetup {
+        mut deal,
+        recipients,
+        ..
+    } = batch_setup().await;
+    deal.env
+        .send(&[mark_defaulted(&deal.contract, &deal.admin.pubkey())], &[])
+        .await
+        .unwrap();
+
+    // The refund only goes to the position's own token account.
+    let (first, _) = recipients[0];
+    let (_, foreign_account) = recipients[1];
+    let result = deal
+        .env
+        .send(
+            &[refund_defaulted(&deal.contract, &first, &foreign_account)],
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::ConstraintTokenOwner);
+
+    let instructions: Vec<_> = recipients
+        .iter()
+        .map(|(investor, token_account)| refund_defaulted(&deal.contract, investor, token_account))
+        .collect();
+    deal.env.send(&instructions, &[]).await.unwrap();
+    for (_, token_account) in &recipients {
+        assert_eq!(deal.env.token_balance(token_account).await, 100);
+    }
+
+    let investor = deal.investor.insecure_clone();
+    deal.env
+        .send(
+            &[close_contract(&deal.contract, &investor.pubkey(), false)],
+            &[&investor],
+        )
+        .await
+        .unwrap();
+    assert!(!deal.env.exists(&deal.contract).await);
+}
+
+#[tokio::test]
+async fn withdraw_fees_splits_between_wallets() {
+    let mut deal = TokenDeal::new(token_terms()).await;
+    let accounts = deal.invest_accounts(deal.investor_token_account);
+    deal.env
+        .send(
+            &[invest(
+                &deal.contract,
+                &deal.investor.pubkey(),
+                &accounts,
+                1_000 * TOKEN,
+            )],
+            &[&deal.investor],
+        )
+        .await
+        .unwrap();
+
+    let operations = Keypair::new();
+    let reserve = Keypair::new();
+    let operations_account = deal
+        .env
+        .token_account(&deal.mint, &operations.pubkey(), None, 0)
+        .await;
+    let reserve_account = deal
+        .env
+        .token_account(&deal.mint, &reserve.pubkey(), None, 0)
+        .await;
+    let admin = deal.admin.pubkey();
+    deal.env
+        .send(
+            &[set_fee_split(
+                &admin,
+                &operations.pubkey(),
+                &reserve.pubkey(),
+                1_000,
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let result = deal
+        .env
+        .send(
+            &[withdraw_fees(
+                &admin,
+                &deal.mint,
+                &operations_account,
+                &reserve_account,
+                5 * TOKEN + 1,
+            )],
+            &[],
+        )
+        .await;
+    assert_error(result, MerakiError::InsufficientTreasury);
+
+    deal.env
+        .send(
+            &[withdraw_fees(
+                &admin,
+                &deal.mint,
+                &operations_account,
+                &reserve_account,
+                5 * TOKEN,
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(deal.env.token_balance(&operations_account).await, 4_500_000);
+    assert_eq!(deal.env.token_balance(&reserve_account).await, 500_000);
+    assert_eq!(deal.env.token_balance(&pda::treasury(&deal.mint)).await, 0);
+
+    // Fees can only go to the configured wallets.
+    let result = deal
+        .env
+        .send(
+            &[withdraw_fees(
+                &admin,
+                &deal.mint,
+                &deal.startup_token_account,
+                &reserve_account,
+                1,
+            )],
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::ConstraintTokenOwner);
+}
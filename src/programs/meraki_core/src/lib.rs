@@ -0,0 +1,204 @@
+//! Fee, revenue-share, cap and vesting math shared by the `meraki_contract`
+//! program and the API server, so on-chain and off-chain numbers always
+//! agree. The crate is `no_std` (with `alloc`) so it builds for the SBF
+//! target.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::fmt;
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+// The platform keeps 1/200 (0.5%) of every investment and revenue report.
+pub const PLATFORM_FEE_DIVISOR: u64 = 200;
+pub const SHARE_DECIMALS: u8 = 6;
+// Share supply that would represent 100% of the startup's equity.
+pub const TOTAL_SHARE_SUPPLY: u64 = 1_000_000 * 10u64.pow(SHARE_DECIMALS as u32);
+// The platform keeps 0.5% of every revenue report, so investors can be owed at
+// most the remaining 99.5%.
+pub const MAX_INVESTOR_RETURN_BPS: u16 = 9_950;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MathError {
+    Overflow,
+    CapExceeded,
+    /// Pro rata split over weights that sum to zero.
+    ZeroWeight,
+}
+
+impl fmt::Display for MathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            MathError::Overflow => "arithmetic overflow",
+            MathError::CapExceeded => "contribution exceeds the contract cap",
+            MathError::ZeroWeight => "weights sum to zero",
+        };
+        f.write_str(message)
+    }
+}
+
+/// How a revenue report is divided between the platform, the investors and
+/// the startup.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct RevenueSplit {
+    pub fee: u64,
+    pub investor_share: u64,
+    pub startup_share: u64,
+}
+
+/// A step in a tiered revenue-share schedule. The tier applies once investors
+/// have been paid `returned_multiple_bps` of what they put in (10_000 = 1x)
+/// and `starts_at` has passed; use 0 for either to ignore that condition.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Tier {
+    pub returned_multiple_bps: u32,
+    pub starts_at: i64,
+    pub rate_bps: u16,
+}
+
+pub fn platform_fee(amount: u64) -> u64 {
+    amount / PLATFORM_FEE_DIVISOR
+}
+
+/// Splits an investment into the platform fee and what reaches the contract.
+pub fn invest_split(total: u64) -> (u64, u64) {
+    let fee = platform_fee(total);
+    (fee, total - fee)
+}
+
+/// Splits a revenue report. The fee and investor share both round down, so
+/// any dust goes to the startup.
+pub fn revenue_split(
+    revenue_amount: u64,
+    investor_return_bps: u16,
+) -> Result<RevenueSplit, MathError> {
+    let fee = platform_fee(revenue_amount);
+    let investor_share = bps_of(revenue_amount, investor_return_bps.into())?;
+    let startup_share = revenue_amount
+        .checked_sub(fee)
+        .and_then(|rest| rest.checked_sub(investor_share))
+        .ok_or(MathError::Overflow)?;
+    Ok(RevenueSplit {
+        fee,
+        investor_share,
+        startup_share,
+    })
+}
+
+/// `amount * bps / 10_000`, rounded down.
+pub fn bps_of(amount: u64, bps: u64) -> Result<u64, MathError> {
+    let value = (amount as u128)
+        .checked_mul(bps as u128)
+        .ok_or(MathError::Overflow)?
+        / BPS_DENOMINATOR as u128;
+    u64::try_from(value).map_err(|_| MathError::Overflow)
+}
+
+/// Splits `total` pro rata to `weights` out of `weight_sum`, flooring each
+/// slice. The rounding remainder goes to the first entry so the slices always
+/// add up to exactly `total`.
+pub fn pro_rata(total: u64, weights: &[u64], weight_sum: u64) -> Result<Vec<u64>, MathError> {
+    if weight_sum == 0 {
+        return Err(MathError::ZeroWeight);
+    }
+
+    let mut slices = Vec::with_capacity(weights.len());
+    let mut assigned: u64 = 0;
+    for weight in weights {
+        let slice = share_of(total, *weight, weight_sum)?;
+        assigned = assigned.checked_add(slice).ok_or(MathError::Overflow)?;
+        slices.push(slice);
+    }
+
+    let remainder = total.checked_sub(assigned).ok_or(MathError::Overflow)?;
+    if let Some(first) = slices.first_mut() {
+        *first += remainder;
+    }
+    Ok(slices)
+}
+
+/// `total * weight / weight_sum`, rounded down; 0 when nothing was raised.
+pub fn share_of(total: u64, weight: u64, weight_sum: u64) -> Result<u64, MathError> {
+    if weight_sum == 0 {
+        return Ok(0);
+    }
+    let slice = (total as u128) * (weight as u128) / (weight_sum as u128);
+    u64::try_from(slice).map_err(|_| MathError::Overflow)
+}
+
+/// Share tokens owed for `contribution`: the contract offers
+/// `equity_offered_bps` of the startup for the full `cap`, so each unit
+/// contributed earns the same slice of that equity.
+pub fn shares_for_contribution(
+    contribution: u64,
+    cap: u64,
+    equity_offered_bps: u16,
+) -> Result<u64, MathError> {
+    let shares = (contribution as u128)
+        .checked_mul(equity_offered_bps as u128)
+        .and_then(|v| v.checked_mul(TOTAL_SHARE_SUPPLY as u128))
+        .and_then(|v| v.checked_div((cap as u128) * BPS_DENOMINATOR as u128))
+        .ok_or(MathError::Overflow)?;
+    u64::try_from(shares).map_err(|_| MathError::Overflow)
+}
+
+/// The new total raised after adding `amount`, if it fits under `cap`.
+pub fn add_contribution(total_raised: u64, amount: u64, cap: u64) -> Result<u64, MathError> {
+    let total_raised = total_raised
+        .checked_add(amount)
+        .ok_or(MathError::Overflow)?;
+    if total_raised > cap {
+        return Err(MathError::CapExceeded);
+    }
+    Ok(total_raised)
+}
+
+pub fn remaining_cap(cap: u64, total_raised: u64) -> u64 {
+    cap.saturating_sub(total_raised)
+}
+
+/// What investors have been paid so far as a multiple of what they put in,
+/// in basis points (10_000 = 1x).
+pub fn returned_multiple_bps(total_distributed: u64, total_raised: u64) -> u128 {
+    if total_raised == 0 {
+        0
+    } else {
+        (total_distributed as u128) * BPS_DENOMINATOR as u128 / (total_raised as u128)
+    }
+}
+
+/// The investor share for a report made at `now`: the last tier whose
+/// conditions are met, or `base_bps` if none are.
+pub fn current_return_bps<I>(
+    base_bps: u16,
+    tiers: I,
+    total_distributed: u64,
+    total_raised: u64,
+    now: i64,
+) -> u16
+where
+    I: IntoIterator<Item = Tier>,
+    I::IntoIter: DoubleEndedIterator,
+{
+    let returned_bps = returned_multiple_bps(total_distributed, total_raised);
+    tiers
+        .into_iter()
+        .rev()
+        .find(|tier| returned_bps >= tier.returned_multiple_bps as u128 && now >= tier.starts_at)
+        .map_or(base_bps, |tier| tier.rate_bps)
+}
+
+/// Linear vesting of `total` over `duration` seconds from `start`, with
+/// nothing released before `start + cliff`.
+pub fn vested_amount(total: u64, start: i64, cliff: i64, duration: i64, now: i64) -> u64 {
+    let elapsed = now.saturating_sub(start);
+    if elapsed < cliff.max(0) {
+        return 0;
+    }
+    if duration <= 0 || elapsed >= duration {
+        return total;
+    }
+    ((total as u128) * (elapsed as u128) / (duration as u128)) as u64
+}
@@ -0,0 +1,157 @@
+use meraki_core::*;
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn invest_split_conserves_the_amount(total in any::<u64>()) {
+        let (fee, rest) = invest_split(total);
+        prop_assert_eq!(fee + rest, total);
+        prop_assert!(fee <= total / 200);
+    }
+
+    #[test]
+    fn revenue_split_conserves_the_amount(
+        revenue in any::<u64>(),
+        bps in 0..=MAX_INVESTOR_RETURN_BPS,
+    ) {
+        let split = revenue_split(revenue, bps).unwrap();
+        prop_assert_eq!(
+            split.fee as u128 + split.investor_share as u128 + split.startup_share as u128,
+            revenue as u128
+        );
+        prop_assert_eq!(split.fee, revenue / 200);
+        prop_assert!(split.investor_share as u128 <= revenue as u128 * bps as u128 / 10_000);
+    }
+
+    #[test]
+    fn pro_rata_distributes_exactly(
+        total in any::<u64>(),
+        weights in prop::collection::vec(1u64..1_000_000_000_000, 1..20),
+    ) {
+        let weight_sum: u64 = weights.iter().sum();
+        let slices = pro_rata(total, &weights, weight_sum).unwrap();
+        prop_assert_eq!(slices.len(), weights.len());
+        prop_assert_eq!(slices.iter().map(|s| *s as u128).sum::<u128>(), total as u128);
+
+        // Only the first slice can exceed its floored share, by less than
+        // one unit per entry.
+        for (i, (slice, weight)) in slices.iter().zip(&weights).enumerate() {
+            let floor = share_of(total, *weight, weight_sum).unwrap();
+            if i == 0 {
+                prop_assert!(*slice >= floor);
+                prop_assert!(*slice - floor < weights.len() as u64);
+            } else {
+                prop_assert_eq!(*slice, floor);
+            }
+        }
+    }
+
+    #[test]
+    fn shares_scale_with_contribution(
+        cap in 1u64..=u64::MAX,
+        equity_bps in 0u16..=10_000,
+        a in any::<u64>(),
+        b in any::<u64>(),
+    ) {
+        let (small, large) = (a.min(b).min(cap), a.max(b).min(cap));
+        let small_shares = shares_for_contribution(small, cap, equity_bps).unwrap();
+        let large_shares = shares_for_contribution(large, cap, equity_bps).unwrap();
+        prop_assert!(small_shares <= large_shares);
+
+        // The whole cap buys exactly the equity on offer.
+        let full = shares_for_contribution(cap, cap, equity_bps).unwrap();
+        prop_assert_eq!(full, TOTAL_SHARE_SUPPLY / 10_000 * equity_bps as u64);
+        prop_assert!(large_shares <= full);
+    }
+
+    #[test]
+    fn contributions_never_exceed_the_cap(
+        cap in any::<u64>(),
+        raised in any::<u64>(),
+        amount in any::<u64>(),
+    ) {
+        match add_contribution(raised, amount, cap) {
+            Ok(total) => {
+                prop_assert_eq!(total, raised + amount);
+                prop_assert!(total <= cap);
+                prop_assert_eq!(remaining_cap(cap, total), cap - total);
+            }
+            Err(MathError::CapExceeded) => {
+                prop_assert!(raised as u128 + amount as u128 > cap as u128);
+            }
+            Err(MathError::Overflow) => {
+                prop_assert!(raised.checked_add(amount).is_none());
+            }
+            Err(other) => prop_assert!(false, "unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn current_return_bps_picks_the_last_reached_tier(
+        base in 0..=MAX_INVESTOR_RETURN_BPS,
+        distributed in any::<u64>(),
+        raised in 1u64..,
+        now in any::<i64>(),
+        tiers in prop::collection::vec(
+            (0u32..40_000, any::<i64>(), 0..=MAX_INVESTOR_RETURN_BPS),
+            0..4,
+        ),
+    ) {
+        let tiers: Vec<Tier> = tiers
+            .into_iter()
+            .map(|(returned_multiple_bps, starts_at, rate_bps)| Tier {
+                returned_multiple_bps,
+                starts_at,
+                rate_bps,
+            })
+            .collect();
+        let rate = current_return_bps(base, tiers.clone(), distributed, raised, now);
+
+        let returned = returned_multiple_bps(distributed, raised);
+        let expected = tiers
+            .iter()
+            .rfind(|t| returned >= t.returned_multiple_bps as u128 && now >= t.starts_at)
+            .map_or(base, |t| t.rate_bps);
+        prop_assert_eq!(rate, expected);
+    }
+
+    #[test]
+    fn vesting_is_monotonic_and_bounded(
+        total in any::<u64>(),
+        start in -1_000_000_000i64..1_000_000_000,
+        cliff in 0i64..10_000_000,
+        duration in 1i64..100_000_000,
+        t1 in -1_000_000_000i64..2_000_000_000,
+        t2 in -1_000_000_000i64..2_000_000_000,
+    ) {
+        let (earlier, later) = (t1.min(t2), t1.max(t2));
+        let vested_earlier = vested_amount(total, start, cliff, duration, earlier);
+        let vested_later = vested_amount(total, start, cliff, duration, later);
+        prop_assert!(vested_earlier <= vested_later);
+        prop_assert!(vested_later <= total);
+
+        prop_assert_eq!(vested_amount(total, start, cliff, duration, start - 1), 0);
+        prop_assert_eq!(
+            vested_amount(total, start, cliff, duration, start + cliff.max(duration)),
+            total
+        );
+    }
+}
+
+#[test]
+fn small_reports_carry_no_fee() {
+    assert_eq!(
+        revenue_split(199, 1_000).unwrap(),
+        RevenueSplit {
+            fee: 0,
+            investor_share: 19,
+            startup_share: 180,
+        }
+    );
+    assert_eq!(invest_split(200), (1, 199));
+}
+
+#[test]
+fn pro_rata_rejects_empty_weights() {
+    assert_eq!(pro_rata(10, &[], 0), Err(MathError::ZeroWeight));
+}
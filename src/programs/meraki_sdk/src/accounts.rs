@@ -0,0 +1,43 @@
+//! Decoding of raw account data fetched over RPC.
+
+use anchor_lang::{AccountDeserialize, Discriminator};
+use meraki_contract::{Amendment, Attestation, Config, Delegate, InvestmentContract, Position};
+
+use crate::SdkError;
+
+/// Decodes an account of a known type, checking its discriminator.
+pub fn decode_account<T: AccountDeserialize>(data: &[u8]) -> Result<T, SdkError> {
+    T::try_deserialize(&mut &data[..]).map_err(|e| SdkError::Deserialize(e.to_string()))
+}
+
+/// Any account owned by the program.
+pub enum MerakiAccount {
+    InvestmentContract(InvestmentContract),
+    Position(Position),
+    Amendment(Amendment),
+    Config(Config),
+    Attestation(Attestation),
+    Delegate(Delegate),
+}
+
+impl MerakiAccount {
+    /// Decodes program-owned account data whose type isn't known up front,
+    /// e.g. from `getProgramAccounts`.
+    pub fn decode(data: &[u8]) -> Result<Self, SdkError> {
+        if data.starts_with(InvestmentContract::DISCRIMINATOR) {
+            decode_account(data).map(MerakiAccount::InvestmentContract)
+        } else if data.starts_with(Position::DISCRIMINATOR) {
+            decode_account(data).map(MerakiAccount::Position)
+        } else if data.starts_with(Amendment::DISCRIMINATOR) {
+            decode_account(data).map(MerakiAccount::Amendment)
+        } else if data.starts_with(Config::DISCRIMINATOR) {
+            decode_account(data).map(MerakiAccount::Config)
+        } else if data.starts_with(Attestation::DISCRIMINATOR) {
+            decode_account(data).map(MerakiAccount::Attestation)
+        } else if data.starts_with(Delegate::DISCRIMINATOR) {
+            decode_account(data).map(MerakiAccount::Delegate)
+        } else {
+            Err(SdkError::UnknownDiscriminator)
+        }
+    }
+}
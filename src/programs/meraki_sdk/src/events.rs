@@ -0,0 +1,92 @@
+//! Decoding of the events `emit!` writes to transaction logs as
+//! `Program data: <base64>` lines.
+
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use meraki_contract::{
+    CollateralSlashed, ContractClosed, ContractDefaulted, ContractInitialized, FeesWithdrawn,
+    TrancheWithdrawn, ID,
+};
+
+use crate::SdkError;
+
+/// Any event emitted by the program.
+pub enum MerakiEvent {
+    ContractInitialized(ContractInitialized),
+    ContractDefaulted(ContractDefaulted),
+    CollateralSlashed(CollateralSlashed),
+    ContractClosed(ContractClosed),
+    FeesWithdrawn(FeesWithdrawn),
+    TrancheWithdrawn(TrancheWithdrawn),
+}
+
+impl MerakiEvent {
+    /// Decodes one event from its discriminator-prefixed bytes.
+    pub fn decode(data: &[u8]) -> Result<Self, SdkError> {
+        if let Some(rest) = data.strip_prefix(ContractInitialized::DISCRIMINATOR) {
+            deserialize(rest).map(MerakiEvent::ContractInitialized)
+        } else if let Some(rest) = data.strip_prefix(ContractDefaulted::DISCRIMINATOR) {
+            deserialize(rest).map(MerakiEvent::ContractDefaulted)
+        } else if let Some(rest) = data.strip_prefix(CollateralSlashed::DISCRIMINATOR) {
+            deserialize(rest).map(MerakiEvent::CollateralSlashed)
+        } else if let Some(rest) = data.strip_prefix(ContractClosed::DISCRIMINATOR) {
+            deserialize(rest).map(MerakiEvent::ContractClosed)
+        } else if let Some(rest) = data.strip_prefix(FeesWithdrawn::DISCRIMINATOR) {
+            deserialize(rest).map(MerakiEvent::FeesWithdrawn)
+        } else if let Some(rest) = data.strip_prefix(TrancheWithdrawn::DISCRIMINATOR) {
+            deserialize(rest).map(MerakiEvent::TrancheWithdrawn)
+        } else {
+            Err(SdkError::UnknownDiscriminator)
+        }
+    }
+}
+
+fn deserialize<T: AnchorDeserialize>(mut data: &[u8]) -> Result<T, SdkError> {
+    T::deserialize(&mut data).map_err(|e| SdkError::Deserialize(e.to_string()))
+}
+
+/// Decodes every Meraki event in a transaction's log messages, in order.
+///
+/// Only `Program data:` lines written while the Meraki program is the one
+/// executing are considered, so data logged by other programs (including
+/// ones it calls through CPI) is skipped.
+pub fn decode_events(logs: &[String]) -> Result<Vec<MerakiEvent>, SdkError> {
+    let program_id = ID.to_string();
+    let mut call_stack: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+
+    for line in logs {
+        if let Some(data) = line.strip_prefix("Program data: ") {
+            if call_stack.last() != Some(&program_id.as_str()) {
+                continue;
+            }
+            let bytes = STANDARD
+                .decode(data.split_whitespace().next().unwrap_or_default())
+                .map_err(|_| SdkError::InvalidLog(line.clone()))?;
+            match MerakiEvent::decode(&bytes) {
+                Ok(event) => events.push(event),
+                // Raw `sol_log_data` output that isn't one of our events.
+                Err(SdkError::UnknownDiscriminator) => {}
+                Err(e) => return Err(e),
+            }
+        } else if let Some(rest) = line.strip_prefix("Program ") {
+            let mut words = rest.split_whitespace();
+            let (Some(program), Some(action)) = (words.next(), words.next()) else {
+                continue;
+            };
+            // `Program log:` / `Program return:` lines, not invocation markers.
+            if program.ends_with(':') {
+                continue;
+            }
+            match action {
+                "invoke" => call_stack.push(program),
+                "success" | "failed:" => {
+                    call_stack.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(events)
+}
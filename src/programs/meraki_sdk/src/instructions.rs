@@ -0,0 +1,712 @@
+//! `Instruction` builders for every program entry point. Accounts the
+//! program derives itself (PDAs, associated token accounts, programs) are
+//! filled in here, so callers only pass the keys that vary.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{get_associated_token_address, spl_associated_token_account};
+use anchor_spl::token::spl_token;
+use meraki_contract::{accounts, instruction, AllowlistMode, ReturnTier, ID};
+
+use crate::pda;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Arguments to `initialize_contract`.
+pub struct ContractTerms {
+    pub amount: u64,
+    pub investor_return_bps: u16,
+    pub duration_days: u64,
+    pub is_native: bool,
+    pub equity_offered_bps: u16,
+    pub allowlist: AllowlistMode,
+    pub return_tiers: Vec<ReturnTier>,
+    pub collateral_amount: u64,
+    pub terms_hash: [u8; 32],
+    pub metadata_uri: String,
+}
+
+pub fn initialize_contract(
+    contract: &Pubkey,
+    investor: &Pubkey,
+    startup: &Pubkey,
+    mint: Option<Pubkey>,
+    terms: ContractTerms,
+) -> Instruction {
+    let mut instruction = ix(
+        accounts::InitializeContract {
+            investment_contract: *contract,
+            investor: *investor,
+            startup: *startup,
+            mint,
+            collateral: pda::collateral(contract),
+            vault: pda::vault(contract),
+            share_mint: pda::share_mint(contract),
+            token_program: spl_token::ID,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::InitializeContract {
+            amount: terms.amount,
+            investor_return_bps: terms.investor_return_bps,
+            duration_days: terms.duration_days,
+            is_native: terms.is_native,
+            equity_offered_bps: terms.equity_offered_bps,
+            allowlist: terms.allowlist,
+            return_tiers: terms.return_tiers,
+            collateral_amount: terms.collateral_amount,
+            terms_hash: terms.terms_hash,
+            metadata_uri: terms.metadata_uri,
+        },
+    );
+    // The startup only signs when it posts collateral.
+    if terms.collateral_amount > 0 {
+        set_signer(&mut instruction, startup);
+    }
+    instruction
+}
+
+pub fn initialize_token_vault(contract: &Pubkey, investor: &Pubkey, mint: &Pubkey) -> Instruction {
+    ix(
+        accounts::InitializeTokenVault {
+            investment_contract: *contract,
+            investor: *investor,
+            mint: *mint,
+            vault: pda::vault(contract),
+            token_vault: pda::token_vault(contract),
+            token_program: spl_token::ID,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::InitializeTokenVault {},
+    )
+}
+
+/// Marks `key` as a signer wherever it appears in `instruction`, for accounts
+/// the program only requires to sign in some cases.
+pub fn set_signer(instruction: &mut Instruction, key: &Pubkey) {
+    for meta in instruction.accounts.iter_mut().filter(|m| m.pubkey == *key) {
+        meta.is_signer = true;
+    }
+}
+
+pub fn invest_native(
+    contract: &Pubkey,
+    investor: &Pubkey,
+    attestation: Option<Pubkey>,
+    amount: u64,
+) -> Instruction {
+    let share_mint = pda::share_mint(contract);
+    ix(
+        accounts::InvestNative {
+            investment_contract: *contract,
+            investor: *investor,
+            attestation,
+            config: pda::config(),
+            position: pda::position(contract, investor),
+            vault: pda::vault(contract),
+            treasury: pda::treasury(&Pubkey::default()),
+            share_mint,
+            investor_share_account: get_associated_token_address(investor, &share_mint),
+            token_program: spl_token::ID,
+            associated_token_program: spl_associated_token_account::ID,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::InvestNative { amount },
+    )
+}
+
+/// Token accounts used by `invest` on an SPL token contract.
+pub struct TokenInvest {
+    pub mint: Pubkey,
+    pub investor_token_account: Pubkey,
+    /// Required when the contract has an allowlist.
+    pub attestation: Option<Pubkey>,
+}
+
+pub fn invest(
+    contract: &Pubkey,
+    investor: &Pubkey,
+    accounts: &TokenInvest,
+    amount: u64,
+) -> Instruction {
+    let share_mint = pda::share_mint(contract);
+    ix(
+        accounts::Invest {
+            investment_contract: *contract,
+            investor: *investor,
+            investor_token_account: accounts.investor_token_account,
+            attestation: accounts.attestation,
+            config: pda::config(),
+            position: pda::position(contract, investor),
+            token_vault: pda::token_vault(contract),
+            treasury: pda::treasury(&accounts.mint),
+            vault: pda::vault(contract),
+            share_mint,
+            investor_share_account: get_associated_token_address(investor, &share_mint),
+            token_program: spl_token::ID,
+            associated_token_program: spl_associated_token_account::ID,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::Invest { amount },
+    )
+}
+
+/// Token accounts used by `record_revenue` and `record_revenue_batch`.
+pub struct TokenRevenue {
+    pub mint: Pubkey,
+    /// Pays the revenue in; owned by the signer.
+    pub payer_token_account: Pubkey,
+    pub startup_token_account: Pubkey,
+}
+
+// Record-revenue and tranche builders take `as_delegate`: when the signer is
+// a delegate rather than the startup, its `Delegate` account is passed along.
+pub fn record_revenue(
+    contract: &Pubkey,
+    investor: &Pubkey,
+    payer: &Pubkey,
+    accounts: &TokenRevenue,
+    investor_token_account: &Pubkey,
+    revenue_amount: u64,
+    as_delegate: bool,
+) -> Instruction {
+    ix(
+        accounts::RecordRevenue {
+            investment_contract: *contract,
+            payer: *payer,
+            delegate: as_delegate.then(|| pda::delegate(contract, payer)),
+            position: pda::position(contract, investor),
+            payer_token_account: accounts.payer_token_account,
+            startup_token_account: accounts.startup_token_account,
+            investor_token_account: *investor_token_account,
+            treasury: pda::treasury(&accounts.mint),
+            token_program: spl_token::ID,
+        },
+        instruction::RecordRevenue { revenue_amount },
+    )
+}
+
+// `recipients` are `(investor, investor_token_account)` pairs; they are sorted
+// by investor key here as the program requires.
+pub fn record_revenue_batch(
+    contract: &Pubkey,
+    payer: &Pubkey,
+    accounts: &TokenRevenue,
+    recipients: &[(Pubkey, Pubkey)],
+    revenue_amount: u64,
+    as_delegate: bool,
+) -> Instruction {
+    let mut instruction = ix(
+        accounts::RecordRevenueBatch {
+            investment_contract: *contract,
+            payer: *payer,
+            delegate: as_delegate.then(|| pda::delegate(contract, payer)),
+            payer_token_account: accounts.payer_token_account,
+            startup_token_account: accounts.startup_token_account,
+            treasury: pda::treasury(&accounts.mint),
+            token_program: spl_token::ID,
+        },
+        instruction::RecordRevenueBatch { revenue_amount },
+    );
+    instruction
+        .accounts
+        .extend(position_pairs(contract, recipients));
+    instruction
+}
+
+fn position_pairs(contract: &Pubkey, recipients: &[(Pubkey, Pubkey)]) -> Vec<AccountMeta> {
+    let mut recipients = recipients.to_vec();
+    recipients.sort_by_key(|(investor, _)| investor.to_bytes());
+    recipients
+        .iter()
+        .flat_map(|(investor, recipient)| {
+            [
+                AccountMeta::new_readonly(pda::position(contract, investor), false),
+                AccountMeta::new(*recipient, false),
+            ]
+        })
+        .collect()
+}
+
+pub fn mint_investment_nft(
+    mint: &Pubkey,
+    investor_token_account: &Pubkey,
+    mint_authority: &Pubkey,
+) -> Instruction {
+    let mut instruction = ix(
+        accounts::MintNFT {
+            mint: *mint,
+            investor_token_account: *investor_token_account,
+            mint_authority: *mint_authority,
+            token_program: spl_token::ID,
+        },
+        instruction::MintInvestmentNft {},
+    );
+    set_signer(&mut instruction, mint_authority);
+    instruction
+}
+
+// Native positions are paid straight to the investor's wallet, so every
+// position is paired with its own investor.
+pub fn record_revenue_native(
+    contract: &Pubkey,
+    payer: &Pubkey,
+    startup: &Pubkey,
+    investors: &[Pubkey],
+    revenue_amount: u64,
+    as_delegate: bool,
+) -> Instruction {
+    let mut instruction = ix(
+        accounts::RecordRevenueNative {
+            investment_contract: *contract,
+            payer: *payer,
+            delegate: as_delegate.then(|| pda::delegate(contract, payer)),
+            startup: *startup,
+            treasury: pda::treasury(&Pubkey::default()),
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::RecordRevenueNative { revenue_amount },
+    );
+    let recipients: Vec<(Pubkey, Pubkey)> = investors.iter().map(|i| (*i, *i)).collect();
+    instruction
+        .accounts
+        .extend(position_pairs(contract, &recipients));
+    instruction
+}
+
+pub fn refund_native(contract: &Pubkey, startup: &Pubkey, investor: &Pubkey) -> Instruction {
+    ix(
+        accounts::RefundNative {
+            investment_contract: *contract,
+            startup: *startup,
+            position: pda::position(contract, investor),
+            investor: *investor,
+            vault: pda::vault(contract),
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::RefundNative {},
+    )
+}
+
+pub fn withdraw_tranche(
+    contract: &Pubkey,
+    authority: &Pubkey,
+    startup_token_account: &Pubkey,
+    amount: u64,
+    as_delegate: bool,
+) -> Instruction {
+    ix(
+        accounts::WithdrawTranche {
+            investment_contract: *contract,
+            authority: *authority,
+            delegate: as_delegate.then(|| pda::delegate(contract, authority)),
+            vault: pda::vault(contract),
+            token_vault: pda::token_vault(contract),
+            startup_token_account: *startup_token_account,
+            token_program: spl_token::ID,
+        },
+        instruction::WithdrawTranche { amount },
+    )
+}
+
+pub fn withdraw_tranche_native(
+    contract: &Pubkey,
+    authority: &Pubkey,
+    startup: &Pubkey,
+    amount: u64,
+    as_delegate: bool,
+) -> Instruction {
+    ix(
+        accounts::WithdrawTrancheNative {
+            investment_contract: *contract,
+            authority: *authority,
+            delegate: as_delegate.then(|| pda::delegate(contract, authority)),
+            vault: pda::vault(contract),
+            startup: *startup,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::WithdrawTrancheNative { amount },
+    )
+}
+
+pub fn add_delegate(
+    contract: &Pubkey,
+    startup: &Pubkey,
+    authority: &Pubkey,
+    permissions: u8,
+) -> Instruction {
+    ix(
+        accounts::AddDelegate {
+            investment_contract: *contract,
+            startup: *startup,
+            delegate: pda::delegate(contract, authority),
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::AddDelegate {
+            authority: *authority,
+            permissions,
+        },
+    )
+}
+
+pub fn revoke_delegate(contract: &Pubkey, startup: &Pubkey, authority: &Pubkey) -> Instruction {
+    ix(
+        accounts::RevokeDelegate {
+            investment_contract: *contract,
+            startup: *startup,
+            delegate: pda::delegate(contract, authority),
+        },
+        instruction::RevokeDelegate {},
+    )
+}
+
+pub fn mark_defaulted(contract: &Pubkey, signer: &Pubkey) -> Instruction {
+    ix(
+        accounts::MarkDefaulted {
+            investment_contract: *contract,
+            config: pda::config(),
+            signer: *signer,
+        },
+        instruction::MarkDefaulted {},
+    )
+}
+
+// `investors` are the wallets the collateral is paid back to.
+pub fn slash_collateral(contract: &Pubkey, startup: &Pubkey, investors: &[Pubkey]) -> Instruction {
+    let mut instruction = ix(
+        settle_collateral_accounts(contract, startup),
+        instruction::SlashCollateral {},
+    );
+    let recipients: Vec<_> = investors
+        .iter()
+        .map(|investor| (*investor, *investor))
+        .collect();
+    instruction
+        .accounts
+        .extend(position_pairs(contract, &recipients));
+    instruction
+}
+
+pub fn complete_contract(contract: &Pubkey, startup: &Pubkey) -> Instruction {
+    ix(
+        return_collateral_accounts(contract, startup),
+        instruction::CompleteContract {},
+    )
+}
+
+pub fn release_collateral(contract: &Pubkey, startup: &Pubkey) -> Instruction {
+    ix(
+        return_collateral_accounts(contract, startup),
+        instruction::ReleaseCollateral {},
+    )
+}
+
+pub fn refund_defaulted_native(contract: &Pubkey, investor: &Pubkey) -> Instruction {
+    ix(
+        accounts::RefundDefaultedNative {
+            investment_contract: *contract,
+            position: pda::position(contract, investor),
+            investor: *investor,
+            vault: pda::vault(contract),
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::RefundDefaultedNative {},
+    )
+}
+
+pub fn refund_defaulted(
+    contract: &Pubkey,
+    investor: &Pubkey,
+    investor_token_account: &Pubkey,
+) -> Instruction {
+    ix(
+        accounts::RefundDefaulted {
+            investment_contract: *contract,
+            position: pda::position(contract, investor),
+            vault: pda::vault(contract),
+            token_vault: pda::token_vault(contract),
+            investor_token_account: *investor_token_account,
+            token_program: spl_token::ID,
+        },
+        instruction::RefundDefaulted {},
+    )
+}
+
+fn return_collateral_accounts(contract: &Pubkey, startup: &Pubkey) -> accounts::ReturnCollateral {
+    accounts::ReturnCollateral {
+        investment_contract: *contract,
+        collateral: pda::collateral(contract),
+        startup: *startup,
+        system_program: anchor_lang::system_program::ID,
+    }
+}
+
+fn settle_collateral_accounts(contract: &Pubkey, startup: &Pubkey) -> accounts::SettleCollateral {
+    accounts::SettleCollateral {
+        investment_contract: *contract,
+        collateral: pda::collateral(contract),
+        startup: *startup,
+        system_program: anchor_lang::system_program::ID,
+    }
+}
+
+pub fn close_contract(contract: &Pubkey, investor: &Pubkey, is_native: bool) -> Instruction {
+    ix(
+        accounts::CloseContract {
+            investment_contract: *contract,
+            investor: *investor,
+            vault: pda::vault(contract),
+            token_vault: (!is_native).then(|| pda::token_vault(contract)),
+            token_program: spl_token::ID,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::CloseContract {},
+    )
+}
+
+pub fn propose_amendment(
+    contract: &Pubkey,
+    proposer: &Pubkey,
+    investor_return_bps: Option<u16>,
+    duration_days: Option<u64>,
+    expires_in_seconds: i64,
+) -> Instruction {
+    ix(
+        accounts::ProposeAmendment {
+            investment_contract: *contract,
+            amendment: pda::amendment(contract),
+            proposer: *proposer,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::ProposeAmendment {
+            investor_return_bps,
+            duration_days,
+            expires_in_seconds,
+        },
+    )
+}
+
+pub fn accept_amendment(contract: &Pubkey, signer: &Pubkey, proposer: &Pubkey) -> Instruction {
+    ix(
+        accounts::AcceptAmendment {
+            investment_contract: *contract,
+            amendment: pda::amendment(contract),
+            signer: *signer,
+            proposer: *proposer,
+        },
+        instruction::AcceptAmendment {},
+    )
+}
+
+pub fn cancel_amendment(contract: &Pubkey, signer: &Pubkey, proposer: &Pubkey) -> Instruction {
+    ix(
+        accounts::CancelAmendment {
+            investment_contract: *contract,
+            amendment: pda::amendment(contract),
+            signer: *signer,
+            proposer: *proposer,
+        },
+        instruction::CancelAmendment {},
+    )
+}
+
+pub fn initialize_config(admin: &Pubkey, compliance_authority: &Pubkey) -> Instruction {
+    ix(
+        accounts::InitializeConfig {
+            config: pda::config(),
+            admin: *admin,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::InitializeConfig {
+            compliance_authority: *compliance_authority,
+        },
+    )
+}
+
+fn update_config_accounts(admin: &Pubkey) -> accounts::UpdateConfig {
+    accounts::UpdateConfig {
+        config: pda::config(),
+        admin: *admin,
+    }
+}
+
+pub fn set_fee_split(
+    admin: &Pubkey,
+    operations_wallet: &Pubkey,
+    reserve_wallet: &Pubkey,
+    reserve_bps: u16,
+) -> Instruction {
+    ix(
+        update_config_accounts(admin),
+        instruction::SetFeeSplit {
+            operations_wallet: *operations_wallet,
+            reserve_wallet: *reserve_wallet,
+            reserve_bps,
+        },
+    )
+}
+
+pub fn set_compliance_authority(admin: &Pubkey, compliance_authority: &Pubkey) -> Instruction {
+    ix(
+        update_config_accounts(admin),
+        instruction::SetComplianceAuthority {
+            compliance_authority: *compliance_authority,
+        },
+    )
+}
+
+pub fn set_arbiter(admin: &Pubkey, arbiter: &Pubkey) -> Instruction {
+    ix(
+        update_config_accounts(admin),
+        instruction::SetArbiter { arbiter: *arbiter },
+    )
+}
+
+pub fn initialize_treasury(admin: &Pubkey, mint: &Pubkey) -> Instruction {
+    ix(
+        accounts::InitializeTreasury {
+            config: pda::config(),
+            admin: *admin,
+            mint: *mint,
+            treasury: pda::treasury(mint),
+            token_program: spl_token::ID,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::InitializeTreasury {},
+    )
+}
+
+pub fn initialize_native_treasury(admin: &Pubkey) -> Instruction {
+    ix(
+        accounts::InitializeNativeTreasury {
+            config: pda::config(),
+            admin: *admin,
+            treasury: pda::treasury(&Pubkey::default()),
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::InitializeNativeTreasury {},
+    )
+}
+
+pub fn withdraw_fees(
+    admin: &Pubkey,
+    mint: &Pubkey,
+    operations_token_account: &Pubkey,
+    reserve_token_account: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    ix(
+        accounts::WithdrawFees {
+            config: pda::config(),
+            admin: *admin,
+            mint: *mint,
+            treasury: pda::treasury(mint),
+            operations_token_account: *operations_token_account,
+            reserve_token_account: *reserve_token_account,
+            token_program: spl_token::ID,
+        },
+        instruction::WithdrawFees { amount },
+    )
+}
+
+pub fn withdraw_native_fees(
+    admin: &Pubkey,
+    operations_wallet: &Pubkey,
+    reserve_wallet: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    ix(
+        accounts::WithdrawNativeFees {
+            config: pda::config(),
+            admin: *admin,
+            treasury: pda::treasury(&Pubkey::default()),
+            operations_wallet: *operations_wallet,
+            reserve_wallet: *reserve_wallet,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::WithdrawNativeFees { amount },
+    )
+}
+
+pub fn issue_attestation(
+    compliance_authority: &Pubkey,
+    scope: &Pubkey,
+    investor: &Pubkey,
+    expires_at: i64,
+) -> Instruction {
+    ix(
+        accounts::IssueAttestation {
+            config: pda::config(),
+            attestation: pda::attestation(scope, investor),
+            compliance_authority: *compliance_authority,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::IssueAttestation {
+            scope: *scope,
+            investor: *investor,
+            expires_at,
+        },
+    )
+}
+
+pub fn revoke_attestation(
+    compliance_authority: &Pubkey,
+    scope: &Pubkey,
+    investor: &Pubkey,
+) -> Instruction {
+    ix(
+        accounts::RevokeAttestation {
+            config: pda::config(),
+            attestation: pda::attestation(scope, investor),
+            compliance_authority: *compliance_authority,
+        },
+        instruction::RevokeAttestation {},
+    )
+}
+
+// The view instructions below change nothing; simulate them and decode the
+// transaction's return data.
+
+pub fn remaining_cap(contract: &Pubkey) -> Instruction {
+    ix(
+        accounts::ViewContract {
+            investment_contract: *contract,
+        },
+        instruction::RemainingCap {},
+    )
+}
+
+pub fn preview_revenue(contract: &Pubkey, revenue_amount: u64) -> Instruction {
+    ix(
+        accounts::ViewContract {
+            investment_contract: *contract,
+        },
+        instruction::PreviewRevenue { revenue_amount },
+    )
+}
+
+pub fn position_view(contract: &Pubkey, investor: &Pubkey) -> Instruction {
+    ix(
+        accounts::ViewPosition {
+            investment_contract: *contract,
+            position: pda::position(contract, investor),
+        },
+        instruction::PositionView {},
+    )
+}
+
+pub fn position_payout(contract: &Pubkey, investor: &Pubkey, revenue_amount: u64) -> Instruction {
+    ix(
+        accounts::ViewPosition {
+            investment_contract: *contract,
+            position: pda::position(contract, investor),
+        },
+        instruction::PositionPayout { revenue_amount },
+    )
+}
@@ -0,0 +1,51 @@
+//! Decoding of `invest`/`invest_native` calls, without depending on the
+//! program crate.
+
+use crate::{Pubkey, SdkError};
+
+/// Anchor discriminators, `sha256("global:<name>")[..8]`, of the two invest
+/// entry points.
+pub const INVEST_DISCRIMINATOR: [u8; 8] = [13, 245, 180, 103, 254, 182, 121, 4];
+pub const INVEST_NATIVE_DISCRIMINATOR: [u8; 8] = [100, 199, 182, 27, 200, 79, 150, 73];
+
+/// An `invest` or `invest_native` call, decoded from a transaction.
+pub struct DecodedInvest {
+    pub contract: Pubkey,
+    pub investor: Pubkey,
+    pub amount: u64,
+    pub is_native: bool,
+}
+
+/// Decodes an `invest`/`invest_native` instruction from its data and the keys
+/// of the accounts it was called with, in order. Any other instruction
+/// decodes to `None`.
+pub fn decode_invest(data: &[u8], accounts: &[Pubkey]) -> Result<Option<DecodedInvest>, SdkError> {
+    let (args, is_native) = if let Some(rest) = data.strip_prefix(&INVEST_DISCRIMINATOR) {
+        (rest, false)
+    } else if let Some(rest) = data.strip_prefix(&INVEST_NATIVE_DISCRIMINATOR) {
+        (rest, true)
+    } else {
+        return Ok(None);
+    };
+    // Both take the amount, a borsh `u64`, as their only argument, and the
+    // contract and the investor as their first two accounts.
+    let amount = args
+        .get(..8)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(SdkError::Deserialize(
+            "invest instruction is missing its amount".to_string(),
+        ))?;
+    let [contract, investor, ..] = accounts else {
+        return Err(SdkError::Deserialize(
+            "invest instruction is missing its accounts".to_string(),
+        ));
+    };
+
+    Ok(Some(DecodedInvest {
+        contract: *contract,
+        investor: *investor,
+        amount,
+        is_native,
+    }))
+}
@@ -0,0 +1,60 @@
+//! Client-side helpers for the `meraki_contract` program: PDA derivation,
+//! instruction builders for every entry point, account decoding and event
+//! decoding from transaction logs. Off-chain code (the API server, scripts,
+//! CLIs) should go through this crate rather than hand-rolling byte layouts.
+//!
+//! The builders and decoders need the program crate and are behind the
+//! default `program` feature. With `default-features = false` only the
+//! program id, the PDAs and [`decode_invest`] are left, which is all the API
+//! server needs to verify investments.
+
+use std::fmt;
+
+#[cfg(feature = "program")]
+pub mod accounts;
+#[cfg(feature = "program")]
+pub mod events;
+#[cfg(feature = "program")]
+pub mod instructions;
+pub mod invest;
+pub mod pda;
+
+#[cfg(feature = "program")]
+pub use accounts::{decode_account, MerakiAccount};
+#[cfg(feature = "program")]
+pub use events::{decode_events, MerakiEvent};
+#[cfg(feature = "program")]
+pub use instructions::ContractTerms;
+pub use invest::{decode_invest, DecodedInvest};
+#[cfg(feature = "program")]
+pub use meraki_contract::{
+    self as program, AllowlistMode, Amendment, Attestation, Config, Delegate, InvestmentContract,
+    Position, PositionView, ReturnTier, RevenueSplitView, ALL_PERMISSIONS,
+    PERMISSION_REPORT_REVENUE, PERMISSION_WITHDRAW_TRANCHE,
+};
+pub use solana_pubkey::Pubkey;
+
+// Kept in step with `declare_id!` in the program; the `decode` tests check it.
+solana_pubkey::declare_id!("51jdU5SpLxidhessiSTiAe3uATxh7sSHn1WKvvVVDK74");
+
+#[derive(Debug)]
+pub enum SdkError {
+    /// The data does not start with the discriminator of any known type.
+    UnknownDiscriminator,
+    /// The discriminator matched but the rest of the data did not decode.
+    Deserialize(String),
+    /// A `Program data:` log line that is not valid base64.
+    InvalidLog(String),
+}
+
+impl fmt::Display for SdkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SdkError::UnknownDiscriminator => f.write_str("unknown account or event discriminator"),
+            SdkError::Deserialize(msg) => write!(f, "failed to deserialize: {}", msg),
+            SdkError::InvalidLog(line) => write!(f, "invalid program data log: {}", line),
+        }
+    }
+}
+
+impl std::error::Error for SdkError {}
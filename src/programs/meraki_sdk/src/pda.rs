@@ -0,0 +1,48 @@
+//! Program-derived addresses, with the same seeds the program checks.
+
+use crate::{Pubkey, ID};
+
+pub fn vault(contract: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"vault", contract.as_ref()], &ID).0
+}
+
+/// Token account holding invested tokens of an SPL token contract.
+pub fn token_vault(contract: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"token_vault", contract.as_ref()], &ID).0
+}
+
+pub fn share_mint(contract: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"share_mint", contract.as_ref()], &ID).0
+}
+
+pub fn collateral(contract: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"collateral", contract.as_ref()], &ID).0
+}
+
+pub fn position(contract: &Pubkey, investor: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"position", contract.as_ref(), investor.as_ref()], &ID).0
+}
+
+pub fn amendment(contract: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"amendment", contract.as_ref()], &ID).0
+}
+
+pub fn config() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &ID).0
+}
+
+/// Fee treasury for `mint`; `Pubkey::default()` is the native SOL treasury.
+pub fn treasury(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"treasury", mint.as_ref()], &ID).0
+}
+
+/// KYC attestation for `investor`; `scope` is a contract, or
+/// `Pubkey::default()` for the global allowlist.
+pub fn attestation(scope: &Pubkey, investor: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"attestation", scope.as_ref(), investor.as_ref()], &ID).0
+}
+
+/// Lets `authority` act for the startup of `contract`.
+pub fn delegate(contract: &Pubkey, authority: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"delegate", contract.as_ref(), authority.as_ref()], &ID).0
+}
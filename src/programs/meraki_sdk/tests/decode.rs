@@ -0,0 +1,124 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountSerialize, Event};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use meraki_contract::ContractDefaulted;
+use meraki_sdk::{
+    decode_account, decode_events, decode_invest, instructions, MerakiAccount, MerakiEvent,
+    Position, SdkError, ID,
+};
+
+#[test]
+fn program_id_matches_the_program() {
+    assert_eq!(ID, meraki_contract::ID);
+}
+
+fn position() -> Position {
+    Position {
+        contract: Pubkey::new_unique(),
+        investor: Pubkey::new_unique(),
+        contributed: 5_000,
+        shares: 42,
+        is_refunded: false,
+        bump: 254,
+    }
+}
+
+#[test]
+fn decodes_accounts_by_discriminator() {
+    let position = position();
+    let mut data = Vec::new();
+    position.try_serialize(&mut data).unwrap();
+
+    let decoded: Position = decode_account(&data).unwrap();
+    assert_eq!(decoded.investor, position.investor);
+    assert_eq!(decoded.shares, 42);
+
+    match MerakiAccount::decode(&data).unwrap() {
+        MerakiAccount::Position(decoded) => assert_eq!(decoded.contributed, 5_000),
+        _ => panic!("expected a position"),
+    }
+
+    assert!(matches!(
+        MerakiAccount::decode(&[0; 16]),
+        Err(SdkError::UnknownDiscriminator)
+    ));
+}
+
+#[test]
+fn decodes_only_events_logged_by_the_program() {
+    let contract = Pubkey::new_unique();
+    let event = ContractDefaulted {
+        contract,
+        marked_by: Pubkey::new_unique(),
+        by_arbiter: true,
+        timestamp: 1_700_000_000,
+    };
+    let data = format!("Program data: {}", STANDARD.encode(event.data()));
+    let token_program = anchor_spl::token::ID;
+    let logs = vec![
+        format!("Program {token_program} invoke [1]"),
+        data.clone(),
+        format!("Program {token_program} success"),
+        format!("Program {ID} invoke [1]"),
+        "Program log: Instruction: MarkDefaulted".to_string(),
+        format!("Program {token_program} invoke [2]"),
+        data.clone(),
+        format!("Program {token_program} success"),
+        data,
+        format!("Program {ID} consumed 5000 of 200000 compute units"),
+        format!("Program {ID} success"),
+    ];
+
+    let events = decode_events(&logs).unwrap();
+    assert_eq!(events.len(), 1);
+    match &events[0] {
+        MerakiEvent::ContractDefaulted(decoded) => {
+            assert_eq!(decoded.contract, contract);
+            assert!(decoded.by_arbiter);
+        }
+        _ => panic!("expected ContractDefaulted"),
+    }
+}
+
+#[test]
+fn decodes_invest_instructions() {
+    let contract = Pubkey::new_unique();
+    let investor = Pubkey::new_unique();
+    let keys = |ix: &anchor_lang::solana_program::instruction::Instruction| {
+        ix.accounts
+            .iter()
+            .map(|meta| meta.pubkey)
+            .collect::<Vec<_>>()
+    };
+
+    let ix = instructions::invest_native(&contract, &investor, None, 7_000);
+    let decoded = decode_invest(&ix.data, &keys(&ix)).unwrap().unwrap();
+    assert_eq!(decoded.contract, contract);
+    assert_eq!(decoded.investor, investor);
+    assert_eq!(decoded.amount, 7_000);
+    assert!(decoded.is_native);
+
+    let accounts = instructions::TokenInvest {
+        mint: Pubkey::new_unique(),
+        investor_token_account: Pubkey::new_unique(),
+        attestation: None,
+    };
+    let ix = instructions::invest(&contract, &investor, &accounts, 12);
+    let decoded = decode_invest(&ix.data, &keys(&ix)).unwrap().unwrap();
+    assert_eq!(decoded.amount, 12);
+    assert!(!decoded.is_native);
+
+    // Other instructions aren't investments...
+    let ix = instructions::refund_native(&contract, &Pubkey::new_unique(), &investor);
+    assert!(decode_invest(&ix.data, &keys(&ix)).unwrap().is_none());
+    // ...and an invest call without its accounts is malformed.
+    let ix = instructions::invest_native(&contract, &investor, None, 1);
+    assert!(matches!(
+        decode_invest(&ix.data, &[contract]),
+        Err(SdkError::Deserialize(_))
+    ));
+    assert!(matches!(
+        decode_invest(&ix.data[..12], &keys(&ix)),
+        Err(SdkError::Deserialize(_))
+    ));
+}
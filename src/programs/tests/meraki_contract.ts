@@ -25,15 +25,17 @@ describe(" Meraki Contract — Teste Completo", () => {
 
     
     const amount = new anchor.BN(_000_000_000); 
-    const investorReturnPercent = 0; 
+    const investorReturnBps = 1_000; // 10%
     const durationDays = new anchor.BN(0); 
+    const equityOfferedBps = 1_000; // 10% do equity
 
     const tx = await program.methods
-      .initializeContract(amount, investorReturnPercent, durationDays)
+      .initializeContract(amount, investorReturnBps, durationDays, true, equityOfferedBps, { open: {} }, [], new anchor.BN(0), Array(32).fill(0), "")
       .accounts({
         investmentContract: investmentContract.publicKey,
         investor: provider.wallet.publicKey,
         startup: startup.publicKey,
+        mint: null,
         systemProgram: SystemProgram.programId,
       })
       .signers([investmentContract])
@@ -62,7 +64,7 @@ describe(" Meraki Contract — Teste Completo", () => {
       investor: contractAccount.investor.toBase58(),
       startup: contractAccount.startup.toBase58(),
       amount: contractAccount.amount.toNumber(),
-      investorReturnPercent: contractAccount.investorReturnPercent,
+      investorReturnBps: contractAccount.investorReturnBps,
       durationDays: contractAccount.durationDays.toNumber(),
       startTime: contractAccount.startTime.toString(),
       isActive: contractAccount.isActive,
@@ -77,8 +79,8 @@ describe(" Meraki Contract — Teste Completo", () => {
       "O valor do contrato não corresponde ao esperado"
     );
     assert.strictEqual(
-      contractAccount.investorReturnPercent,
-      investorReturnPercent,
+      contractAccount.investorReturnBps,
+      investorReturnBps,
       "O percentual de retorno está incorreto"
     );
     assert.strictEqual(
@@ -0,0 +1,16 @@
+-- Sessions back the refresh tokens: one row per login, revocable at any time.
+-- Only a SHA-256 hash of the current refresh token is stored. The previous
+-- hash is kept to detect a rotated-out token being replayed.
+CREATE TABLE "sessions" (
+  "id" uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+  "user_id" uuid NOT NULL REFERENCES "users" ("id") ON DELETE CASCADE,
+  "refresh_token_hash" text UNIQUE NOT NULL,
+  "previous_token_hash" text,
+  "expires_at" timestamptz NOT NULL,
+  "revoked_at" timestamptz,
+  "created_at" timestamptz NOT NULL DEFAULT now(),
+  "last_used_at" timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX "sessions_user_id_idx" ON "sessions" ("user_id");
+CREATE INDEX "sessions_previous_token_hash_idx" ON "sessions" ("previous_token_hash");
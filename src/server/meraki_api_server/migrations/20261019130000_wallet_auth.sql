@@ -0,0 +1,41 @@
+-- Sign-In With Solana: users created from a wallet signature have no email
+-- or name until they fill in their profile, and the wallet identifies them.
+ALTER TABLE users
+ALTER COLUMN email DROP NOT NULL,
+ALTER COLUMN full_name DROP NOT NULL;
+
+-- Users used to be able to share a wallet. Which account keeps it is a
+-- decision for a person, so stop here with the list rather than guess; see
+-- "Migração de carteiras duplicadas" in docs/docs.md.
+DO $$
+DECLARE
+  duplicated text;
+BEGIN
+  SELECT string_agg(wallet_public_key, ', ') INTO duplicated
+  FROM (
+    SELECT wallet_public_key FROM users
+    GROUP BY wallet_public_key
+    HAVING count(*) > 1
+  ) AS d;
+
+  IF duplicated IS NOT NULL THEN
+    RAISE EXCEPTION 'wallets registered to more than one user: %', duplicated
+      USING HINT = 'Give each wallet a single owner, then run the migration again.';
+  END IF;
+END
+$$;
+
+CREATE UNIQUE INDEX "users_wallet_public_key_key" ON "users" ("wallet_public_key");
+
+-- One row per issued sign-in message; a nonce can be redeemed once.
+CREATE TABLE "wallet_challenges" (
+  "id" uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+  "wallet_public_key" text NOT NULL,
+  "nonce" text UNIQUE NOT NULL,
+  "message" text NOT NULL,
+  "expires_at" timestamptz NOT NULL,
+  "used_at" timestamptz,
+  "created_at" timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX "wallet_challenges_wallet_public_key_idx" ON "wallet_challenges" ("wallet_public_key");
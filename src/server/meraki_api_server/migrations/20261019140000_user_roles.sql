@@ -0,0 +1,13 @@
+-- Roles carried in access tokens. Founders own startups, admins moderate
+-- everything regardless of ownership.
+CREATE TYPE "UserRole" AS ENUM (
+  'investor',
+  'founder',
+  'admin'
+);
+
+ALTER TABLE users
+ADD COLUMN "role" "UserRole" NOT NULL DEFAULT 'investor';
+
+UPDATE users SET role = 'founder'
+WHERE id IN (SELECT user_id FROM startups WHERE user_id IS NOT NULL);
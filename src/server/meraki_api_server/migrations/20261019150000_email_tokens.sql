@@ -0,0 +1,29 @@
+ALTER TABLE users
+ADD COLUMN "email_verified_at" timestamptz;
+
+CREATE TYPE "UserTokenPurpose" AS ENUM (
+  'verify_email',
+  'reset_password'
+);
+
+-- Single-use tokens mailed to users. Only a SHA-256 hash is stored.
+CREATE TABLE "user_tokens" (
+  "id" uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+  "user_id" uuid NOT NULL REFERENCES "users" ("id") ON DELETE CASCADE,
+  "purpose" "UserTokenPurpose" NOT NULL,
+  "token_hash" text UNIQUE NOT NULL,
+  "expires_at" timestamptz NOT NULL,
+  "used_at" timestamptz,
+  "created_at" timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX "user_tokens_user_id_idx" ON "user_tokens" ("user_id");
+
+-- Mail captured by the outbox mailer instead of being sent
+CREATE TABLE "email_outbox" (
+  "id" uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+  "recipient" text NOT NULL,
+  "subject" text NOT NULL,
+  "body" text NOT NULL,
+  "created_at" timestamptz NOT NULL DEFAULT now()
+);
@@ -0,0 +1,14 @@
+-- Full-text search over startups. Pitches are written in Portuguese or
+-- English, so every field is indexed with both stemmers; the name weighs
+-- most, then the slogan, then the longer texts.
+ALTER TABLE startups
+ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+  setweight(to_tsvector('portuguese', coalesce(name, '')), 'A') ||
+  setweight(to_tsvector('english', coalesce(name, '')), 'A') ||
+  setweight(to_tsvector('portuguese', coalesce(slogan, '')), 'B') ||
+  setweight(to_tsvector('english', coalesce(slogan, '')), 'B') ||
+  setweight(to_tsvector('portuguese', coalesce(description, '') || ' ' || coalesce(problem, '')), 'C') ||
+  setweight(to_tsvector('english', coalesce(description, '') || ' ' || coalesce(problem, '')), 'C')
+) STORED;
+
+CREATE INDEX startups_search_vector_idx ON startups USING GIN (search_vector);
@@ -0,0 +1,8 @@
+-- Investments are only recorded once their transaction is verified on-chain,
+-- so the signature is required. Rows from before that have none: they get a
+-- marker that can't be a base58 signature, which keeps them unique.
+UPDATE investments SET transaction_hash = 'unverified:' || id
+WHERE transaction_hash IS NULL;
+
+ALTER TABLE investments
+ALTER COLUMN transaction_hash SET NOT NULL;
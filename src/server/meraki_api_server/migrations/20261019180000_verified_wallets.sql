@@ -0,0 +1,15 @@
+-- A wallet is only linked to a user after it signs a Sign-In With Solana
+-- challenge. Accounts with a password got theirs from the signup or profile
+-- body, which proved nothing, so unlink them; owners link again by signing.
+ALTER TABLE users
+ALTER COLUMN wallet_public_key DROP NOT NULL;
+
+UPDATE users SET wallet_public_key = NULL
+WHERE password_hash IS NOT NULL;
+
+-- Wallet-only accounts were created by a signed sign-in, but could change
+-- their wallet through the profile afterwards. Ending their sessions makes
+-- the next sign-in prove the wallet they hold now.
+UPDATE sessions SET revoked_at = now()
+WHERE revoked_at IS NULL
+  AND user_id IN (SELECT id FROM users WHERE password_hash IS NULL);
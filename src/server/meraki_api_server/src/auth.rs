@@ -1,15 +1,16 @@
-use crate::error::AppError;
-use argon::{
+use crate::{error::AppError, keys::JwtKeys, models::UserRole, sessions};
+use argon2::{
     password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
-    Argon,
+    Argon2,
 };
 use axum::{
     async_trait,
-    extract::FromRequestParts,
+    extract::{FromRef, FromRequestParts},
     http::{header, request::Parts},
 };
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::{marker::PhantomData, sync::Arc};
 use time::{Duration, OffsetDateTime};
 use uuid::Uuid;
 
@@ -17,42 +18,45 @@ use uuid::Uuid;
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TokenClaims {
     pub sub: Uuid, // Subject (the user ID)
-    pub iat: i6, // Issued at
-    pub exp: i6, // Expiration time
+    pub sid: Uuid, // Session the token was issued for
+    pub role: UserRole, // Role at the time the token was issued
+    pub iat: i64, // Issued at
+    pub exp: i64, // Expiration time
 }
 
-// Secret key for signing the JWT.
-// In a real application, this should be loaded from environment variables!
-const JWT_SECRET: &[u8] = b"your-super-secret-and-long-key";
+// Access tokens are short-lived; clients renew them with a refresh token.
+pub const ACCESS_TOKEN_TTL: Duration = Duration::minutes(15);
 
-/// Creates a new JWT for a given user ID.
-pub fn create_jwt(user_id: Uuid) -> Result<String, AppError> {
+/// Creates a new access token for a user's session, signed with the current
+/// signing key.
+pub fn create_jwt(
+    keys: &JwtKeys,
+    user_id: Uuid,
+    role: UserRole,
+    session_id: Uuid,
+) -> Result<String, AppError> {
     let now = OffsetDateTime::now_utc();
     let claims = TokenClaims {
         sub: user_id,
+        sid: session_id,
+        role,
         iat: now.unix_timestamp(),
-        // Token expires in  day
-        exp: (now + Duration::days()).unix_timestamp(),
+        exp: (now + ACCESS_TOKEN_TTL).unix_timestamp(),
     };
 
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(JWT_SECRET),
-    )
-    .map_err(|e| AppError::InternalServerError(format!("Failed to create token: {}", e)))
+    keys.encode(&claims)
 }
 
-/// Hashes a password using Argon
+/// Hashes a password using Argon2
 pub async fn hash_password(password: String) -> Result<String, AppError> {
     // Run the blocking password hashing operation in a separate thread
     tokio::task::spawn_blocking(move || {
         let salt = SaltString::generate(&mut OsRng);
-        let argon = Argon::default();
+        let argon = Argon2::default();
         argon
             .hash_password(password.as_bytes(), &salt)
             .map(|hash| hash.to_string())
-            .map_err(|e| AppError::PasswordHashError(e))
+            .map_err(AppError::PasswordHashError)
     })
     .await
     .map_err(|e| AppError::InternalServerError(format!("Task join error: {}", e)))?
@@ -63,11 +67,13 @@ pub async fn hash_password(password: String) -> Result<String, AppError> {
 #[async_trait]
 impl<S> FromRequestParts<S> for TokenClaims
 where
+    Arc<JwtKeys>: FromRef<S>,
+    PgPool: FromRef<S>,
     S: Send + Sync,
 {
     type Rejection = AppError;
 
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         // Extract the Authorization header
         let auth_header = parts
             .headers
@@ -83,14 +89,61 @@ where
         // Extract the token (skip "Bearer " prefix)
         let token = &auth_header[7..];
 
-        // Decode the token
-        let token_data = decode::<TokenClaims>(
-            token,
-            &DecodingKey::from_secret(JWT_SECRET),
-            &Validation::default(),
-        )
-        .map_err(|_| AppError::Unauthorized)?;
+        // Decode the token with the key named by its `kid` header
+        let keys = Arc::<JwtKeys>::from_ref(state);
+        let claims = keys.decode::<TokenClaims>(token)?;
 
-        Ok(token_data.claims)
+        // Reject tokens whose session was logged out or revoked
+        let pool = PgPool::from_ref(state);
+        if !sessions::is_active(&pool, claims.sid, claims.sub).await? {
+            return Err(AppError::Unauthorized);
+        }
+
+        Ok(claims)
+    }
+}
+
+impl TokenClaims {
+    /// Whether the user may modify a resource owned by `owner`: its owner,
+    /// or any admin.
+    pub fn can_manage(&self, owner: Option<Uuid>) -> bool {
+        self.role == UserRole::Admin || owner == Some(self.sub)
+    }
+}
+
+// Marker types naming the role a route requires, for `RequireRole<R>`.
+pub trait Role {
+    const ROLE: UserRole;
+}
+
+pub struct Admin;
+
+impl Role for Admin {
+    const ROLE: UserRole = UserRole::Admin;
+}
+
+// Extractor guard: authenticates like `TokenClaims`, but rejects users
+// without role `R` with 403. Admins satisfy every role. Handlers that also
+// need the caller's id extract `TokenClaims` alongside it.
+pub struct RequireRole<R: Role>(PhantomData<R>);
+
+#[async_trait]
+impl<S, R> FromRequestParts<S> for RequireRole<R>
+where
+    Arc<JwtKeys>: FromRef<S>,
+    PgPool: FromRef<S>,
+    S: Send + Sync,
+    R: Role,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let claims = TokenClaims::from_request_parts(parts, state).await?;
+
+        if claims.role != R::ROLE && claims.role != UserRole::Admin {
+            return Err(AppError::Forbidden);
+        }
+
+        Ok(RequireRole(PhantomData))
     }
 }
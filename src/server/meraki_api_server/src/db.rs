@@ -17,3 +17,68 @@ pub async fn create_pool() -> Result<PgPool, Error> {
     println!("Connection to the database established.");
     Ok(pool)
 }
+
+/// Promotes the user registered as `ADMIN_EMAIL`, if set, to admin. This is
+/// how a fresh install gets its first admin; after that admins manage roles
+/// through `/admin`. Their next token refresh picks the role up.
+pub async fn bootstrap_admin(pool: &PgPool) -> Result<(), Error> {
+    let Ok(email) = env::var("ADMIN_EMAIL") else {
+        return Ok(());
+    };
+
+    if promote_admin(pool, &email).await? {
+        println!("Promoted ADMIN_EMAIL to admin.");
+    }
+    Ok(())
+}
+
+// Signing up doesn't prove the address, so the account must have verified
+// it first; otherwise whoever registered the address first would get in.
+async fn promote_admin(pool: &PgPool, email: &str) -> Result<bool, Error> {
+    let result = sqlx::query!(
+        "UPDATE users SET role = 'admin', updated_at = now() WHERE email = $1 AND email_verified_at IS NOT NULL AND role <> 'admin'",
+        email
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn sign_up(pool: &PgPool, verified: bool) {
+        sqlx::query(
+            "INSERT INTO users (email, email_verified_at) VALUES ('admin@example.com', CASE WHEN $1 THEN now() END)",
+        )
+        .bind(verified)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn role(pool: &PgPool) -> String {
+        sqlx::query_scalar("SELECT role::text FROM users WHERE email = 'admin@example.com'")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn promotes_a_verified_account(pool: PgPool) {
+        sign_up(&pool, true).await;
+
+        assert!(promote_admin(&pool, "admin@example.com").await.unwrap());
+        assert_eq!(role(&pool).await, "admin");
+    }
+
+    #[sqlx::test]
+    async fn ignores_an_unverified_account(pool: PgPool) {
+        sign_up(&pool, false).await;
+
+        assert!(!promote_admin(&pool, "admin@example.com").await.unwrap());
+        assert_eq!(role(&pool).await, "investor");
+    }
+}
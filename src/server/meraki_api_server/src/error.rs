@@ -4,14 +4,22 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
-use serde_json::json;
+use serde::Serialize;
+use std::fmt;
 
 // Define a more comprehensive error type for the application
 pub enum AppError {
-    // Represents a generic internal server error
+    // Represents a generic internal server error; the message is only logged
     InternalServerError(String),
+    // Represents a failed call to a service the API depends on (the Solana
+    // RPC); the message is only logged
+    Upstream(String),
     // Represents an error for when a resource is not found
     NotFound(String),
+    // Represents a request with invalid parameters
+    BadRequest(String),
+    // Represents a request that clashes with existing data (e.g. a duplicate email)
+    Conflict(String),
     // Represents an authentication error
     Unauthorized,
     // Represents a permission error (user is authenticated but not allowed)
@@ -19,21 +27,102 @@ pub enum AppError {
     // Wraps SQLx database errors
     SqlxError(sqlx::Error),
     // Wraps password hashing errors
-    PasswordHashError(argon::password_hash::Error),
+    PasswordHashError(argon2::password_hash::Error),
+    // Represents a request body that parsed but broke validation rules
+    Validation(Vec<FieldError>),
+}
+
+impl AppError {
+    /// Stable identifier sent as `code` in the error body, for clients to
+    /// match on instead of the message.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::InternalServerError(_) => "internal_error",
+            AppError::Upstream(_) => "upstream_error",
+            AppError::NotFound(_) => "not_found",
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Conflict(_) => "conflict",
+            AppError::Unauthorized => "unauthorized",
+            AppError::Forbidden => "forbidden",
+            AppError::SqlxError(_) => "database_error",
+            AppError::PasswordHashError(_) => "internal_error",
+            AppError::Validation(_) => "validation_failed",
+        }
+    }
+}
+
+// For logs; responses hide the details of database and hashing errors
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::InternalServerError(msg)
+            | AppError::Upstream(msg)
+            | AppError::NotFound(msg)
+            | AppError::BadRequest(msg)
+            | AppError::Conflict(msg) => f.write_str(msg),
+            AppError::Unauthorized => f.write_str("unauthorized"),
+            AppError::Forbidden => f.write_str("forbidden"),
+            AppError::SqlxError(e) => write!(f, "database error: {}", e),
+            AppError::PasswordHashError(e) => write!(f, "password hash error: {}", e),
+            AppError::Validation(fields) => write!(f, "{} invalid fields", fields.len()),
+        }
+    }
+}
+
+/// Body of every error response.
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct ErrorBody {
+    pub error: String,
+    /// One of `bad_request`, `unauthorized`, `forbidden`, `not_found`,
+    /// `conflict`, `validation_failed`, `internal_error`, `database_error`,
+    /// `upstream_error`
+    pub code: String,
+}
+
+/// One invalid field in a request body.
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct FieldError {
+    /// Field name as sent in the body
+    pub field: String,
+    /// Rule that failed, e.g. `email`, `length` or `solana_pubkey`
+    pub code: String,
+    pub message: String,
+}
+
+/// Body of a 422 response.
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct ValidationErrorBody {
+    pub error: String,
+    /// Always `validation_failed`
+    pub code: String,
+    pub fields: Vec<FieldError>,
 }
 
 // Implement how to convert AppError into an HTTP response
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let code = self.code().to_string();
         let (status, error_message) = match self {
-            AppError::InternalServerError(msg) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("An internal server error occurred: {}", msg),
-            ),
+            AppError::InternalServerError(msg) => {
+                tracing::error!(error = %msg, "internal error");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "An internal server error occurred".to_string(),
+                )
+            }
+            AppError::Upstream(msg) => {
+                tracing::error!(error = %msg, "upstream error");
+                (
+                    StatusCode::BAD_GATEWAY,
+                    "An upstream service is unavailable".to_string(),
+                )
+            }
             AppError::NotFound(item) => (
                 StatusCode::NOT_FOUND,
                 format!("{} not found", item),
             ),
+            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, msg),
             AppError::Unauthorized => (
                 StatusCode::UNAUTHORIZED,
                 "Authentication required".to_string(),
@@ -43,40 +132,260 @@ impl IntoResponse for AppError {
                 "Access denied. You do not have permission to perform this action.".to_string(),
             ),
             AppError::SqlxError(e) => {
-                eprintln!("SQL Error: {:?}", e);
+                tracing::error!(error = ?e, "database error");
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     "A database error occurred".to_string(),
                 )
             }
             AppError::PasswordHashError(e) => {
-                eprintln!("Password Hash Error: {:?}", e);
+                tracing::error!(error = ?e, "password hash error");
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     "An internal security error occurred".to_string(),
                 )
             }
+            AppError::Validation(fields) => {
+                let body = ValidationErrorBody {
+                    error: "Validation failed".to_string(),
+                    code,
+                    fields,
+                };
+                return (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response();
+            }
         };
 
         // Create a JSON response body
-        let body = Json(json!({
-            "error": error_message,
-        }));
+        let body = Json(ErrorBody {
+            error: error_message,
+            code,
+        });
 
         (status, body).into_response()
     }
 }
 
-// Enable converting from `sqlx::Error` to `AppError` with `?`
+// SQLSTATE codes the API turns into client errors
+// (https://www.postgresql.org/docs/current/errcodes-appendix.html)
+const UNIQUE_VIOLATION: &str = "23505";
+const FOREIGN_KEY_VIOLATION: &str = "23503";
+const CHECK_VIOLATION: &str = "23514";
+const NOT_NULL_VIOLATION: &str = "23502";
+const NUMERIC_VALUE_OUT_OF_RANGE: &str = "22003";
+
+// Enable converting from `sqlx::Error` to `AppError` with `?`.
+//
+// Constraint violations are the client's fault, so they become 4xx errors
+// with a generic message; the SQL error itself is never sent back.
 impl From<sqlx::Error> for AppError {
     fn from(e: sqlx::Error) -> Self {
+        // Lookups check for missing rows themselves, so this is a row that
+        // disappeared between two queries
+        if let sqlx::Error::RowNotFound = e {
+            return AppError::NotFound("Resource".to_string());
+        }
+
+        if let Some(db) = e.as_database_error() {
+            match db.code().as_deref() {
+                Some(UNIQUE_VIOLATION) => {
+                    return AppError::Conflict(duplicate_message(db.constraint()))
+                }
+                // A `DELETE` goes through `AppError::from_delete` instead
+                Some(FOREIGN_KEY_VIOLATION) => {
+                    return AppError::BadRequest("A referenced record does not exist".to_string())
+                }
+                Some(CHECK_VIOLATION) | Some(NUMERIC_VALUE_OUT_OF_RANGE) => {
+                    return AppError::BadRequest("A value is out of the allowed range".to_string())
+                }
+                Some(NOT_NULL_VIOLATION) => {
+                    return AppError::BadRequest("A required value is missing".to_string())
+                }
+                _ => {}
+            }
+        }
+
         AppError::SqlxError(e)
     }
 }
 
+impl AppError {
+    /// Converts the error of a `DELETE`. The only foreign key a delete can
+    /// break is one pointing at the deleted row, so that is a conflict rather
+    /// than a missing reference.
+    pub fn from_delete(e: sqlx::Error) -> Self {
+        let still_referenced = e
+            .as_database_error()
+            .is_some_and(|db| db.code().as_deref() == Some(FOREIGN_KEY_VIOLATION));
+        if still_referenced {
+            return AppError::Conflict(
+                "This record is still referenced by other records".to_string(),
+            );
+        }
+
+        AppError::from(e)
+    }
+}
+
+fn duplicate_message(constraint: Option<&str>) -> String {
+    match constraint {
+        Some("users_email_key") => "A user with this email already exists",
+        Some("users_wallet_public_key_key") => "A user with this wallet already exists",
+        Some("investments_transaction_hash_key") => {
+            "This transaction is already recorded as an investment"
+        }
+        Some("nft_progress_investment_id_milestone_id_key") => {
+            "Progress for this milestone is already recorded"
+        }
+        _ => "This record already exists",
+    }
+    .to_string()
+}
+
 // Allows using the `?` operator with argon errors
-impl From<argon::password_hash::Error> for AppError {
-    fn from(e: argon::password_hash::Error) -> Self {
+impl From<argon2::password_hash::Error> for AppError {
+    fn from(e: argon2::password_hash::Error) -> Self {
         AppError::PasswordHashError(e)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+    use std::error::Error as StdError;
+
+    // What Postgres reports for a failed statement, minus the message text
+    #[derive(Debug)]
+    struct FakeDbError {
+        code: &'static str,
+        constraint: Option<&'static str>,
+    }
+
+    impl fmt::Display for FakeDbError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "SQLSTATE {}", self.code)
+        }
+    }
+
+    impl StdError for FakeDbError {}
+
+    impl sqlx::error::DatabaseError for FakeDbError {
+        fn message(&self) -> &str {
+            "message text is not part of the mapping"
+        }
+
+        fn code(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Borrowed(self.code))
+        }
+
+        fn as_error(&self) -> &(dyn StdError + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn StdError + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn StdError + Send + Sync + 'static> {
+            self
+        }
+
+        fn constraint(&self) -> Option<&str> {
+            self.constraint
+        }
+
+        fn kind(&self) -> sqlx::error::ErrorKind {
+            sqlx::error::ErrorKind::Other
+        }
+    }
+
+    fn db_error(code: &'static str, constraint: Option<&'static str>) -> sqlx::Error {
+        sqlx::Error::Database(Box::new(FakeDbError { code, constraint }))
+    }
+
+    #[test]
+    fn unique_violations_are_conflicts_named_after_the_constraint() {
+        match AppError::from(db_error(UNIQUE_VIOLATION, Some("users_email_key"))) {
+            AppError::Conflict(message) => {
+                assert_eq!(message, "A user with this email already exists")
+            }
+            other => panic!("expected a conflict, got {}", other),
+        }
+        match AppError::from(db_error(UNIQUE_VIOLATION, Some("some_new_key"))) {
+            AppError::Conflict(message) => assert_eq!(message, "This record already exists"),
+            other => panic!("expected a conflict, got {}", other),
+        }
+    }
+
+    #[test]
+    fn foreign_key_violations_depend_on_the_operation() {
+        let error = || db_error(FOREIGN_KEY_VIOLATION, Some("startups_user_id_fkey"));
+
+        assert!(matches!(AppError::from(error()), AppError::BadRequest(_)));
+        assert!(matches!(
+            AppError::from_delete(error()),
+            AppError::Conflict(_)
+        ));
+    }
+
+    #[test]
+    fn bad_values_are_bad_requests() {
+        for code in [
+            CHECK_VIOLATION,
+            NUMERIC_VALUE_OUT_OF_RANGE,
+            NOT_NULL_VIOLATION,
+        ] {
+            assert!(matches!(
+                AppError::from(db_error(code, None)),
+                AppError::BadRequest(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn other_database_errors_stay_internal() {
+        // serialization_failure
+        let error = AppError::from(db_error("40001", None));
+
+        assert!(matches!(error, AppError::SqlxError(_)));
+        assert_eq!(
+            error.into_response().status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[test]
+    fn deletes_map_other_errors_as_usual() {
+        assert!(matches!(
+            AppError::from_delete(sqlx::Error::RowNotFound),
+            AppError::NotFound(_)
+        ));
+        assert!(matches!(
+            AppError::from_delete(db_error(UNIQUE_VIOLATION, None)),
+            AppError::Conflict(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn server_side_details_stay_out_of_the_response() {
+        let secret = "https://rpc.example.com/?api-key=secret";
+        for (error, status) in [
+            (
+                AppError::InternalServerError(secret.to_string()),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+            (
+                AppError::Upstream(secret.to_string()),
+                StatusCode::BAD_GATEWAY,
+            ),
+        ] {
+            let response = error.into_response();
+            assert_eq!(response.status(), status);
+
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert!(!String::from_utf8_lossy(&body).contains("secret"));
+        }
+    }
+}
@@ -1,28 +1,66 @@
 use crate::{
-    auth::{self, hash_password, TokenClaims},
+    auth::{self, hash_password, Admin, RequireRole, TokenClaims},
     error::AppError,
+    keys::{JwkSet, JwtKeys},
+    mailer::{Email, Mailer},
     models::{
-        self, CreateContractPayload, CreateFilePayload, CreateInvestmentPayload,
+        self, ContractFilter, CreateContractPayload, CreateFilePayload, CreateInvestmentPayload,
         CreateMilestonePayload, CreateNftProgressPayload, CreateStartupPayload,
-        CreateUserPayload, CreateWalletConnectionPayload, UpdateContractPayload,
-        UpdateMilestonePayload, UpdateStartupPayload, UpdateUserPayload,
+        CreateUserPayload, CreateWalletConnectionPayload, RevenuePreviewQuery, StartupFilter,
+        StartupSearchQuery, UpdateContractPayload, UpdateMilestonePayload, UpdateStartupPayload,
+        UpdateUserPayload, UpdateUserRolePayload, UserFilter, UserRole,
     },
+    pagination::{self, ListQuery, Listing, Page, SortType, DEFAULT_LIMIT, MAX_LIMIT},
+    sessions,
+    solana::{self, SolanaRpc},
+    tokens::{self, UserTokenPurpose},
+    validation::ValidatedJson,
+    wallet_auth,
 };
 use axum::{
-    extract::{Path, State},
+    extract::{FromRef, Path, Query, State},
     http::StatusCode,
     Json,
 };
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
+use std::sync::Arc;
 use uuid::Uuid;
-use argon::{
+use argon2::{
     password_hash::{PasswordHash, PasswordVerifier},
-    Argon,
+    Argon2,
 };
 
 #[derive(Clone)]
 pub struct AppState {
     pub db_pool: PgPool,
+    pub jwt_keys: Arc<JwtKeys>,
+    pub mailer: Arc<dyn Mailer>,
+    pub solana: Arc<dyn SolanaRpc>,
+}
+
+// Lets handlers extract just the part of the state they need
+impl FromRef<AppState> for PgPool {
+    fn from_ref(state: &AppState) -> Self {
+        state.db_pool.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<JwtKeys> {
+    fn from_ref(state: &AppState) -> Self {
+        state.jwt_keys.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn Mailer> {
+    fn from_ref(state: &AppState) -> Self {
+        state.mailer.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn SolanaRpc> {
+    fn from_ref(state: &AppState) -> Self {
+        state.solana.clone()
+    }
 }
 
 // --- Auth Handlers ---
@@ -34,8 +72,35 @@ pub struct LoginPayload {
 }
 
 #[derive(serde::Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct LoginResponse {
+    // Short-lived access token, sent as `Authorization: Bearer`
     token: String,
+    // Exchanged at /auth/refresh for a new token pair; single use
+    refresh_token: String,
+    // Seconds until `token` expires
+    expires_in: i64,
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshPayload {
+    pub refresh_token: String,
+}
+
+// Issues the access token for a session, paired with its refresh token
+fn token_pair(
+    keys: &JwtKeys,
+    user_id: Uuid,
+    role: UserRole,
+    session_id: Uuid,
+    refresh_token: String,
+) -> Result<LoginResponse, AppError> {
+    Ok(LoginResponse {
+        token: auth::create_jwt(keys, user_id, role, session_id)?,
+        refresh_token,
+        expires_in: auth::ACCESS_TOKEN_TTL.whole_seconds(),
+    })
 }
 
 #[utoipa::path(
@@ -43,89 +108,499 @@ pub struct LoginResponse {
     path = "/auth/login",
     request_body = LoginPayload,
     responses(
-        (status = 00, description = "Login successful", body = LoginResponse),
-        (status = 0, description = "Invalid credentials")
+        (status = 200, description = "Login successful", body = LoginResponse),
+        (status = 401, description = "Invalid credentials")
     )
 )]
 pub async fn login(
     State(pool): State<PgPool>,
+    State(keys): State<Arc<JwtKeys>>,
     Json(payload): Json<LoginPayload>,
 ) -> Result<Json<LoginResponse>, AppError> {
     let user = sqlx::query!(
-        "SELECT id, password_hash FROM users WHERE email = $",
+        r#"SELECT id, password_hash, role AS "role: UserRole" FROM users WHERE email = $1"#,
         payload.email
     )
     .fetch_optional(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?
+    .await?
     .ok_or(AppError::Unauthorized)?;
 
     let password_hash_str = user.password_hash.ok_or(AppError::Unauthorized)?;
     let parsed_hash = PasswordHash::new(&password_hash_str).map_err(|_| AppError::Unauthorized)?;
 
-    Argon::default()
+    Argon2::default()
         .verify_password(payload.password.as_bytes(), &parsed_hash)
         .map_err(|_| AppError::Unauthorized)?;
 
-    let token = auth::create_jwt(user.id)?;
+    let (session_id, refresh_token) = sessions::create(&pool, user.id).await?;
 
-    Ok(Json(LoginResponse { token }))
+    Ok(Json(token_pair(&keys, user.id, user.role, session_id, refresh_token)?))
 }
 
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    request_body = RefreshPayload,
+    responses(
+        (status = 200, description = "New access and refresh tokens", body = LoginResponse),
+        (status = 401, description = "Refresh token invalid, expired, reused or revoked")
+    )
+)]
+pub async fn refresh(
+    State(pool): State<PgPool>,
+    State(keys): State<Arc<JwtKeys>>,
+    Json(payload): Json<RefreshPayload>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let (user_id, role, session_id, refresh_token) =
+        sessions::rotate(&pool, &payload.refresh_token).await?;
 
-// --- User Handlers ---
+    Ok(Json(token_pair(&keys, user_id, role, session_id, refresh_token)?))
+}
 
 #[utoipa::path(
     post,
-    path = "/users",
-    request_body = CreateUserPayload,
+    path = "/auth/logout",
+    responses(
+        (status = 204, description = "Current session revoked"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn logout(
+    State(pool): State<PgPool>,
+    claims: TokenClaims,
+) -> Result<StatusCode, AppError> {
+    sessions::revoke(&pool, claims.sid).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout-all",
     responses(
-        (status = 0, description = "User created successfully", body = User)
+        (status = 204, description = "Every session of the user revoked"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearer_auth" = [])
     )
 )]
-pub async fn create_user(
+pub async fn logout_all(
     State(pool): State<PgPool>,
-    Json(payload): Json<CreateUserPayload>,
-) -> Result<(StatusCode, Json<models::User>), AppError> {
-    let password_hash = hash_password(payload.password.clone()).await?;
+    claims: TokenClaims,
+) -> Result<StatusCode, AppError> {
+    sessions::revoke_all(&pool, claims.sub).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct TokenPayload {
+    pub token: String,
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema, validator::Validate)]
+pub struct ForgotPasswordPayload {
+    #[validate(email)]
+    pub email: String,
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema, validator::Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetPasswordPayload {
+    pub token: String,
+    #[validate(length(min = 8, max = 128))]
+    pub new_password: String,
+}
+
+// Links in emails point at the web client, which posts the token back here
+fn client_link(path: &str, token: &str) -> String {
+    let base = std::env::var("APP_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+    format!("{}/{}?token={}", base.trim_end_matches('/'), path, token)
+}
+
+async fn send_verification_email(
+    pool: &PgPool,
+    mailer: &dyn Mailer,
+    user_id: Uuid,
+    email: String,
+) -> Result<(), AppError> {
+    let token = tokens::issue(pool, user_id, UserTokenPurpose::VerifyEmail).await?;
+    mailer
+        .send(Email {
+            to: email,
+            subject: "Confirm your Meraki email".to_string(),
+            body: format!(
+                "Confirm your email address by opening this link within 24 hours:\n\n{}\n",
+                client_link("verify-email", &token)
+            ),
+        })
+        .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/verify-email",
+    request_body = TokenPayload,
+    responses(
+        (status = 204, description = "Email verified"),
+        (status = 400, description = "Token invalid, expired or already used")
+    )
+)]
+pub async fn verify_email(
+    State(pool): State<PgPool>,
+    Json(payload): Json<TokenPayload>,
+) -> Result<StatusCode, AppError> {
+    let user_id = tokens::redeem(&pool, &payload.token, UserTokenPurpose::VerifyEmail)
+        .await?
+        .ok_or(AppError::BadRequest("Invalid or expired token".to_string()))?;
+
+    sqlx::query!(
+        "UPDATE users SET email_verified_at = now(), updated_at = now() WHERE id = $1 AND email_verified_at IS NULL",
+        user_id
+    )
+    .execute(&pool)
+    .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/verify-email/resend",
+    responses(
+        (status = 204, description = "Verification email sent"),
+        (status = 400, description = "No email, or already verified"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn resend_verification_email(
+    State(pool): State<PgPool>,
+    State(mailer): State<Arc<dyn Mailer>>,
+    claims: TokenClaims,
+) -> Result<StatusCode, AppError> {
+    let user = sqlx::query!(
+        "SELECT email, email_verified_at FROM users WHERE id = $1",
+        claims.sub
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound("User".to_string()))?;
+
+    let email = match (user.email, user.email_verified_at) {
+        (Some(email), None) => email,
+        _ => return Err(AppError::BadRequest("Nothing to verify".to_string())),
+    };
+    send_verification_email(&pool, mailer.as_ref(), claims.sub, email).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/forgot-password",
+    request_body = ForgotPasswordPayload,
+    responses(
+        (status = 204, description = "Reset email sent if the account exists"),
+        (status = 422, description = "Invalid fields", body = ValidationErrorBody)
+    )
+)]
+pub async fn forgot_password(
+    State(pool): State<PgPool>,
+    State(mailer): State<Arc<dyn Mailer>>,
+    ValidatedJson(payload): ValidatedJson<ForgotPasswordPayload>,
+) -> Result<StatusCode, AppError> {
+    let user_id = sqlx::query_scalar!("SELECT id FROM users WHERE email = $1", payload.email)
+        .fetch_optional(&pool)
+        .await?;
+
+    // Answer the same either way, so the endpoint can't be used to find
+    // out which emails have accounts
+    if let Some(user_id) = user_id {
+        let token = tokens::issue(&pool, user_id, UserTokenPurpose::ResetPassword).await?;
+        mailer
+            .send(Email {
+                to: payload.email,
+                subject: "Reset your Meraki password".to_string(),
+                body: format!(
+                    "Choose a new password by opening this link within an hour:\n\n{}\n\nIf you didn't ask for this, ignore this email.\n",
+                    client_link("reset-password", &token)
+                ),
+            })
+            .await?;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/reset-password",
+    request_body = ResetPasswordPayload,
+    responses(
+        (status = 204, description = "Password changed; every session is logged out"),
+        (status = 400, description = "Token invalid, expired or already used"),
+        (status = 422, description = "Invalid fields", body = ValidationErrorBody)
+    )
+)]
+pub async fn reset_password(
+    State(pool): State<PgPool>,
+    ValidatedJson(payload): ValidatedJson<ResetPasswordPayload>,
+) -> Result<StatusCode, AppError> {
+    let user_id = tokens::redeem(&pool, &payload.token, UserTokenPurpose::ResetPassword)
+        .await?
+        .ok_or(AppError::BadRequest("Invalid or expired token".to_string()))?;
+
+    let password_hash = hash_password(payload.new_password).await?;
+    // Receiving the reset link also proves the email address
+    sqlx::query!(
+        "UPDATE users SET password_hash = $1, email_verified_at = COALESCE(email_verified_at, now()), updated_at = now() WHERE id = $2",
+        password_hash,
+        user_id
+    )
+    .execute(&pool)
+    .await?;
+    sessions::revoke_all(&pool, user_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletChallengePayload {
+    pub wallet_public_key: String,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletChallengeResponse {
+    // Text the wallet must sign, verbatim
+    message: String,
+    nonce: String,
+    #[schema(value_type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    expires_at: time::OffsetDateTime,
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletVerifyPayload {
+    pub wallet_public_key: String,
+    pub nonce: String,
+    // Base58 ed25519 signature over the challenge message
+    pub signature: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/wallet/challenge",
+    request_body = WalletChallengePayload,
+    responses(
+        (status = 200, description = "Sign-in message for the wallet to sign", body = WalletChallengeResponse),
+        (status = 400, description = "Invalid wallet public key")
+    )
+)]
+pub async fn wallet_challenge(
+    State(pool): State<PgPool>,
+    Json(payload): Json<WalletChallengePayload>,
+) -> Result<Json<WalletChallengeResponse>, AppError> {
+    wallet_auth::parse_public_key(&payload.wallet_public_key)?;
+
+    let nonce = wallet_auth::generate_nonce();
+    let issued_at = time::OffsetDateTime::now_utc();
+    let expires_at = issued_at + wallet_auth::CHALLENGE_TTL;
+    let message = wallet_auth::challenge_message(
+        &payload.wallet_public_key,
+        &nonce,
+        issued_at,
+        expires_at,
+    )?;
+
+    // Expired challenges are never redeemed, so drop the wallet's old ones
+    sqlx::query!(
+        "DELETE FROM wallet_challenges WHERE wallet_public_key = $1 AND expires_at < now()",
+        payload.wallet_public_key
+    )
+    .execute(&pool)
+    .await?;
+    sqlx::query!(
+        "INSERT INTO wallet_challenges (wallet_public_key, nonce, message, expires_at) VALUES ($1, $2, $3, $4)",
+        payload.wallet_public_key,
+        nonce,
+        message,
+        expires_at
+    )
+    .execute(&pool)
+    .await?;
+
+    Ok(Json(WalletChallengeResponse {
+        message,
+        nonce,
+        expires_at,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/wallet/verify",
+    request_body = WalletVerifyPayload,
+    responses(
+        (status = 200, description = "Login successful", body = LoginResponse),
+        (status = 400, description = "Invalid wallet public key"),
+        (status = 401, description = "Unknown, expired or used challenge, or invalid signature")
+    )
+)]
+pub async fn wallet_verify(
+    State(pool): State<PgPool>,
+    State(keys): State<Arc<JwtKeys>>,
+    Json(payload): Json<WalletVerifyPayload>,
+) -> Result<Json<LoginResponse>, AppError> {
+    redeem_wallet_challenge(&pool, &payload).await?;
+
+    // First sign-in with an unknown wallet creates its user; the no-op update
+    // makes RETURNING yield the existing row otherwise.
+    let user = sqlx::query!(
+        r#"
+        INSERT INTO users (wallet_public_key) VALUES ($1)
+        ON CONFLICT (wallet_public_key) DO UPDATE SET wallet_public_key = EXCLUDED.wallet_public_key
+        RETURNING id, role AS "role: UserRole"
+        "#,
+        payload.wallet_public_key
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    let (session_id, refresh_token) = sessions::create(&pool, user.id).await?;
+
+    Ok(Json(token_pair(&keys, user.id, user.role, session_id, refresh_token)?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/me/wallet",
+    request_body = WalletVerifyPayload,
+    responses(
+        (status = 200, description = "Wallet linked to the user", body = User),
+        (status = 400, description = "Invalid wallet public key"),
+        (status = 401, description = "Unauthorized, unknown, expired or used challenge, or invalid signature"),
+        (status = 409, description = "Wallet already linked to another user", body = ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn link_wallet(
+    State(pool): State<PgPool>,
+    claims: TokenClaims,
+    Json(payload): Json<WalletVerifyPayload>,
+) -> Result<Json<models::User>, AppError> {
+    redeem_wallet_challenge(&pool, &payload).await?;
 
     let user = sqlx::query_as!(
         models::User,
+        r#"UPDATE users SET wallet_public_key = $1, updated_at = now() WHERE id = $2 RETURNING id, full_name, email, password_hash, wallet_public_key, role AS "role: _", email_verified_at, created_at, updated_at"#,
+        payload.wallet_public_key,
+        claims.sub
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound("User".to_string()))?;
+
+    Ok(Json(user))
+}
+
+// Checks that the wallet signed one of its live challenges. This is the only
+// proof of owning a wallet, so it gates both signing in and linking.
+async fn redeem_wallet_challenge(
+    pool: &PgPool,
+    payload: &WalletVerifyPayload,
+) -> Result<(), AppError> {
+    let public_key = wallet_auth::parse_public_key(&payload.wallet_public_key)?;
+
+    // Redeeming the nonce first makes each challenge single use, even if the
+    // signature turns out to be wrong.
+    let message = sqlx::query_scalar!(
         r#"
-        INSERT INTO users (full_name, email, password_hash, wallet_public_key)
-        VALUES ($, $, $, $)
-        RETURNING id, full_name, email, password_hash, wallet_public_key, created_at, updated_at
+        UPDATE wallet_challenges SET used_at = now()
+        WHERE nonce = $1 AND wallet_public_key = $2 AND used_at IS NULL AND expires_at > now()
+        RETURNING message
         "#,
-        payload.full_name,
-        payload.email,
-        password_hash,
+        payload.nonce,
         payload.wallet_public_key
     )
-    .fetch_one(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::Unauthorized)?;
 
-    Ok((StatusCode::CREATED, Json(user)))
+    wallet_auth::verify_signature(&public_key, &message, &payload.signature)
 }
 
 #[utoipa::path(
     get,
+    path = "/.well-known/jwks.json",
+    responses(
+        (status = 200, description = "Public keys that verify access tokens", body = JwkSet)
+    )
+)]
+pub async fn jwks(State(keys): State<Arc<JwtKeys>>) -> Json<JwkSet> {
+    Json(keys.jwks().clone())
+}
+
+
+// --- User Handlers ---
+
+const USERS: Listing = Listing {
+    table: "users",
+    columns: "id, full_name, email, password_hash, wallet_public_key, role, email_verified_at, created_at, updated_at",
+    sort_fields: &[("created_at", SortType::Timestamp), ("updated_at", SortType::Timestamp)],
+    default_sort: "-created_at",
+    created_column: Some("created_at"),
+};
+
+#[utoipa::path(
+    post,
     path = "/users",
+    request_body = CreateUserPayload,
     responses(
-        (status = 00, description = "List of all users", body = [User])
+        (status = 201, description = "User created successfully", body = User),
+        (status = 409, description = "Email already registered", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ValidationErrorBody)
     )
 )]
-pub async fn get_users(
+pub async fn create_user(
     State(pool): State<PgPool>,
-) -> Result<Json<Vec<models::User>>, AppError> {
-    let users = sqlx::query_as!(
+    State(mailer): State<Arc<dyn Mailer>>,
+    ValidatedJson(payload): ValidatedJson<CreateUserPayload>,
+) -> Result<(StatusCode, Json<models::User>), AppError> {
+    let password_hash = hash_password(payload.password.clone()).await?;
+
+    let user = sqlx::query_as!(
         models::User,
-        "SELECT id, full_name, email, password_hash, wallet_public_key, created_at, updated_at FROM users"
+        r#"
+        INSERT INTO users (full_name, email, password_hash)
+        VALUES ($1, $2, $3)
+        RETURNING id, full_name, email, password_hash, wallet_public_key, role AS "role: _", email_verified_at, created_at, updated_at
+        "#,
+        payload.full_name,
+        payload.email,
+        password_hash
     )
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?;
-    Ok(Json(users))
+    .fetch_one(&pool)
+    .await?;
+
+    // The account is usable right away; a failed email shouldn't undo the
+    // signup, and the user can ask for another one
+    if let Err(e) = send_verification_email(&pool, mailer.as_ref(), user.id, payload.email).await {
+        tracing::warn!(user_id = %user.id, error = %e, "failed to send the verification email");
+    }
+
+    Ok((StatusCode::CREATED, Json(user)))
 }
 
 #[utoipa::path(
@@ -135,18 +610,18 @@ pub async fn get_users(
         ("id" = Uuid, Path, description = "User ID")
     ),
     responses(
-        (status = 00, description = "User found", body = User),
-        (status = 0, description = "User not found")
+        (status = 200, description = "User found", body = User),
+        (status = 404, description = "User not found")
     )
 )]
 pub async fn get_user_by_id(
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<models::User>, AppError> {
-    let user = sqlx::query_as!(models::User, "SELECT id, full_name, email, password_hash, wallet_public_key, created_at, updated_at FROM users WHERE id = $", id)
-        .fetch_one(&pool)
-        .await
-        .map_err(|_| AppError::NotFound("User".to_string()))?;
+    let user = sqlx::query_as!(models::User, r#"SELECT id, full_name, email, password_hash, wallet_public_key, role AS "role: _", email_verified_at, created_at, updated_at FROM users WHERE id = $1"#, id)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or(AppError::NotFound("User".to_string()))?;
 
     Ok(Json(user))
 }
@@ -156,8 +631,9 @@ pub async fn get_user_by_id(
     path = "/auth/me",
     request_body = UpdateUserPayload,
     responses(
-        (status = 00, description = "User updated successfully", body = User),
-        (status = 0, description = "Unauthorized")
+        (status = 200, description = "User updated successfully", body = User),
+        (status = 401, description = "Unauthorized"),
+        (status = 422, description = "Invalid fields", body = ValidationErrorBody)
     ),
     security(
         ("bearer_auth" = [])
@@ -166,20 +642,19 @@ pub async fn get_user_by_id(
 pub async fn update_me(
     State(pool): State<PgPool>,
     claims: TokenClaims,
-    Json(payload): Json<UpdateUserPayload>,
+    ValidatedJson(payload): ValidatedJson<UpdateUserPayload>,
 ) -> Result<Json<models::User>, AppError> {
     let user_id = claims.sub;
 
     let user = sqlx::query_as!(
         models::User,
-        "UPDATE users SET full_name = COALESCE($, full_name), wallet_public_key = COALESCE($, wallet_public_key), updated_at = now() WHERE id = $ RETURNING id, full_name, email, password_hash, wallet_public_key, created_at, updated_at",
+        r#"UPDATE users SET full_name = COALESCE($1, full_name), updated_at = now() WHERE id = $2 RETURNING id, full_name, email, password_hash, wallet_public_key, role AS "role: _", email_verified_at, created_at, updated_at"#,
         payload.full_name,
-        payload.wallet_public_key,
         user_id
     )
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| AppError::NotFound("User".to_string()))?;
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound("User".to_string()))?;
 
     Ok(Json(user))
 }
@@ -188,8 +663,9 @@ pub async fn update_me(
     delete,
     path = "/auth/me",
     responses(
-        (status = 0, description = "User deleted successfully"),
-        (status = 0, description = "Unauthorized")
+        (status = 204, description = "User deleted successfully"),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "User still owns startups or investments", body = ErrorBody)
     ),
     security(
         ("bearer_auth" = [])
@@ -201,10 +677,10 @@ pub async fn delete_me(
 ) -> Result<StatusCode, AppError> {
     let user_id = claims.sub;
 
-    let result = sqlx::query!("DELETE FROM users WHERE id = $", user_id)
+    let result = sqlx::query!("DELETE FROM users WHERE id = $1", user_id)
         .execute(&pool)
         .await
-        .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+        .map_err(AppError::from_delete)?;
 
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound("User".to_string()));
@@ -215,13 +691,22 @@ pub async fn delete_me(
 
 // --- Startup Handlers ---
 
+const STARTUPS: Listing = Listing {
+    table: "startups",
+    columns: "id, user_id, name, slogan, description, problem, logo_url, video_pitch_url, created_at",
+    sort_fields: &[("created_at", SortType::Timestamp), ("name", SortType::Text)],
+    default_sort: "-created_at",
+    created_column: Some("created_at"),
+};
+
 #[utoipa::path(
     post,
     path = "/startups",
     request_body = CreateStartupPayload,
     responses(
-        (status = 0, description = "Startup created successfully", body = Startup),
-        (status = 0, description = "Unauthorized")
+        (status = 201, description = "Startup created successfully", body = Startup),
+        (status = 401, description = "Unauthorized"),
+        (status = 422, description = "Invalid fields", body = ValidationErrorBody)
     ),
     security(
         ("bearer_auth" = [])
@@ -230,7 +715,7 @@ pub async fn delete_me(
 pub async fn create_startup(
     State(pool): State<PgPool>,
     claims: TokenClaims,
-    Json(payload): Json<CreateStartupPayload>,
+    ValidatedJson(payload): ValidatedJson<CreateStartupPayload>,
 ) -> Result<(StatusCode, Json<models::Startup>), AppError> {
     let user_id = claims.sub;
 
@@ -238,8 +723,8 @@ pub async fn create_startup(
         models::Startup,
         r#"
         INSERT INTO startups (user_id, name, slogan, description, problem, logo_url, video_pitch_url)
-        VALUES ($, $, $, $, $5, $6, $7)
-        RETURNING *
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, user_id, name, slogan, description, problem, logo_url, video_pitch_url, created_at
         "#,
         user_id,
         payload.name,
@@ -250,8 +735,15 @@ pub async fn create_startup(
         payload.video_pitch_url
     )
     .fetch_one(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+    .await?;
+
+    // Owning a startup makes an investor a founder (from their next token)
+    sqlx::query!(
+        "UPDATE users SET role = 'founder', updated_at = now() WHERE id = $1 AND role = 'investor'",
+        user_id
+    )
+    .execute(&pool)
+    .await?;
 
     Ok((StatusCode::CREATED, Json(startup)))
 }
@@ -259,18 +751,96 @@ pub async fn create_startup(
 #[utoipa::path(
     get,
     path = "/startups",
+    params(ListQuery, StartupFilter),
     responses(
-        (status = 00, description = "List of all startups", body = [Startup])
+        (status = 200, description = "A page of startups", body = StartupPage),
+        (status = 400, description = "Invalid paging, sort or filter parameters")
     )
 )]
 pub async fn get_startups(
     State(pool): State<PgPool>,
-) -> Result<Json<Vec<models::Startup>>, AppError> {
-    let startups = sqlx::query_as!(models::Startup, "SELECT * FROM startups")
-        .fetch_all(&pool)
-        .await
-        .map_err(|e| AppError::InternalServerError(e.to_string()))?;
-    Ok(Json(startups))
+    Query(list): Query<ListQuery>,
+    Query(filter): Query<StartupFilter>,
+) -> Result<Json<Page<models::Startup>>, AppError> {
+    let page = STARTUPS.fetch(&pool, &list, |f| filter.apply(f)).await?;
+    Ok(Json(page))
+}
+
+// Highlighting re-parses the text, so it uses the language whose query
+// actually matches it. Relevance has no unique value to resume from, so the
+// results are paged by offset.
+#[utoipa::path(
+    get,
+    path = "/startups/search",
+    params(StartupSearchQuery),
+    responses(
+        (status = 200, description = "Matching startups, best match first", body = StartupSearchPage),
+        (status = 400, description = "Empty query or invalid limit, cursor or offset")
+    )
+)]
+pub async fn search_startups(
+    State(pool): State<PgPool>,
+    Query(query): Query<StartupSearchQuery>,
+) -> Result<Json<Page<models::StartupSearchHit>>, AppError> {
+    let q = query.q.trim();
+    if q.is_empty() {
+        return Err(AppError::BadRequest("q can't be empty".to_string()));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(AppError::BadRequest(format!(
+            "limit must be between 1 and {}",
+            MAX_LIMIT
+        )));
+    }
+    let offset = match &query.cursor {
+        Some(cursor) => pagination::cursor_offset(cursor)?,
+        None => query.offset.unwrap_or(0),
+    };
+    if offset < 0 {
+        return Err(AppError::BadRequest("offset can't be negative".to_string()));
+    }
+
+    let total: i64 = sqlx::query_scalar(
+        r#"
+        SELECT count(*) FROM startups
+        WHERE search_vector @@ (websearch_to_tsquery('portuguese', $1) || websearch_to_tsquery('english', $1))
+        "#,
+    )
+    .bind(q)
+    .fetch_one(&pool)
+    .await?;
+
+    let hits = sqlx::query_as::<_, models::StartupSearchHit>(
+        r#"
+        WITH query AS (
+            SELECT websearch_to_tsquery('portuguese', $1) AS pt, websearch_to_tsquery('english', $1) AS en
+        ), matches AS (
+            SELECT s.id, s.user_id, s.name, s.slogan, s.description, s.problem, s.logo_url, s.video_pitch_url, s.created_at,
+                concat_ws(' ', s.name, s.slogan, s.description, s.problem) AS document, query.pt, query.en,
+                ts_rank(s.search_vector, query.pt || query.en) AS rank
+            FROM startups s, query
+            WHERE s.search_vector @@ (query.pt || query.en)
+            ORDER BY rank DESC, s.created_at DESC, s.id DESC
+            LIMIT $2 OFFSET $3
+        )
+        SELECT id, user_id, name, slogan, description, problem, logo_url, video_pitch_url, created_at, rank,
+            CASE WHEN to_tsvector('portuguese', document) @@ pt
+                THEN ts_headline('portuguese', document, pt, 'StartSel=<mark>, StopSel=</mark>, MaxFragments=2')
+                ELSE ts_headline('english', document, en, 'StartSel=<mark>, StopSel=</mark>, MaxFragments=2')
+            END AS headline
+        FROM matches
+        ORDER BY rank DESC, created_at DESC, id DESC
+        "#,
+    )
+    .bind(q)
+    // One extra row tells whether there is a next page
+    .bind(limit + 1)
+    .bind(offset)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(Page::by_offset(hits, limit, offset, total)))
 }
 
 #[utoipa::path(
@@ -281,10 +851,11 @@ pub async fn get_startups(
         ("id" = Uuid, Path, description = "Startup ID")
     ),
     responses(
-        (status = 00, description = "Startup updated", body = Startup),
-        (status = 0, description = "Unauthorized"),
-        (status = 0, description = "Forbidden"),
-        (status = 0, description = "Startup not found")
+        (status = 200, description = "Startup updated", body = Startup),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Startup not found"),
+        (status = 422, description = "Invalid fields", body = ValidationErrorBody)
     ),
     security(
         ("bearer_auth" = [])
@@ -294,17 +865,15 @@ pub async fn update_startup(
     State(pool): State<PgPool>,
     claims: TokenClaims,
     Path(id): Path<Uuid>,
-    Json(payload): Json<UpdateStartupPayload>,
+    ValidatedJson(payload): ValidatedJson<UpdateStartupPayload>,
 ) -> Result<Json<models::Startup>, AppError> {
-    let user_id = claims.sub;
-
-    let startup_owner: (Option<Uuid>,) = sqlx::query_as("SELECT user_id FROM startups WHERE id = $")
+    let startup_owner: (Option<Uuid>,) = sqlx::query_as("SELECT user_id FROM startups WHERE id = $1")
         .bind(id)
-        .fetch_one(&pool)
-        .await
-        .map_err(|_| AppError::NotFound("Startup".to_string()))?;
+        .fetch_optional(&pool)
+        .await?
+        .ok_or(AppError::NotFound("Startup".to_string()))?;
 
-    if startup_owner.0 != Some(user_id) {
+    if !claims.can_manage(startup_owner.0) {
         return Err(AppError::Forbidden);
     }
 
@@ -313,14 +882,14 @@ pub async fn update_startup(
         r#"
         UPDATE startups
         SET 
-            name = COALESCE($, name),
-            slogan = COALESCE($, slogan),
-            description = COALESCE($, description),
-            problem = COALESCE($, problem),
+            name = COALESCE($1, name),
+            slogan = COALESCE($2, slogan),
+            description = COALESCE($3, description),
+            problem = COALESCE($4, problem),
             logo_url = COALESCE($5, logo_url),
             video_pitch_url = COALESCE($6, video_pitch_url)
         WHERE id = $7
-        RETURNING *
+        RETURNING id, user_id, name, slogan, description, problem, logo_url, video_pitch_url, created_at
         "#,
         payload.name,
         payload.slogan,
@@ -331,8 +900,7 @@ pub async fn update_startup(
         id
     )
     .fetch_one(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+    .await?;
 
     Ok(Json(updated_startup))
 }
@@ -344,10 +912,11 @@ pub async fn update_startup(
         ("id" = Uuid, Path, description = "Startup ID")
     ),
     responses(
-        (status = 0, description = "Startup deleted"),
-        (status = 0, description = "Unauthorized"),
-        (status = 0, description = "Forbidden"),
-        (status = 0, description = "Startup not found")
+        (status = 204, description = "Startup deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Startup not found"),
+        (status = 409, description = "Startup has contracts with investments", body = ErrorBody)
     ),
     security(
         ("bearer_auth" = [])
@@ -358,28 +927,34 @@ pub async fn delete_startup(
     claims: TokenClaims,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode, AppError> {
-    let user_id = claims.sub;
-
-    let startup_owner: (Option<Uuid>,) = sqlx::query_as("SELECT user_id FROM startups WHERE id = $")
+    let startup_owner: (Option<Uuid>,) = sqlx::query_as("SELECT user_id FROM startups WHERE id = $1")
         .bind(id)
-        .fetch_one(&pool)
-        .await
-        .map_err(|_| AppError::NotFound("Startup".to_string()))?;
+        .fetch_optional(&pool)
+        .await?
+        .ok_or(AppError::NotFound("Startup".to_string()))?;
 
-    if startup_owner.0 != Some(user_id) {
+    if !claims.can_manage(startup_owner.0) {
         return Err(AppError::Forbidden);
     }
 
-    sqlx::query!("DELETE FROM startups WHERE id = $", id)
+    sqlx::query!("DELETE FROM startups WHERE id = $1", id)
         .execute(&pool)
         .await
-        .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+        .map_err(AppError::from_delete)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
 // --- Contract Handlers ---
 
+const CONTRACTS: Listing = Listing {
+    table: "contracts",
+    columns: "id, startup_id, requested_amount, equity_offered, smart_contract_address, status, created_at",
+    sort_fields: &[("created_at", SortType::Timestamp)],
+    default_sort: "-created_at",
+    created_column: Some("created_at"),
+};
+
 #[utoipa::path(
     post,
     path = "/startups/{startup_id}/contracts",
@@ -388,10 +963,11 @@ pub async fn delete_startup(
         ("startup_id" = Uuid, Path, description = "Startup ID to create contract for")
     ),
     responses(
-        (status = 0, description = "Contract created", body = Contract),
-        (status = 0, description = "Unauthorized"),
-        (status = 0, description = "Forbidden"),
-        (status = 0, description = "Startup not found")
+        (status = 201, description = "Contract created", body = Contract),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Startup not found"),
+        (status = 422, description = "Invalid fields", body = ValidationErrorBody)
     ),
     security(
         ("bearer_auth" = [])
@@ -401,18 +977,16 @@ pub async fn create_contract(
     State(pool): State<PgPool>,
     claims: TokenClaims,
     Path(startup_id): Path<Uuid>,
-    Json(payload): Json<CreateContractPayload>,
+    ValidatedJson(payload): ValidatedJson<CreateContractPayload>,
 ) -> Result<(StatusCode, Json<models::Contract>), AppError> {
-    let user_id = claims.sub;
-
     let startup_owner: (Option<Uuid>,) =
-        sqlx::query_as("SELECT user_id FROM startups WHERE id = $")
+        sqlx::query_as("SELECT user_id FROM startups WHERE id = $1")
             .bind(startup_id)
-            .fetch_one(&pool)
-            .await
-            .map_err(|_| AppError::NotFound("Startup".to_string()))?;
+            .fetch_optional(&pool)
+            .await?
+            .ok_or(AppError::NotFound("Startup".to_string()))?;
 
-    if startup_owner.0 != Some(user_id) {
+    if !claims.can_manage(startup_owner.0) {
         return Err(AppError::Forbidden);
     }
 
@@ -420,7 +994,7 @@ pub async fn create_contract(
         models::Contract,
         r#"
         INSERT INTO contracts (startup_id, requested_amount, equity_offered, smart_contract_address)
-        VALUES ($, $, $, $)
+        VALUES ($1, $2, $3, $4)
         RETURNING id, startup_id, requested_amount, equity_offered, smart_contract_address, status AS "status: _", created_at
         "#,
         startup_id,
@@ -429,8 +1003,7 @@ pub async fn create_contract(
         payload.smart_contract_address
     )
     .fetch_one(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+    .await?;
 
     Ok((StatusCode::CREATED, Json(contract)))
 }
@@ -439,26 +1012,28 @@ pub async fn create_contract(
     get,
     path = "/startups/{startup_id}/contracts",
     params(
-        ("startup_id" = Uuid, Path, description = "Startup ID")
+        ("startup_id" = Uuid, Path, description = "Startup ID"),
+        ListQuery,
+        ContractFilter
     ),
     responses(
-        (status = 00, description = "List of contracts", body = [Contract])
+        (status = 200, description = "A page of contracts", body = ContractPage),
+        (status = 400, description = "Invalid paging, sort or filter parameters")
     )
 )]
 pub async fn get_contracts_for_startup(
     State(pool): State<PgPool>,
     Path(startup_id): Path<Uuid>,
-) -> Result<Json<Vec<models::Contract>>, AppError> {
-    let contracts = sqlx::query_as!(
-        models::Contract,
-        r#"SELECT id, startup_id, requested_amount, equity_offered, smart_contract_address, status AS "status: _", created_at FROM contracts WHERE startup_id = $"#,
-        startup_id
-    )
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?;
-
-    Ok(Json(contracts))
+    Query(list): Query<ListQuery>,
+    Query(filter): Query<ContractFilter>,
+) -> Result<Json<Page<models::Contract>>, AppError> {
+    let page = CONTRACTS
+        .fetch(&pool, &list, |f| {
+            f.and().push("startup_id = ").push_bind(startup_id);
+            filter.apply(f);
+        })
+        .await?;
+    Ok(Json(page))
 }
 
 #[utoipa::path(
@@ -468,8 +1043,8 @@ pub async fn get_contracts_for_startup(
         ("id" = Uuid, Path, description = "Contract ID")
     ),
     responses(
-        (status = 00, description = "Contract found", body = Contract),
-        (status = 0, description = "Contract not found")
+        (status = 200, description = "Contract found", body = Contract),
+        (status = 404, description = "Contract not found")
     )
 )]
 pub async fn get_contract_by_id(
@@ -478,12 +1053,12 @@ pub async fn get_contract_by_id(
 ) -> Result<Json<models::Contract>, AppError> {
     let contract = sqlx::query_as!(
         models::Contract,
-        r#"SELECT id, startup_id, requested_amount, equity_offered, smart_contract_address, status AS "status: _", created_at FROM contracts WHERE id = $"#,
+        r#"SELECT id, startup_id, requested_amount, equity_offered, smart_contract_address, status AS "status: _", created_at FROM contracts WHERE id = $1"#,
         id
     )
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| AppError::NotFound("Contract".to_string()))?;
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound("Contract".to_string()))?;
 
     Ok(Json(contract))
 }
@@ -496,10 +1071,11 @@ pub async fn get_contract_by_id(
         ("id" = Uuid, Path, description = "Contract ID")
     ),
     responses(
-        (status = 00, description = "Contract updated", body = Contract),
-        (status = 0, description = "Unauthorized"),
-        (status = 0, description = "Forbidden"),
-        (status = 0, description = "Contract not found")
+        (status = 200, description = "Contract updated", body = Contract),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Contract not found"),
+        (status = 422, description = "Invalid fields", body = ValidationErrorBody)
     ),
     security(
         ("bearer_auth" = [])
@@ -509,19 +1085,17 @@ pub async fn update_contract(
     State(pool): State<PgPool>,
     claims: TokenClaims,
     Path(id): Path<Uuid>,
-    Json(payload): Json<UpdateContractPayload>,
+    ValidatedJson(payload): ValidatedJson<UpdateContractPayload>,
 ) -> Result<Json<models::Contract>, AppError> {
-    let user_id = claims.sub;
-
     let contract_owner: (Option<Uuid>,) = sqlx::query_as(
-        "SELECT s.user_id FROM contracts c JOIN startups s ON c.startup_id = s.id WHERE c.id = $",
+        "SELECT s.user_id FROM contracts c JOIN startups s ON c.startup_id = s.id WHERE c.id = $1",
     )
     .bind(id)
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| AppError::NotFound("Contract".to_string()))?;
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound("Contract".to_string()))?;
 
-    if contract_owner.0 != Some(user_id) {
+    if !claims.can_manage(contract_owner.0) {
         return Err(AppError::Forbidden);
     }
 
@@ -530,10 +1104,10 @@ pub async fn update_contract(
         r#"
         UPDATE contracts
         SET
-            requested_amount = COALESCE($, requested_amount),
-            equity_offered = COALESCE($, equity_offered),
-            smart_contract_address = COALESCE($, smart_contract_address),
-            status = COALESCE($, status)
+            requested_amount = COALESCE($1, requested_amount),
+            equity_offered = COALESCE($2, equity_offered),
+            smart_contract_address = COALESCE($3, smart_contract_address),
+            status = COALESCE($4, status)
         WHERE id = $5
         RETURNING id, startup_id, requested_amount, equity_offered, smart_contract_address, status AS "status: _", created_at
         "#,
@@ -544,14 +1118,21 @@ pub async fn update_contract(
         id
     )
     .fetch_one(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+    .await?;
 
     Ok(Json(contract))
 }
 
 // --- Investment Handlers ---
 
+const INVESTMENTS: Listing = Listing {
+    table: "investments",
+    columns: "id, contract_id, investor_id, amount_invested, nft_token_id, transaction_hash, created_at",
+    sort_fields: &[("created_at", SortType::Timestamp)],
+    default_sort: "-created_at",
+    created_column: Some("created_at"),
+};
+
 #[utoipa::path(
     post,
     path = "/contracts/{contract_id}/invest",
@@ -560,9 +1141,13 @@ pub async fn update_contract(
         ("contract_id" = Uuid, Path, description = "Contract ID to invest in")
     ),
     responses(
-        (status = 0, description = "Investment created", body = Investment),
-        (status = 0, description = "Unauthorized"),
-        (status = 0, description = "Contract not found")
+        (status = 201, description = "Investment created", body = Investment),
+        (status = 400, description = "Transaction not found, failed, or doesn't match the payload", body = ErrorBody),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Contract not found"),
+        (status = 409, description = "Transaction already recorded", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ValidationErrorBody),
+        (status = 502, description = "The Solana RPC failed", body = ErrorBody)
     ),
     security(
         ("bearer_auth" = [])
@@ -570,18 +1155,58 @@ pub async fn update_contract(
 )]
 pub async fn create_investment(
     State(pool): State<PgPool>,
+    State(rpc): State<Arc<dyn SolanaRpc>>,
     claims: TokenClaims,
     Path(contract_id): Path<Uuid>,
-    Json(payload): Json<CreateInvestmentPayload>,
+    ValidatedJson(payload): ValidatedJson<CreateInvestmentPayload>,
 ) -> Result<(StatusCode, Json<models::Investment>), AppError> {
     let investor_id = claims.sub;
 
+    // Only record investments that actually happened on-chain
+    let contract_address = sqlx::query_scalar!(
+        "SELECT smart_contract_address FROM contracts WHERE id = $1",
+        contract_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound("Contract".to_string()))?
+    .ok_or(AppError::BadRequest("Contract is not deployed on-chain yet".to_string()))?;
+    let wallet = sqlx::query_scalar!(
+        "SELECT wallet_public_key FROM users WHERE id = $1",
+        investor_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound("User".to_string()))?
+    .ok_or(AppError::BadRequest(
+        "Link your wallet before recording investments".to_string(),
+    ))?;
+    // Spares the RPC call for a plain replay; the insert settles races
+    let recorded = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM investments WHERE transaction_hash = $1) AS "exists!""#,
+        payload.transaction_hash
+    )
+    .fetch_one(&pool)
+    .await?;
+    if recorded {
+        return Err(already_recorded());
+    }
+    solana::verify_investment(
+        rpc.as_ref(),
+        &payload.transaction_hash,
+        &contract_address,
+        &wallet,
+        &payload.amount_invested,
+    )
+    .await?;
+
     let investment = sqlx::query_as!(
         models::Investment,
         r#"
         INSERT INTO investments (contract_id, investor_id, amount_invested, nft_token_id, transaction_hash)
-        VALUES ($, $, $, $, $5)
-        RETURNING *
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (transaction_hash) DO NOTHING
+        RETURNING id, contract_id, investor_id, amount_invested, nft_token_id, transaction_hash, created_at
         "#,
         contract_id,
         investor_id,
@@ -589,45 +1214,52 @@ pub async fn create_investment(
         payload.nft_token_id,
         payload.transaction_hash
     )
-    .fetch_one(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(already_recorded)?;
 
     Ok((StatusCode::CREATED, Json(investment)))
 }
 
+// A transaction is recorded as one investment at most, so it can't be
+// replayed to inflate what someone invested
+fn already_recorded() -> AppError {
+    AppError::Conflict("This transaction is already recorded as an investment".to_string())
+}
+
 #[utoipa::path(
     get,
     path = "/contracts/{contract_id}/investments",
     params(
-        ("contract_id" = Uuid, Path, description = "Contract ID")
+        ("contract_id" = Uuid, Path, description = "Contract ID"),
+        ListQuery
     ),
     responses(
-        (status = 00, description = "List of investments for a contract", body = [Investment])
+        (status = 200, description = "A page of investments for a contract", body = InvestmentPage),
+        (status = 400, description = "Invalid paging or sort parameters")
     )
 )]
 pub async fn get_investments_for_contract(
     State(pool): State<PgPool>,
     Path(contract_id): Path<Uuid>,
-) -> Result<Json<Vec<models::Investment>>, AppError> {
-    let investments = sqlx::query_as!(
-        models::Investment,
-        "SELECT * FROM investments WHERE contract_id = $",
-        contract_id
-    )
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?;
-
-    Ok(Json(investments))
+    Query(list): Query<ListQuery>,
+) -> Result<Json<Page<models::Investment>>, AppError> {
+    let page = INVESTMENTS
+        .fetch(&pool, &list, |f| {
+            f.and().push("contract_id = ").push_bind(contract_id);
+        })
+        .await?;
+    Ok(Json(page))
 }
 
 #[utoipa::path(
     get,
     path = "/auth/me/investments",
+    params(ListQuery),
     responses(
-        (status = 00, description = "List of investments for the logged-in user", body = [Investment]),
-        (status = 0, description = "Unauthorized")
+        (status = 200, description = "A page of the logged-in user's investments", body = InvestmentPage),
+        (status = 400, description = "Invalid paging or sort parameters"),
+        (status = 401, description = "Unauthorized")
     ),
     security(
         ("bearer_auth" = [])
@@ -636,22 +1268,27 @@ pub async fn get_investments_for_contract(
 pub async fn get_my_investments(
     State(pool): State<PgPool>,
     claims: TokenClaims,
-) -> Result<Json<Vec<models::Investment>>, AppError> {
-    let user_id = claims.sub;
-    let investments = sqlx::query_as!(
-        models::Investment,
-        "SELECT * FROM investments WHERE investor_id = $",
-        user_id
-    )
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?;
-
-    Ok(Json(investments))
+    Query(list): Query<ListQuery>,
+) -> Result<Json<Page<models::Investment>>, AppError> {
+    let page = INVESTMENTS
+        .fetch(&pool, &list, |f| {
+            f.and().push("investor_id = ").push_bind(claims.sub);
+        })
+        .await?;
+    Ok(Json(page))
 }
 
 // --- Milestone Handlers ---
 
+// Milestones have no timestamps, so they list by title
+const MILESTONES: Listing = Listing {
+    table: "milestones",
+    columns: "id, contract_id, title, description",
+    sort_fields: &[("title", SortType::Text)],
+    default_sort: "title",
+    created_column: None,
+};
+
 #[utoipa::path(
     post,
     path = "/contracts/{contract_id}/milestones",
@@ -660,10 +1297,11 @@ pub async fn get_my_investments(
         ("contract_id" = Uuid, Path, description = "Contract ID to add milestone to")
     ),
     responses(
-        (status = 0, description = "Milestone created", body = Milestone),
-        (status = 0, description = "Unauthorized"),
-        (status = 0, description = "Forbidden"),
-        (status = 0, description = "Contract not found")
+        (status = 201, description = "Milestone created", body = Milestone),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Contract not found"),
+        (status = 422, description = "Invalid fields", body = ValidationErrorBody)
     ),
     security(
         ("bearer_auth" = [])
@@ -673,32 +1311,29 @@ pub async fn create_milestone(
     State(pool): State<PgPool>,
     claims: TokenClaims,
     Path(contract_id): Path<Uuid>,
-    Json(payload): Json<CreateMilestonePayload>,
+    ValidatedJson(payload): ValidatedJson<CreateMilestonePayload>,
 ) -> Result<(StatusCode, Json<models::Milestone>), AppError> {
-    let user_id = claims.sub;
-
     let contract_owner: (Option<Uuid>,) = sqlx::query_as(
-        "SELECT s.user_id FROM contracts c JOIN startups s ON c.startup_id = s.id WHERE c.id = $",
+        "SELECT s.user_id FROM contracts c JOIN startups s ON c.startup_id = s.id WHERE c.id = $1",
     )
     .bind(contract_id)
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| AppError::NotFound("Contract".to_string()))?;
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound("Contract".to_string()))?;
 
-    if contract_owner.0 != Some(user_id) {
+    if !claims.can_manage(contract_owner.0) {
         return Err(AppError::Forbidden);
     }
 
     let milestone = sqlx::query_as!(
         models::Milestone,
-        "INSERT INTO milestones (contract_id, title, description) VALUES ($, $, $) RETURNING *",
+        "INSERT INTO milestones (contract_id, title, description) VALUES ($1, $2, $3) RETURNING *",
         contract_id,
         payload.title,
         payload.description
     )
     .fetch_one(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+    .await?;
 
     Ok((StatusCode::CREATED, Json(milestone)))
 }
@@ -707,26 +1342,25 @@ pub async fn create_milestone(
     get,
     path = "/contracts/{contract_id}/milestones",
     params(
-        ("contract_id" = Uuid, Path, description = "Contract ID")
+        ("contract_id" = Uuid, Path, description = "Contract ID"),
+        ListQuery
     ),
     responses(
-        (status = 00, description = "List of milestones for a contract", body = [Milestone])
+        (status = 200, description = "A page of milestones for a contract", body = MilestonePage),
+        (status = 400, description = "Invalid paging or sort parameters")
     )
 )]
 pub async fn get_milestones_for_contract(
     State(pool): State<PgPool>,
     Path(contract_id): Path<Uuid>,
-) -> Result<Json<Vec<models::Milestone>>, AppError> {
-    let milestones = sqlx::query_as!(
-        models::Milestone,
-        "SELECT * FROM milestones WHERE contract_id = $",
-        contract_id
-    )
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?;
-
-    Ok(Json(milestones))
+    Query(list): Query<ListQuery>,
+) -> Result<Json<Page<models::Milestone>>, AppError> {
+    let page = MILESTONES
+        .fetch(&pool, &list, |f| {
+            f.and().push("contract_id = ").push_bind(contract_id);
+        })
+        .await?;
+    Ok(Json(page))
 }
 
 #[utoipa::path(
@@ -737,10 +1371,11 @@ pub async fn get_milestones_for_contract(
         ("id" = Uuid, Path, description = "Milestone ID")
     ),
     responses(
-        (status = 00, description = "Milestone updated", body = Milestone),
-        (status = 0, description = "Unauthorized"),
-        (status = 0, description = "Forbidden"),
-        (status = 0, description = "Milestone not found")
+        (status = 200, description = "Milestone updated", body = Milestone),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Milestone not found"),
+        (status = 422, description = "Invalid fields", body = ValidationErrorBody)
     ),
     security(
         ("bearer_auth" = [])
@@ -750,38 +1385,43 @@ pub async fn update_milestone(
     State(pool): State<PgPool>,
     claims: TokenClaims,
     Path(id): Path<Uuid>,
-    Json(payload): Json<UpdateMilestonePayload>,
+    ValidatedJson(payload): ValidatedJson<UpdateMilestonePayload>,
 ) -> Result<Json<models::Milestone>, AppError> {
-    let user_id = claims.sub;
-
     let milestone_owner: (Option<Uuid>,) = sqlx::query_as(
-        "SELECT s.user_id FROM milestones m JOIN contracts c ON m.contract_id = c.id JOIN startups s ON c.startup_id = s.id WHERE m.id = $",
+        "SELECT s.user_id FROM milestones m JOIN contracts c ON m.contract_id = c.id JOIN startups s ON c.startup_id = s.id WHERE m.id = $1",
     )
     .bind(id)
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| AppError::NotFound("Milestone".to_string()))?;
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound("Milestone".to_string()))?;
 
-    if milestone_owner.0 != Some(user_id) {
+    if !claims.can_manage(milestone_owner.0) {
         return Err(AppError::Forbidden);
     }
 
     let milestone = sqlx::query_as!(
         models::Milestone,
-        "UPDATE milestones SET title = COALESCE($, title), description = COALESCE($, description) WHERE id = $ RETURNING *",
+        "UPDATE milestones SET title = COALESCE($1, title), description = COALESCE($2, description) WHERE id = $3 RETURNING *",
         payload.title,
         payload.description,
         id
     )
     .fetch_one(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+    .await?;
 
     Ok(Json(milestone))
 }
 
 // --- File Handlers ---
 
+const FILES: Listing = Listing {
+    table: "files",
+    columns: "id, startup_id, file_url, description, created_at",
+    sort_fields: &[("created_at", SortType::Timestamp)],
+    default_sort: "-created_at",
+    created_column: Some("created_at"),
+};
+
 #[utoipa::path(
     post,
     path = "/startups/{startup_id}/files",
@@ -790,10 +1430,11 @@ pub async fn update_milestone(
         ("startup_id" = Uuid, Path, description = "Startup ID to upload file for")
     ),
     responses(
-        (status = 0, description = "File created", body = File),
-        (status = 0, description = "Unauthorized"),
-        (status = 0, description = "Forbidden"),
-        (status = 0, description = "Startup not found")
+        (status = 201, description = "File created", body = File),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Startup not found"),
+        (status = 422, description = "Invalid fields", body = ValidationErrorBody)
     ),
     security(
         ("bearer_auth" = [])
@@ -803,31 +1444,28 @@ pub async fn create_file(
     State(pool): State<PgPool>,
     claims: TokenClaims,
     Path(startup_id): Path<Uuid>,
-    Json(payload): Json<CreateFilePayload>,
+    ValidatedJson(payload): ValidatedJson<CreateFilePayload>,
 ) -> Result<(StatusCode, Json<models::File>), AppError> {
-    let user_id = claims.sub;
-
     let startup_owner: (Option<Uuid>,) =
-        sqlx::query_as("SELECT user_id FROM startups WHERE id = $")
+        sqlx::query_as("SELECT user_id FROM startups WHERE id = $1")
             .bind(startup_id)
-            .fetch_one(&pool)
-            .await
-            .map_err(|_| AppError::NotFound("Startup".to_string()))?;
+            .fetch_optional(&pool)
+            .await?
+            .ok_or(AppError::NotFound("Startup".to_string()))?;
 
-    if startup_owner.0 != Some(user_id) {
+    if !claims.can_manage(startup_owner.0) {
         return Err(AppError::Forbidden);
     }
 
     let file = sqlx::query_as!(
         models::File,
-        "INSERT INTO files (startup_id, file_url, description) VALUES ($, $, $) RETURNING *",
+        "INSERT INTO files (startup_id, file_url, description) VALUES ($1, $2, $3) RETURNING *",
         startup_id,
         payload.file_url,
         payload.description
     )
     .fetch_one(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+    .await?;
 
     Ok((StatusCode::CREATED, Json(file)))
 }
@@ -836,26 +1474,25 @@ pub async fn create_file(
     get,
     path = "/startups/{startup_id}/files",
     params(
-        ("startup_id" = Uuid, Path, description = "Startup ID")
+        ("startup_id" = Uuid, Path, description = "Startup ID"),
+        ListQuery
     ),
     responses(
-        (status = 00, description = "List of files for a startup", body = [File])
+        (status = 200, description = "A page of files for a startup", body = FilePage),
+        (status = 400, description = "Invalid paging or sort parameters")
     )
 )]
 pub async fn get_files_for_startup(
     State(pool): State<PgPool>,
     Path(startup_id): Path<Uuid>,
-) -> Result<Json<Vec<models::File>>, AppError> {
-    let files = sqlx::query_as!(
-        models::File,
-        "SELECT * FROM files WHERE startup_id = $",
-        startup_id
-    )
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?;
-
-    Ok(Json(files))
+    Query(list): Query<ListQuery>,
+) -> Result<Json<Page<models::File>>, AppError> {
+    let page = FILES
+        .fetch(&pool, &list, |f| {
+            f.and().push("startup_id = ").push_bind(startup_id);
+        })
+        .await?;
+    Ok(Json(page))
 }
 
 #[utoipa::path(
@@ -865,10 +1502,10 @@ pub async fn get_files_for_startup(
         ("id" = Uuid, Path, description = "File ID")
     ),
     responses(
-        (status = 0, description = "File deleted"),
-        (status = 0, description = "Unauthorized"),
-        (status = 0, description = "Forbidden"),
-        (status = 0, description = "File not found")
+        (status = 204, description = "File deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "File not found")
     ),
     security(
         ("bearer_auth" = [])
@@ -879,30 +1516,35 @@ pub async fn delete_file(
     claims: TokenClaims,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode, AppError> {
-    let user_id = claims.sub;
-
     let file_owner: (Option<Uuid>,) = sqlx::query_as(
-        "SELECT s.user_id FROM files f JOIN startups s ON f.startup_id = s.id WHERE f.id = $",
+        "SELECT s.user_id FROM files f JOIN startups s ON f.startup_id = s.id WHERE f.id = $1",
     )
     .bind(id)
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| AppError::NotFound("File".to_string()))?;
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound("File".to_string()))?;
 
-    if file_owner.0 != Some(user_id) {
+    if !claims.can_manage(file_owner.0) {
         return Err(AppError::Forbidden);
     }
 
-    sqlx::query!("DELETE FROM files WHERE id = $", id)
+    sqlx::query!("DELETE FROM files WHERE id = $1", id)
         .execute(&pool)
-        .await
-        .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+        .await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
 // --- NFT Progress Handlers ---
 
+const NFT_PROGRESS: Listing = Listing {
+    table: "nft_progress",
+    columns: "id, investment_id, milestone_id, visual_state, updated_at",
+    sort_fields: &[("updated_at", SortType::Timestamp)],
+    default_sort: "-updated_at",
+    created_column: None,
+};
+
 #[utoipa::path(
     post,
     path = "/investments/{investment_id}/milestones/{milestone_id}/progress",
@@ -912,10 +1554,11 @@ pub async fn delete_file(
         ("milestone_id" = Uuid, Path, description = "Milestone ID")
     ),
     responses(
-        (status = 0, description = "NFT progress upserted", body = NftProgress),
-        (status = 0, description = "Unauthorized"),
-        (status = 0, description = "Forbidden"),
-        (status = 0, description = "Investment or Milestone not found")
+        (status = 201, description = "NFT progress upserted", body = NftProgress),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Investment or Milestone not found"),
+        (status = 422, description = "Invalid fields", body = ValidationErrorBody)
     ),
     security(
         ("bearer_auth" = [])
@@ -925,29 +1568,29 @@ pub async fn upsert_nft_progress(
     State(pool): State<PgPool>,
     claims: TokenClaims,
     Path((investment_id, milestone_id)): Path<(Uuid, Uuid)>,
-    Json(payload): Json<CreateNftProgressPayload>,
+    ValidatedJson(payload): ValidatedJson<CreateNftProgressPayload>,
 ) -> Result<(StatusCode, Json<models::NftProgress>), AppError> {
     // Ensure the investment exists and is owned by the logged-in user
     let inv = sqlx::query!(
-        "SELECT investor_id, contract_id FROM investments WHERE id = $",
+        "SELECT investor_id, contract_id FROM investments WHERE id = $1",
         investment_id
     )
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| AppError::NotFound("Investment".to_string()))?;
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound("Investment".to_string()))?;
 
-    if inv.investor_id != Some(claims.sub) {
+    if !claims.can_manage(inv.investor_id) {
         return Err(AppError::Forbidden);
     }
 
     // Ensure the milestone exists and belongs to the same contract as the investment
     let mile = sqlx::query!(
-        "SELECT contract_id FROM milestones WHERE id = $",
+        "SELECT contract_id FROM milestones WHERE id = $1",
         milestone_id
     )
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| AppError::NotFound("Milestone".to_string()))?;
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound("Milestone".to_string()))?;
 
     if Some(mile.contract_id) != inv.contract_id {
         return Err(AppError::Forbidden);
@@ -957,7 +1600,7 @@ pub async fn upsert_nft_progress(
         models::NftProgress,
         r#"
         INSERT INTO nft_progress (investment_id, milestone_id, visual_state)
-        VALUES ($, $, $)
+        VALUES ($1, $2, $3)
         ON CONFLICT (investment_id, milestone_id)
         DO UPDATE SET visual_state = EXCLUDED.visual_state, updated_at = now()
         RETURNING id, investment_id, milestone_id, visual_state, updated_at
@@ -967,8 +1610,7 @@ pub async fn upsert_nft_progress(
         payload.visual_state
     )
     .fetch_one(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+    .await?;
 
     Ok((StatusCode::CREATED, Json(progress)))
 }
@@ -977,37 +1619,45 @@ pub async fn upsert_nft_progress(
     get,
     path = "/investments/{investment_id}/progress",
     params(
-        ("investment_id" = Uuid, Path, description = "Investment ID")
+        ("investment_id" = Uuid, Path, description = "Investment ID"),
+        ListQuery
     ),
     responses(
-        (status = 00, description = "List NFT progress entries for an investment", body = [NftProgress])
+        (status = 200, description = "A page of NFT progress entries for an investment", body = NftProgressPage),
+        (status = 400, description = "Invalid paging or sort parameters")
     )
 )]
 pub async fn get_nft_progress_for_investment(
     State(pool): State<PgPool>,
     Path(investment_id): Path<Uuid>,
-) -> Result<Json<Vec<models::NftProgress>>, AppError> {
-    let items = sqlx::query_as!(
-        models::NftProgress,
-        r#"SELECT id, investment_id, milestone_id, visual_state, updated_at FROM nft_progress WHERE investment_id = $ ORDER BY updated_at DESC"#,
-        investment_id
-    )
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?;
-
-    Ok(Json(items))
+    Query(list): Query<ListQuery>,
+) -> Result<Json<Page<models::NftProgress>>, AppError> {
+    let page = NFT_PROGRESS
+        .fetch(&pool, &list, |f| {
+            f.and().push("investment_id = ").push_bind(investment_id);
+        })
+        .await?;
+    Ok(Json(page))
 }
 
 // --- Wallet Connection Handlers ---
 
+const WALLET_CONNECTIONS: Listing = Listing {
+    table: "wallet_connections",
+    columns: "id, user_id, wallet_public_key, network, connected_at",
+    sort_fields: &[("connected_at", SortType::Timestamp)],
+    default_sort: "-connected_at",
+    created_column: Some("connected_at"),
+};
+
 #[utoipa::path(
     post,
     path = "/auth/me/wallet-connections",
     request_body = CreateWalletConnectionPayload,
     responses(
-        (status = 0, description = "Wallet connection created", body = WalletConnection),
-        (status = 0, description = "Unauthorized")
+        (status = 201, description = "Wallet connection created", body = WalletConnection),
+        (status = 401, description = "Unauthorized"),
+        (status = 422, description = "Invalid fields", body = ValidationErrorBody)
     ),
     security(
         ("bearer_auth" = [])
@@ -1016,7 +1666,7 @@ pub async fn get_nft_progress_for_investment(
 pub async fn create_wallet_connection(
     State(pool): State<PgPool>,
     claims: TokenClaims,
-    Json(payload): Json<CreateWalletConnectionPayload>,
+    ValidatedJson(payload): ValidatedJson<CreateWalletConnectionPayload>,
 ) -> Result<(StatusCode, Json<models::WalletConnection>), AppError> {
     let user_id = claims.sub;
 
@@ -1024,7 +1674,7 @@ pub async fn create_wallet_connection(
         models::WalletConnection,
         r#"
         INSERT INTO wallet_connections (user_id, wallet_public_key, network)
-        VALUES ($, $, COALESCE($, 'solana'))
+        VALUES ($1, $2, COALESCE($3, 'solana'))
         RETURNING id, user_id, wallet_public_key, network, connected_at
         "#,
         user_id,
@@ -1032,8 +1682,7 @@ pub async fn create_wallet_connection(
         payload.network
     )
     .fetch_one(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+    .await?;
 
     Ok((StatusCode::CREATED, Json(conn)))
 }
@@ -1042,26 +1691,25 @@ pub async fn create_wallet_connection(
     get,
     path = "/users/{id}/wallet-connections",
     params(
-        ("id" = Uuid, Path, description = "User ID")
+        ("id" = Uuid, Path, description = "User ID"),
+        ListQuery
     ),
     responses(
-        (status = 00, description = "List wallet connections for a user", body = [WalletConnection])
+        (status = 200, description = "A page of wallet connections for a user", body = WalletConnectionPage),
+        (status = 400, description = "Invalid paging or sort parameters")
     )
 )]
 pub async fn get_wallet_connections_for_user(
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
-) -> Result<Json<Vec<models::WalletConnection>>, AppError> {
-    let conns = sqlx::query_as!(
-        models::WalletConnection,
-        r#"SELECT id, user_id, wallet_public_key, network, connected_at FROM wallet_connections WHERE user_id = $ ORDER BY connected_at DESC"#,
-        id
-    )
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| AppError::InternalServerError(e.to_string()))?;
-
-    Ok(Json(conns))
+    Query(list): Query<ListQuery>,
+) -> Result<Json<Page<models::WalletConnection>>, AppError> {
+    let page = WALLET_CONNECTIONS
+        .fetch(&pool, &list, |f| {
+            f.and().push("user_id = ").push_bind(id);
+        })
+        .await?;
+    Ok(Json(page))
 }
 
 #[utoipa::path(
@@ -1071,10 +1719,10 @@ pub async fn get_wallet_connections_for_user(
         ("id" = Uuid, Path, description = "Wallet connection ID")
     ),
     responses(
-        (status = 0, description = "Wallet connection deleted"),
-        (status = 0, description = "Unauthorized"),
-        (status = 0, description = "Forbidden"),
-        (status = 0, description = "Wallet connection not found")
+        (status = 204, description = "Wallet connection deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Wallet connection not found")
     ),
     security(
         ("bearer_auth" = [])
@@ -1085,24 +1733,448 @@ pub async fn delete_wallet_connection(
     claims: TokenClaims,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode, AppError> {
-    let user_id = claims.sub;
-
     let owner = sqlx::query!(
-        "SELECT user_id FROM wallet_connections WHERE id = $",
+        "SELECT user_id FROM wallet_connections WHERE id = $1",
         id
     )
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| AppError::NotFound("WalletConnection".to_string()))?;
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound("WalletConnection".to_string()))?;
 
-    if owner.user_id != Some(user_id) {
+    if !claims.can_manage(owner.user_id) {
         return Err(AppError::Forbidden);
     }
 
-    sqlx::query!("DELETE FROM wallet_connections WHERE id = $", id)
+    sqlx::query!("DELETE FROM wallet_connections WHERE id = $1", id)
         .execute(&pool)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// --- Admin Handlers ---
+// Startups, contracts and the rest are moderated through their regular
+// routes, where admins pass every ownership check.
+
+#[utoipa::path(
+    get,
+    path = "/admin/users",
+    params(ListQuery, UserFilter),
+    responses(
+        (status = 200, description = "A page of users, with roles", body = UserPage),
+        (status = 400, description = "Invalid paging, sort or filter parameters"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn admin_get_users(
+    State(pool): State<PgPool>,
+    _admin: RequireRole<Admin>,
+    Query(list): Query<ListQuery>,
+    Query(filter): Query<UserFilter>,
+) -> Result<Json<Page<models::User>>, AppError> {
+    let page = USERS.fetch(&pool, &list, |f| filter.apply(f)).await?;
+    Ok(Json(page))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/admin/users/{id}/role",
+    request_body = UpdateUserRolePayload,
+    params(
+        ("id" = Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Role updated; the user's sessions are revoked", body = User),
+        (status = 400, description = "Unknown role", body = ErrorBody),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "User not found"),
+        (status = 409, description = "The user is the last admin", body = ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn admin_update_user_role(
+    State(pool): State<PgPool>,
+    _admin: RequireRole<Admin>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(payload): ValidatedJson<UpdateUserRolePayload>,
+) -> Result<Json<models::User>, AppError> {
+    let mut tx = pool.begin().await?;
+    if payload.role != UserRole::Admin {
+        ensure_not_last_admin(&mut tx, id).await?;
+    }
+
+    let user = sqlx::query_as!(
+        models::User,
+        r#"
+        UPDATE users SET role = $1, updated_at = now() WHERE id = $2
+        RETURNING id, full_name, email, password_hash, wallet_public_key, role AS "role: _", email_verified_at, created_at, updated_at
+        "#,
+        payload.role as _,
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound("User".to_string()))?;
+    tx.commit().await?;
+
+    // Tokens carry the role, so make the user sign in again to pick it up
+    sessions::revoke_all(&pool, id).await?;
+
+    Ok(Json(user))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/users/{id}",
+    params(
+        ("id" = Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 204, description = "User deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "User not found"),
+        (status = 409, description = "User still owns startups or investments, or is the last admin", body = ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn admin_delete_user(
+    State(pool): State<PgPool>,
+    _admin: RequireRole<Admin>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = pool.begin().await?;
+    ensure_not_last_admin(&mut tx, id).await?;
+
+    let result = sqlx::query!("DELETE FROM users WHERE id = $1", id)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+        .map_err(AppError::from_delete)?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("User".to_string()));
+    }
+    tx.commit().await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+// Refuses to demote or delete `id` when it is the only admin left, which would
+// leave nobody able to moderate. The admin rows stay locked until `tx` ends,
+// so two admins can't remove each other concurrently.
+async fn ensure_not_last_admin(tx: &mut PgConnection, id: Uuid) -> Result<(), AppError> {
+    let admins = sqlx::query_scalar!("SELECT id FROM users WHERE role = 'admin' FOR UPDATE")
+        .fetch_all(&mut *tx)
+        .await?;
+
+    if admins == [id] {
+        return Err(AppError::Conflict("Cannot remove the last admin".to_string()));
+    }
+
+    Ok(())
+}
+
+// --- Revenue Preview Handlers ---
+
+// Uses the same `meraki_core` math as `record_revenue`, so the preview matches
+// what the program will transfer to the lamport.
+#[utoipa::path(
+    get,
+    path = "/revenue/preview",
+    params(RevenuePreviewQuery),
+    responses(
+        (status = 200, description = "How the revenue report would be split", body = RevenuePreview),
+        (status = 400, description = "Investor return is above the maximum, or the revenue cannot be split", body = ErrorBody)
+    )
+)]
+pub async fn preview_revenue(
+    Query(query): Query<RevenuePreviewQuery>,
+) -> Result<Json<models::RevenuePreview>, AppError> {
+    if query.investor_return_bps > meraki_core::MAX_INVESTOR_RETURN_BPS {
+        return Err(AppError::BadRequest(format!(
+            "investorReturnBps must be at most {}",
+            meraki_core::MAX_INVESTOR_RETURN_BPS
+        )));
+    }
+
+    let split = meraki_core::revenue_split(query.revenue_amount, query.investor_return_bps)
+        .map_err(|e| AppError::BadRequest(format!("Revenue cannot be split: {}", e)))?;
+
+    Ok(Json(models::RevenuePreview {
+        platform_fee: split.fee,
+        investor_share: split.investor_share,
+        startup_share: split.startup_share,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::testing::{native_investment, FakeRpc};
+    use meraki_sdk::Pubkey;
+    use sqlx::types::BigDecimal;
+
+    const SIGNATURE: &str =
+        "5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnbJLgp8uirBgmQpjKhoR4tjF3ZpRzrFmBV6UjKdiSZkQUW";
+
+    struct Fixture {
+        contract_id: Uuid,
+        contract: Pubkey,
+        wallet: Pubkey,
+    }
+
+    impl Fixture {
+        async fn new(pool: &PgPool) -> Self {
+            let contract = Pubkey::new_unique();
+            let contract_id = sqlx::query_scalar(
+                "INSERT INTO contracts (smart_contract_address) VALUES ($1) RETURNING id",
+            )
+            .bind(contract.to_string())
+            .fetch_one(pool)
+            .await
+            .unwrap();
+
+            Fixture {
+                contract_id,
+                contract,
+                wallet: Pubkey::new_unique(),
+            }
+        }
+
+        // A signed-up user, with `wallet` linked or not
+        async fn investor(&self, pool: &PgPool, linked: bool) -> TokenClaims {
+            let wallet = linked.then(|| self.wallet.to_string());
+            let id = sqlx::query_scalar(
+                "INSERT INTO users (email, wallet_public_key) VALUES ($1, $2) RETURNING id",
+            )
+            .bind(format!("{}@example.com", Uuid::new_v4()))
+            .bind(wallet)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+
+            TokenClaims {
+                sub: id,
+                sid: Uuid::new_v4(),
+                role: UserRole::Investor,
+                iat: 0,
+                exp: 0,
+            }
+        }
+
+        async fn invest(
+            &self,
+            pool: &PgPool,
+            claims: TokenClaims,
+        ) -> Result<(StatusCode, Json<models::Investment>), AppError> {
+            let rpc: Arc<dyn SolanaRpc> = Arc::new(FakeRpc(Some(native_investment(
+                self.contract,
+                self.wallet,
+                500,
+            ))));
+            let payload = CreateInvestmentPayload {
+                amount_invested: BigDecimal::from(500),
+                nft_token_id: None,
+                transaction_hash: SIGNATURE.to_string(),
+            };
+
+            create_investment(
+                State(pool.clone()),
+                State(rpc),
+                claims,
+                Path(self.contract_id),
+                ValidatedJson(payload),
+            )
+            .await
+        }
+    }
+
+    #[sqlx::test]
+    async fn investments_need_a_linked_wallet(pool: PgPool) {
+        let fixture = Fixture::new(&pool).await;
+        let claims = fixture.investor(&pool, false).await;
+
+        match fixture.invest(&pool, claims).await {
+            Err(AppError::BadRequest(message)) => assert!(message.contains("Link your wallet")),
+            Err(other) => panic!("expected a bad request, got {}", other),
+            Ok(_) => panic!("expected the investment to be rejected"),
+        }
+    }
+
+    #[sqlx::test]
+    async fn a_transaction_is_recorded_once(pool: PgPool) {
+        let fixture = Fixture::new(&pool).await;
+        let claims = fixture.investor(&pool, true).await;
+        let again = TokenClaims {
+            sid: Uuid::new_v4(),
+            ..claims
+        };
+
+        let (status, Json(investment)) = fixture
+            .invest(&pool, claims)
+            .await
+            .unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(investment.transaction_hash, SIGNATURE);
+
+        assert!(matches!(
+            fixture.invest(&pool, again).await,
+            Err(AppError::Conflict(_))
+        ));
+    }
+
+    async fn search(
+        pool: &PgPool,
+        q: &str,
+        cursor: Option<String>,
+    ) -> Page<models::StartupSearchHit> {
+        let query = StartupSearchQuery {
+            q: q.to_string(),
+            limit: Some(2),
+            cursor,
+            offset: None,
+        };
+        let Json(page) = search_startups(State(pool.clone()), Query(query))
+            .await
+            .unwrap_or_else(|e| panic!("{}", e));
+        page
+    }
+
+    #[sqlx::test]
+    async fn search_stems_portuguese_and_pages_by_cursor(pool: PgPool) {
+        for (name, description) in [
+            ("Agro Futuro", "Crédito para pequenos produtores rurais"),
+            ("Caixa Amiga", "Empréstimos para pequenas empresas do interior"),
+            ("Loja Certa", "Gestão de estoque para empresas de varejo"),
+            ("Rota Verde", "Logística de entregas por bicicleta"),
+        ] {
+            sqlx::query("INSERT INTO startups (name, description) VALUES ($1, $2)")
+                .bind(name)
+                .bind(description)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        // "empresa" only matches "empresas" once both are stemmed.
+        let stemmed = search(&pool, "empresa", None).await;
+        assert_eq!(stemmed.total, 2);
+        assert_eq!(stemmed.items.len(), 2);
+        assert!(stemmed.next_cursor.is_none());
+        assert!(stemmed.items.iter().all(|hit| hit.headline.contains("<mark>empresas</mark>")));
+
+        // Three matches, two per page
+
+        let first = search(&pool, "pequeno or empresa", None).await;
+        assert_eq!(first.total, 3);
+        let names: Vec<_> = first.items.iter().map(|hit| hit.startup.name.clone()).collect();
+        let second = search(&pool, "pequeno or empresa", first.next_cursor).await;
+        assert_eq!(second.total, 3);
+        assert_eq!(second.items.len(), 1);
+        assert!(second.next_cursor.is_none());
+        assert!(!names.contains(&second.items[0].startup.name));
+    }
+
+    // A fixed wallet and a challenge issued to it
+    struct Challenge {
+        key: ed25519_dalek::SigningKey,
+        address: String,
+        nonce: String,
+        message: String,
+    }
+
+    impl Challenge {
+        async fn issue(pool: &PgPool, seed: u8) -> Self {
+            let key = ed25519_dalek::SigningKey::from_bytes(&[seed; 32]);
+            let address = bs58::encode(key.verifying_key().as_bytes()).into_string();
+            let payload = WalletChallengePayload {
+                wallet_public_key: address.clone(),
+            };
+            let Json(challenge) = wallet_challenge(State(pool.clone()), Json(payload))
+                .await
+                .unwrap_or_else(|e| panic!("{}", e));
+
+            Challenge {
+                key,
+                address,
+                nonce: challenge.nonce,
+                message: challenge.message,
+            }
+        }
+
+        fn signed_by(&self, key: &ed25519_dalek::SigningKey) -> WalletVerifyPayload {
+            use ed25519_dalek::Signer;
+            let signature = key.sign(self.message.as_bytes()).to_bytes();
+            WalletVerifyPayload {
+                wallet_public_key: bs58::encode(key.verifying_key().as_bytes()).into_string(),
+                nonce: self.nonce.clone(),
+                signature: bs58::encode(signature).into_string(),
+            }
+        }
+    }
+
+    #[sqlx::test]
+    async fn a_signed_challenge_is_redeemed_once(pool: PgPool) {
+        let challenge = Challenge::issue(&pool, 1).await;
+        let payload = challenge.signed_by(&challenge.key);
+        assert_eq!(payload.wallet_public_key, challenge.address);
+
+        assert!(redeem_wallet_challenge(&pool, &payload).await.is_ok());
+        assert!(matches!(
+            redeem_wallet_challenge(&pool, &payload).await,
+            Err(AppError::Unauthorized)
+        ));
+    }
+
+    #[sqlx::test]
+    async fn a_challenge_is_only_redeemed_by_its_wallet(pool: PgPool) {
+        let challenge = Challenge::issue(&pool, 1).await;
+        let other = ed25519_dalek::SigningKey::from_bytes(&[2; 32]);
+
+        // Another wallet signing the same nonce
+        assert!(matches!(
+            redeem_wallet_challenge(&pool, &challenge.signed_by(&other)).await,
+            Err(AppError::Unauthorized)
+        ));
+        // The right wallet with another wallet's signature
+        let forged = WalletVerifyPayload {
+            wallet_public_key: challenge.address.clone(),
+            ..challenge.signed_by(&other)
+        };
+        assert!(matches!(
+            redeem_wallet_challenge(&pool, &forged).await,
+            Err(AppError::Unauthorized)
+        ));
+        // The failed attempt used up the nonce.
+        assert!(matches!(
+            redeem_wallet_challenge(&pool, &challenge.signed_by(&challenge.key)).await,
+            Err(AppError::Unauthorized)
+        ));
+    }
+
+    #[sqlx::test]
+    async fn an_expired_challenge_is_rejected(pool: PgPool) {
+        let challenge = Challenge::issue(&pool, 1).await;
+        sqlx::query(
+            "UPDATE wallet_challenges SET expires_at = now() - interval '1 second' WHERE nonce = $1",
+        )
+        .bind(&challenge.nonce)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            redeem_wallet_challenge(&pool, &challenge.signed_by(&challenge.key)).await,
+            Err(AppError::Unauthorized)
+        ));
+    }
+}
@@ -0,0 +1,397 @@
+// src/keys.rs
+use crate::error::AppError;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ed25519_dalek::VerifyingKey;
+use jsonwebtoken::{
+    decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use rsa::{pkcs8::DecodePublicKey, traits::PublicKeyParts, RsaPublicKey};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+// kid used for tokens signed with the `JWT_SECRET` fallback
+const FALLBACK_KID: &str = "default";
+
+// The file `JWT_KEYS_FILE` points to. Key paths are relative to the file.
+//
+// {
+//   "signing_kid": "2026-10",
+//   "keys": [
+//     { "kid": "2026-10", "alg": "EdDSA", "private_key": "2026-10.pem", "public_key": "2026-10.pub.pem" },
+//     { "kid": "2026-07", "alg": "RS256", "public_key": "2026-07.pub.pem" }
+//   ]
+// }
+//
+// Every listed key verifies tokens; only `signing_kid` signs new ones, so a
+// retired key can stay in the list (public half only) until the tokens it
+// signed have expired.
+#[derive(Deserialize)]
+struct KeysConfig {
+    signing_kid: String,
+    keys: Vec<KeyConfig>,
+}
+
+#[derive(Deserialize)]
+struct KeyConfig {
+    kid: String,
+    alg: Algorithm,
+    private_key: Option<PathBuf>,
+    public_key: PathBuf,
+}
+
+/// A public key in JWK format, as published at `/.well-known/jwks.json`.
+#[derive(Clone, Serialize, utoipa::ToSchema)]
+pub struct Jwk {
+    pub kty: String,
+    pub kid: String,
+    pub alg: String,
+    #[serde(rename = "use")]
+    pub key_use: String,
+    // Ed25519 keys
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crv: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+    // RSA keys
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub e: Option<String>,
+}
+
+#[derive(Clone, Serialize, utoipa::ToSchema)]
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
+
+/// The keys used to sign and verify access tokens.
+pub struct JwtKeys {
+    signing_kid: String,
+    encoding_key: EncodingKey,
+    decoding_keys: HashMap<String, (Algorithm, DecodingKey)>,
+    jwks: JwkSet,
+}
+
+impl JwtKeys {
+    /// Loads the keys listed in `JWT_KEYS_FILE`, or falls back to an HS256
+    /// key from `JWT_SECRET` when no key file is configured.
+    pub fn from_env() -> Result<Self, String> {
+        if let Ok(path) = env::var("JWT_KEYS_FILE") {
+            return Self::from_file(Path::new(&path));
+        }
+
+        let secret = env::var("JWT_SECRET")
+            .map_err(|_| "either JWT_KEYS_FILE or JWT_SECRET must be set".to_string())?;
+        Ok(Self::from_secret(secret.as_bytes()))
+    }
+
+    /// A single shared HS256 key. Symmetric keys are never published, so the
+    /// JWKS is empty.
+    pub fn from_secret(secret: &[u8]) -> Self {
+        let mut decoding_keys = HashMap::new();
+        decoding_keys.insert(
+            FALLBACK_KID.to_string(),
+            (Algorithm::HS256, DecodingKey::from_secret(secret)),
+        );
+        JwtKeys {
+            signing_kid: FALLBACK_KID.to_string(),
+            encoding_key: EncodingKey::from_secret(secret),
+            decoding_keys,
+            jwks: JwkSet { keys: Vec::new() },
+        }
+    }
+
+    fn from_file(path: &Path) -> Result<Self, String> {
+        let raw = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let config: KeysConfig = serde_json::from_str(&raw)
+            .map_err(|e| format!("invalid key file {}: {}", path.display(), e))?;
+        let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let read_pem = |file: &Path| {
+            fs::read(base_dir.join(file))
+                .map_err(|e| format!("failed to read {}: {}", file.display(), e))
+        };
+
+        let mut encoding_key = None;
+        let mut decoding_keys = HashMap::new();
+        let mut jwks = JwkSet { keys: Vec::new() };
+        for key in config.keys {
+            let public_pem = read_pem(&key.public_key)?;
+            let (decoding_key, jwk) = match key.alg {
+                Algorithm::EdDSA => (
+                    DecodingKey::from_ed_pem(&public_pem),
+                    ed25519_jwk(&key.kid, &public_pem),
+                ),
+                Algorithm::RS256 => (
+                    DecodingKey::from_rsa_pem(&public_pem),
+                    rsa_jwk(&key.kid, &public_pem),
+                ),
+                other => {
+                    return Err(format!("key {}: unsupported algorithm {:?}", key.kid, other))
+                }
+            };
+            let decoding_key = decoding_key.map_err(|e| format!("key {}: {}", key.kid, e))?;
+            jwks.keys.push(jwk.map_err(|e| format!("key {}: {}", key.kid, e))?);
+
+            if key.kid == config.signing_kid {
+                let private_file = key
+                    .private_key
+                    .as_deref()
+                    .ok_or_else(|| format!("signing key {} has no private_key", key.kid))?;
+                let private_pem = read_pem(private_file)?;
+                let loaded = match key.alg {
+                    Algorithm::EdDSA => EncodingKey::from_ed_pem(&private_pem),
+                    _ => EncodingKey::from_rsa_pem(&private_pem),
+                };
+                encoding_key = Some(loaded.map_err(|e| format!("key {}: {}", key.kid, e))?);
+            }
+            if decoding_keys
+                .insert(key.kid.clone(), (key.alg, decoding_key))
+                .is_some()
+            {
+                return Err(format!("duplicate kid {}", key.kid));
+            }
+        }
+
+        let encoding_key = encoding_key
+            .ok_or_else(|| format!("signing_kid {} is not in the key list", config.signing_kid))?;
+        Ok(JwtKeys {
+            signing_kid: config.signing_kid,
+            encoding_key,
+            decoding_keys,
+            jwks,
+        })
+    }
+
+    /// Signs `claims` with the current signing key, tagging the token with its kid.
+    pub fn encode<T: Serialize>(&self, claims: &T) -> Result<String, AppError> {
+        let (algorithm, _) = &self.decoding_keys[&self.signing_kid];
+        let mut header = Header::new(*algorithm);
+        header.kid = Some(self.signing_kid.clone());
+
+        encode(&header, claims, &self.encoding_key)
+            .map_err(|e| AppError::InternalServerError(format!("Failed to create token: {}", e)))
+    }
+
+    /// Verifies a token against the key named by its `kid` header.
+    pub fn decode<T: DeserializeOwned>(&self, token: &str) -> Result<T, AppError> {
+        let header = decode_header(token).map_err(|_| AppError::Unauthorized)?;
+        let (algorithm, key) = header
+            .kid
+            .as_deref()
+            .and_then(|kid| self.decoding_keys.get(kid))
+            .ok_or(AppError::Unauthorized)?;
+
+        decode::<T>(token, key, &Validation::new(*algorithm))
+            .map(|data| data.claims)
+            .map_err(|_| AppError::Unauthorized)
+    }
+
+    pub fn jwks(&self) -> &JwkSet {
+        &self.jwks
+    }
+}
+
+fn ed25519_jwk(kid: &str, public_pem: &[u8]) -> Result<Jwk, String> {
+    let pem = std::str::from_utf8(public_pem).map_err(|e| e.to_string())?;
+    let key = VerifyingKey::from_public_key_pem(pem)
+        .map_err(|e| format!("not an Ed25519 public key: {}", e))?;
+
+    Ok(Jwk {
+        kty: "OKP".to_string(),
+        kid: kid.to_string(),
+        alg: "EdDSA".to_string(),
+        key_use: "sig".to_string(),
+        crv: Some("Ed25519".to_string()),
+        x: Some(URL_SAFE_NO_PAD.encode(key.as_bytes())),
+        n: None,
+        e: None,
+    })
+}
+
+fn rsa_jwk(kid: &str, public_pem: &[u8]) -> Result<Jwk, String> {
+    let pem = std::str::from_utf8(public_pem).map_err(|e| e.to_string())?;
+    let key = RsaPublicKey::from_public_key_pem(pem).map_err(|e| e.to_string())?;
+
+    Ok(Jwk {
+        kty: "RSA".to_string(),
+        kid: kid.to_string(),
+        alg: "RS256".to_string(),
+        key_use: "sig".to_string(),
+        crv: None,
+        x: None,
+        n: Some(URL_SAFE_NO_PAD.encode(key.n().to_bytes_be())),
+        e: Some(URL_SAFE_NO_PAD.encode(key.e().to_bytes_be())),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{
+        pkcs8::{spki::der::pem::LineEnding, EncodePrivateKey, EncodePublicKey},
+        SigningKey,
+    };
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[derive(Serialize, Deserialize)]
+    struct Claims {
+        sub: String,
+        exp: u64,
+    }
+
+    fn claims() -> Claims {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        Claims {
+            sub: "user".to_string(),
+            exp: now.as_secs() + 60,
+        }
+    }
+
+    fn public_pem(key: &SigningKey) -> String {
+        key.verifying_key()
+            .to_public_key_pem(LineEnding::LF)
+            .unwrap()
+    }
+
+    // Writes `<kid>.pem` / `<kid>.pub.pem` for every key and a key file that
+    // signs with `signing_kid`; only that key gets a `private_key` entry.
+    fn key_file(dir: &Path, signing_kid: &str, keys: &[(&str, &SigningKey)]) -> PathBuf {
+        fs::create_dir_all(dir).unwrap();
+        let mut listed = Vec::new();
+        for (kid, key) in keys {
+            let private = key.to_pkcs8_pem(LineEnding::LF).unwrap();
+            fs::write(dir.join(format!("{}.pem", kid)), private.as_bytes()).unwrap();
+            fs::write(dir.join(format!("{}.pub.pem", kid)), public_pem(key)).unwrap();
+            let private_key = (*kid == signing_kid).then(|| format!("{}.pem", kid));
+            listed.push(serde_json::json!({
+                "kid": kid,
+                "alg": "EdDSA",
+                "private_key": private_key,
+                "public_key": format!("{}.pub.pem", kid),
+            }));
+        }
+
+        let path = dir.join(format!("keys-{}.json", signing_kid));
+        let config = serde_json::json!({ "signing_kid": signing_kid, "keys": listed });
+        fs::write(&path, config.to_string()).unwrap();
+        path
+    }
+
+    fn load(path: &Path) -> JwtKeys {
+        JwtKeys::from_file(path).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn temp_dir() -> PathBuf {
+        env::temp_dir().join(format!("meraki-keys-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn ed25519_jwk_matches_rfc_8037() {
+        // The example key from RFC 8037, appendix A.
+        let seed = URL_SAFE_NO_PAD
+            .decode("nWGxne_9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A")
+            .unwrap();
+        let key = SigningKey::from_bytes(&seed.try_into().unwrap());
+
+        let jwk = ed25519_jwk("rfc", public_pem(&key).as_bytes()).unwrap();
+
+        assert_eq!(jwk.kty, "OKP");
+        assert_eq!(jwk.crv.as_deref(), Some("Ed25519"));
+        assert_eq!(
+            jwk.x.as_deref(),
+            Some("11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo")
+        );
+    }
+
+    #[test]
+    fn ed25519_jwk_rejects_other_key_types() {
+        // An X25519 key has the same 44 byte SubjectPublicKeyInfo layout as
+        // an Ed25519 one; only the algorithm OID tells them apart.
+        let mut der = vec![
+            0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x6e, 0x03, 0x21, 0x00,
+        ];
+        der.extend([7; 32]);
+        let pem = format!(
+            "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+            base64::engine::general_purpose::STANDARD.encode(&der)
+        );
+
+        assert!(ed25519_jwk("x25519", pem.as_bytes()).is_err());
+        assert!(ed25519_jwk("garbage", b"not a pem").is_err());
+    }
+
+    #[test]
+    fn tokens_from_a_rotated_key_still_verify() {
+        let dir = temp_dir();
+        let old = SigningKey::from_bytes(&[1; 32]);
+        let new = SigningKey::from_bytes(&[2; 32]);
+        let before = load(&key_file(&dir, "2026-07", &[("2026-07", &old)]));
+        let after = load(&key_file(
+            &dir,
+            "2026-10",
+            &[("2026-10", &new), ("2026-07", &old)],
+        ));
+
+        let old_token = before.encode(&claims()).unwrap_or_else(|e| panic!("{}", e));
+        let decoded: Claims = after.decode(&old_token).unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(decoded.sub, "user");
+
+        let new_token = after.encode(&claims()).unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(
+            decode_header(&new_token).unwrap().kid.as_deref(),
+            Some("2026-10")
+        );
+        assert!(after.decode::<Claims>(&new_token).is_ok());
+
+        let kids: Vec<_> = after.jwks().keys.iter().map(|k| k.kid.as_str()).collect();
+        assert_eq!(kids, ["2026-10", "2026-07"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn tokens_from_an_unknown_kid_are_rejected() {
+        let dir = temp_dir();
+        let retired = load(&key_file(
+            &dir,
+            "2026-01",
+            &[("2026-01", &SigningKey::from_bytes(&[3; 32]))],
+        ));
+        let current = load(&key_file(
+            &dir,
+            "2026-10",
+            &[("2026-10", &SigningKey::from_bytes(&[4; 32]))],
+        ));
+
+        let token = retired
+            .encode(&claims())
+            .unwrap_or_else(|e| panic!("{}", e));
+        assert!(matches!(
+            current.decode::<Claims>(&token),
+            Err(AppError::Unauthorized)
+        ));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn secret_fallback_signs_and_verifies_hs256() {
+        let keys = JwtKeys::from_secret(b"secret");
+        let token = keys.encode(&claims()).unwrap_or_else(|e| panic!("{}", e));
+
+        let header = decode_header(&token).unwrap();
+        assert_eq!(header.alg, Algorithm::HS256);
+        assert_eq!(header.kid.as_deref(), Some(FALLBACK_KID));
+        assert!(keys.decode::<Claims>(&token).is_ok());
+        assert!(keys.jwks().keys.is_empty());
+
+        let other = JwtKeys::from_secret(b"another secret");
+        assert!(matches!(
+            other.decode::<Claims>(&token),
+            Err(AppError::Unauthorized)
+        ));
+    }
+}
@@ -5,14 +5,23 @@ mod auth;
 mod db;
 mod error;
 mod handlers;
+mod keys;
+mod mailer;
 mod models;
+mod pagination;
+mod sessions;
+mod solana;
+mod tokens;
+mod validation;
+mod wallet_auth;
 
 use axum::{
     routing::{delete, get, patch, post},
     Router,
 };
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 use tower_http::cors::{CorsLayer, Any};
+use tracing_subscriber::EnvFilter;
 use utoipa::{
     openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
     Modify, OpenApi,
@@ -28,15 +37,26 @@ use utoipa_swagger_ui::SwaggerUi;
     paths(
         // Auth
         handlers::login,
+        handlers::refresh,
+        handlers::logout,
+        handlers::logout_all,
+        handlers::wallet_challenge,
+        handlers::wallet_verify,
+        handlers::link_wallet,
+        handlers::verify_email,
+        handlers::resend_verification_email,
+        handlers::forgot_password,
+        handlers::reset_password,
+        handlers::jwks,
         // Users
         handlers::create_user,
-        handlers::get_users,
         handlers::get_user_by_id,
         handlers::update_me,
         handlers::delete_me,
         // Startups
         handlers::create_startup,
         handlers::get_startups,
+        handlers::search_startups,
         handlers::update_startup,
         handlers::delete_startup,
         // Contracts
@@ -62,13 +82,25 @@ use utoipa_swagger_ui::SwaggerUi;
         // Wallet Connections
         handlers::create_wallet_connection,
         handlers::get_wallet_connections_for_user,
-        handlers::delete_wallet_connection
+        handlers::delete_wallet_connection,
+        // Revenue Preview
+        handlers::preview_revenue,
+        // Admin
+        handlers::admin_get_users,
+        handlers::admin_update_user_role,
+        handlers::admin_delete_user
     ),
     // List of schemas (models) used in the endpoints
     components(
         schemas(
             // Payloads
             handlers::LoginPayload,
+            handlers::RefreshPayload,
+            handlers::WalletChallengePayload,
+            handlers::WalletVerifyPayload,
+            handlers::TokenPayload,
+            handlers::ForgotPasswordPayload,
+            handlers::ResetPasswordPayload,
             models::CreateUserPayload,
             models::UpdateUserPayload,
             models::CreateStartupPayload,
@@ -81,17 +113,37 @@ use utoipa_swagger_ui::SwaggerUi;
             models::CreateFilePayload,
             models::CreateNftProgressPayload,
             models::CreateWalletConnectionPayload,
+            models::UpdateUserRolePayload,
             // Responses / Models
+            error::ErrorBody,
+            error::ValidationErrorBody,
+            error::FieldError,
             handlers::LoginResponse,
+            handlers::WalletChallengeResponse,
+            keys::JwkSet,
+            keys::Jwk,
             models::User,
+            models::UserRole,
             models::Startup,
+            models::StartupSearchHit,
             models::Contract,
             models::ContractStatus,
             models::Investment,
             models::Milestone,
             models::File,
             models::NftProgress,
-            models::WalletConnection
+            models::WalletConnection,
+            models::RevenuePreview,
+            // Pages returned by the list endpoints
+            pagination::UserPage,
+            pagination::StartupPage,
+            pagination::StartupSearchPage,
+            pagination::ContractPage,
+            pagination::InvestmentPage,
+            pagination::MilestonePage,
+            pagination::FilePage,
+            pagination::NftProgressPage,
+            pagination::WalletConnectionPage
         )
     ),
     // Add bearer auth security scheme
@@ -127,37 +179,78 @@ async fn main() {
     // Load .env file
     dotenvy::dotenv().expect("Failed to read .env file");
 
-    // . Connect to the Database
+    // Log to stdout, at `info` unless RUST_LOG says otherwise
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .init();
+
+    // 1. Connect to the Database
     let db_pool = db::create_pool()
         .await
         .expect("Failed to create the database connection pool.");
+    db::bootstrap_admin(&db_pool)
+        .await
+        .expect("Failed to promote ADMIN_EMAIL to admin.");
+
+    // 2. Load the JWT signing and verification keys
+    let jwt_keys = keys::JwtKeys::from_env().expect("Failed to load the JWT keys.");
 
-    // . Application state: we pass the PgPool directly as router state
+    // 3. Pick where outgoing email goes (SMTP or the local outbox table), as set by MAILER
+    let mailer = mailer::from_env(db_pool.clone()).expect("Failed to configure the mailer.");
 
-    // . Create the Swagger UI route
+    // 4. Solana RPC client, used to verify transactions reported by clients
+    let solana = solana::JsonRpcClient::from_env().expect("Failed to configure the Solana RPC client.");
+
+    // 5. Application state: handlers extract the pool, keys, mailer or RPC client from it
+    let app_state = handlers::AppState {
+        db_pool,
+        jwt_keys: Arc::new(jwt_keys),
+        mailer,
+        solana: Arc::new(solana),
+    };
+
+    // 6. Create the Swagger UI route
     let swagger_ui = SwaggerUi::new("/swagger-ui")
         .url("/api-docs/openapi.json", ApiDoc::openapi());
 
-    // . Configure CORS
+    // 7. Configure CORS
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
         .allow_headers(Any);
 
-    // 5. Create the Main Router
+    // Moderation routes, grouped under /admin
+    let admin_routes = Router::new()
+        .route("/users", get(handlers::admin_get_users))
+        .route("/users/:id", delete(handlers::admin_delete_user))
+        .route("/users/:id/role", patch(handlers::admin_update_user_role));
+
+    // 8. Create the Main Router
     let app = Router::new()
         // Merge Swagger UI routes into the router
         .merge(swagger_ui)
         
         // --- API Routes ---
         .route("/auth/login", post(handlers::login))
+        .route("/auth/refresh", post(handlers::refresh))
+        .route("/auth/logout", post(handlers::logout))
+        .route("/auth/logout-all", post(handlers::logout_all))
+        .route("/auth/wallet/challenge", post(handlers::wallet_challenge))
+        .route("/auth/wallet/verify", post(handlers::wallet_verify))
+        .route("/auth/verify-email", post(handlers::verify_email))
+        .route("/auth/verify-email/resend", post(handlers::resend_verification_email))
+        .route("/auth/forgot-password", post(handlers::forgot_password))
+        .route("/auth/reset-password", post(handlers::reset_password))
+        .route("/.well-known/jwks.json", get(handlers::jwks))
         .route("/auth/me", patch(handlers::update_me).delete(handlers::delete_me))
         .route("/auth/me/investments", get(handlers::get_my_investments))
+        .route("/auth/me/wallet", post(handlers::link_wallet))
         
-        .route("/users", post(handlers::create_user).get(handlers::get_users))
+        .route("/users", post(handlers::create_user))
         .route("/users/:id", get(handlers::get_user_by_id))
 
         .route("/startups", post(handlers::create_startup).get(handlers::get_startups))
+        .route("/startups/search", get(handlers::search_startups))
         .route("/startups/:id", patch(handlers::update_startup).delete(handlers::delete_startup))
 
         .route("/startups/:startup_id/contracts", post(handlers::create_contract).get(handlers::get_contracts_for_startup))
@@ -177,18 +270,22 @@ async fn main() {
     .route("/auth/me/wallet-connections", post(handlers::create_wallet_connection))
     .route("/users/:id/wallet-connections", get(handlers::get_wallet_connections_for_user))
     .route("/wallet-connections/:id", delete(handlers::delete_wallet_connection))
+    // Revenue preview
+    .route("/revenue/preview", get(handlers::preview_revenue))
+    // Admin (every handler requires `RequireRole<Admin>`)
+    .nest("/admin", admin_routes)
         // ------------------
 
         // Add CORS middleware
         .layer(cors)
-        // Provide `app_state` (with the pool) to all handlers
-        .with_state(db_pool);
+        // Provide `app_state` (with the pool, keys, mailer and RPC client) to all handlers
+        .with_state(app_state);
 
-    // 6. Start the server
-    let port: u6 = std::env::var("PORT")
+    // 9. Start the server
+    let port: u16 = std::env::var("PORT")
         .unwrap_or_else(|_| "8080".to_string())
         .parse()
-        .expect("PORT must be a valid u6");
+        .expect("PORT must be a valid u16");
         
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     println!("Server running at http://{}", addr);
@@ -1,42 +1,82 @@
 // src/models.rs
+use crate::pagination::Filters;
 use sqlx::types::BigDecimal;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use time::OffsetDateTime;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
+use validator::Validate;
 
 // --- User Models ---
 
+#[derive(Serialize, Deserialize, ToSchema, sqlx::Type, Clone, Copy, Debug, PartialEq, Eq)]
+#[sqlx(type_name = "UserRole", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum UserRole {
+    Investor,
+    Founder,
+    Admin,
+}
+
 #[derive(Serialize, FromRow, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct User {
     pub id: Uuid,
     pub full_name: Option<String>,
-    pub email: String,
+    // Missing for users who signed up with their wallet
+    pub email: Option<String>,
     #[serde(skip_serializing)] // Never send password hash to the client
+    #[allow(dead_code)]
     pub password_hash: Option<String>,
-    pub wallet_public_key: String,
+    // Set once the user proves the wallet by signing a challenge
+    pub wallet_public_key: Option<String>,
+    pub role: UserRole,
+    #[schema(value_type = Option<String>)]
+    pub email_verified_at: Option<OffsetDateTime>,
     #[schema(value_type = String)]
     pub created_at: OffsetDateTime,
     #[schema(value_type = String)]
     pub updated_at: OffsetDateTime,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UserFilter {
+    /// Only users with this role
+    pub role: Option<UserRole>,
+}
+
+impl UserFilter {
+    pub fn apply(&self, filters: &mut Filters) {
+        if let Some(role) = self.role {
+            filters.and().push("role = ").push_bind(role);
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateUserPayload {
+    #[validate(email)]
     pub email: String,
+    #[validate(length(min = 8, max = 128))]
     pub password: String,
-    pub wallet_public_key: String,
+    #[validate(length(min = 1, max = 200))]
     pub full_name: Option<String>,
 }
 
-#[derive(Deserialize, ToSchema, Default)]
+#[derive(Deserialize, ToSchema, Validate, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateUserPayload {
+    #[validate(length(min = 1, max = 200))]
     pub full_name: Option<String>,
-    pub wallet_public_key: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateUserRolePayload {
+    pub role: UserRole,
 }
 
 // --- Startup Models ---
@@ -56,25 +96,77 @@ pub struct Startup {
     pub created_at: OffsetDateTime,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StartupFilter {
+    /// Only startups owned by this user
+    pub owner_id: Option<Uuid>,
+}
+
+impl StartupFilter {
+    pub fn apply(&self, filters: &mut Filters) {
+        if let Some(owner_id) = self.owner_id {
+            filters.and().push("user_id = ").push_bind(owner_id);
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StartupSearchQuery {
+    /// Search terms; supports `"quoted phrases"`, `or` and `-excluded` words
+    pub q: String,
+    /// Results to return, 20 by default and at most 100
+    pub limit: Option<i64>,
+    /// `nextCursor` from the previous page
+    pub cursor: Option<String>,
+    /// Results to skip; ignored when `cursor` is set
+    pub offset: Option<i64>,
+}
+
+#[derive(Serialize, FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupSearchHit {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub startup: Startup,
+    pub rank: f32,
+    /// Passages of the name, slogan, description and problem around the
+    /// matches, with the matched words wrapped in `<mark>`
+    pub headline: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateStartupPayload {
+    #[validate(length(min = 1, max = 120))]
     pub name: String,
+    #[validate(length(max = 200))]
     pub slogan: Option<String>,
+    #[validate(length(max = 5000))]
     pub description: Option<String>,
+    #[validate(length(max = 5000))]
     pub problem: Option<String>,
+    #[validate(url)]
     pub logo_url: Option<String>,
+    #[validate(url)]
     pub video_pitch_url: Option<String>,
 }
 
-#[derive(Deserialize, ToSchema, Default)]
+#[derive(Deserialize, ToSchema, Validate, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateStartupPayload {
+    #[validate(length(min = 1, max = 120))]
     pub name: Option<String>,
+    #[validate(length(max = 200))]
     pub slogan: Option<String>,
+    #[validate(length(max = 5000))]
     pub description: Option<String>,
+    #[validate(length(max = 5000))]
     pub problem: Option<String>,
+    #[validate(url)]
     pub logo_url: Option<String>,
+    #[validate(url)]
     pub video_pitch_url: Option<String>,
 }
 
@@ -104,23 +196,44 @@ pub struct Contract {
     pub created_at: OffsetDateTime,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ContractFilter {
+    /// Only contracts in this status
+    pub status: Option<ContractStatus>,
+}
+
+impl ContractFilter {
+    pub fn apply(&self, filters: &mut Filters) {
+        if let Some(status) = &self.status {
+            filters.and().push("status = ").push_bind(status.clone());
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateContractPayload {
     #[schema(value_type = String)]
+    #[validate(custom(function = "crate::validation::positive_amount"))]
     pub requested_amount: Option<BigDecimal>,
     #[schema(value_type = String)]
+    #[validate(custom(function = "crate::validation::percentage"))]
     pub equity_offered: Option<BigDecimal>,
+    #[validate(custom(function = "crate::validation::solana_pubkey"))]
     pub smart_contract_address: Option<String>,
 }
 
-#[derive(Deserialize, ToSchema, Default)]
+#[derive(Deserialize, ToSchema, Validate, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateContractPayload {
     #[schema(value_type = String)]
+    #[validate(custom(function = "crate::validation::positive_amount"))]
     pub requested_amount: Option<BigDecimal>,
     #[schema(value_type = String)]
+    #[validate(custom(function = "crate::validation::percentage"))]
     pub equity_offered: Option<BigDecimal>,
+    #[validate(custom(function = "crate::validation::solana_pubkey"))]
     pub smart_contract_address: Option<String>,
     pub status: Option<ContractStatus>,
 }
@@ -136,18 +249,22 @@ pub struct Investment {
     #[schema(value_type = String)]
     pub amount_invested: Option<BigDecimal>,
     pub nft_token_id: Option<String>,
-    pub transaction_hash: Option<String>,
+    pub transaction_hash: String,
     #[schema(value_type = String)]
     pub created_at: OffsetDateTime,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateInvestmentPayload {
     #[schema(value_type = String)]
+    #[validate(custom(function = "crate::validation::positive_amount"))]
     pub amount_invested: BigDecimal,
+    #[validate(custom(function = "crate::validation::solana_pubkey"))]
     pub nft_token_id: Option<String>,
-    pub transaction_hash: Option<String>,
+    // Signature of the confirmed `invest` transaction, checked on-chain
+    #[validate(custom(function = "crate::validation::solana_signature"))]
+    pub transaction_hash: String,
 }
 
 // --- Milestone Models ---
@@ -161,17 +278,21 @@ pub struct Milestone {
     pub description: Option<String>,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateMilestonePayload {
+    #[validate(length(min = 1, max = 200))]
     pub title: String,
+    #[validate(length(max = 5000))]
     pub description: Option<String>,
 }
 
-#[derive(Deserialize, ToSchema, Default)]
+#[derive(Deserialize, ToSchema, Validate, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateMilestonePayload {
+    #[validate(length(min = 1, max = 200))]
     pub title: Option<String>,
+    #[validate(length(max = 5000))]
     pub description: Option<String>,
 }
 
@@ -188,10 +309,12 @@ pub struct File {
     pub created_at: OffsetDateTime,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateFilePayload {
+    #[validate(url)]
     pub file_url: String,
+    #[validate(length(max = 1000))]
     pub description: Option<String>,
 }
 
@@ -208,9 +331,10 @@ pub struct NftProgress {
     pub updated_at: OffsetDateTime,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateNftProgressPayload {
+    #[validate(length(min = 1, max = 100))]
     pub visual_state: Option<String>,
 }
 
@@ -227,9 +351,31 @@ pub struct WalletConnection {
     pub connected_at: OffsetDateTime,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateWalletConnectionPayload {
+    #[validate(custom(function = "crate::validation::solana_pubkey"))]
     pub wallet_public_key: String,
+    #[validate(length(min = 1, max = 32))]
     pub network: Option<String>,
 }
+
+// --- Revenue Preview Models ---
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct RevenuePreviewQuery {
+    /// Reported revenue in base units (lamports or token base units)
+    pub revenue_amount: u64,
+    /// Investor share of the revenue in basis points
+    pub investor_return_bps: u16,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RevenuePreview {
+    pub platform_fee: u64,
+    pub investor_share: u64,
+    pub startup_share: u64,
+}
@@ -0,0 +1,472 @@
+// src/pagination.rs
+use crate::{
+    error::AppError,
+    models::{
+        Contract, File, Investment, Milestone, NftProgress, Startup, StartupSearchHit, User,
+        WalletConnection,
+    },
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sqlx::{postgres::PgRow, FromRow, PgPool, Postgres, QueryBuilder, Row};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+pub const DEFAULT_LIMIT: i64 = 20;
+pub const MAX_LIMIT: i64 = 100;
+
+/// Paging, sorting and time range parameters shared by every list endpoint.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListQuery {
+    /// Items per page, 20 by default and at most 100
+    pub limit: Option<i64>,
+    /// `nextCursor` from the previous page
+    pub cursor: Option<String>,
+    /// Number of items to skip; ignored when `cursor` is set
+    pub offset: Option<i64>,
+    /// Field to sort by, prefixed with `-` for descending order (e.g. `-created_at`)
+    pub sort: Option<String>,
+    /// Only items created at or after this time (RFC 3339)
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    #[param(value_type = Option<String>)]
+    pub created_after: Option<OffsetDateTime>,
+    /// Only items created before this time (RFC 3339)
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    #[param(value_type = Option<String>)]
+    pub created_before: Option<OffsetDateTime>,
+}
+
+/// One page of a list. `total` counts every item matching the filters, not
+/// just the ones after the cursor.
+#[derive(Serialize, ToSchema)]
+#[aliases(
+    UserPage = Page<User>,
+    StartupPage = Page<Startup>,
+    StartupSearchPage = Page<StartupSearchHit>,
+    ContractPage = Page<Contract>,
+    InvestmentPage = Page<Investment>,
+    MilestonePage = Page<Milestone>,
+    FilePage = Page<File>,
+    NftProgressPage = Page<NftProgress>,
+    WalletConnectionPage = Page<WalletConnection>
+)]
+#[serde(rename_all = "camelCase")]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Pass as `cursor` to get the next page; missing on the last page
+    pub next_cursor: Option<String>,
+    pub total: i64,
+}
+
+/// What a list endpoint reads from and how it may be sorted.
+pub struct Listing {
+    pub table: &'static str,
+    /// Columns selected for each item: the fields of the item type, and
+    /// nothing internal to the table
+    pub columns: &'static str,
+    /// Columns the list can be sorted by, with their types. Only NOT NULL
+    /// columns work, since the cursor compares against the last value.
+    pub sort_fields: &'static [(&'static str, SortType)],
+    pub default_sort: &'static str,
+    /// Timestamp column `created_after` and `created_before` apply to
+    pub created_column: Option<&'static str>,
+}
+
+/// Type of a sort column, which decides how its value is kept in a cursor.
+#[derive(Clone, Copy)]
+pub enum SortType {
+    Timestamp,
+    Text,
+}
+
+impl SortType {
+    // Reads `column` from the last row of a page as cursor text. Timestamps
+    // are formatted here as RFC 3339, not by Postgres, whose text output
+    // depends on the session's DateStyle and TimeZone.
+    fn read(self, row: &PgRow, column: &str) -> Result<String, AppError> {
+        Ok(match self {
+            SortType::Timestamp => row
+                .try_get::<OffsetDateTime, _>(column)?
+                .format(&Rfc3339)
+                .map_err(|e| AppError::InternalServerError(e.to_string()))?,
+            SortType::Text => row.try_get(column)?,
+        })
+    }
+
+    // Binds a cursor value back with its own type
+    fn bind(self, builder: &mut QueryBuilder<'_, Postgres>, value: &str) -> Result<(), AppError> {
+        match self {
+            SortType::Timestamp => {
+                let value = OffsetDateTime::parse(value, &Rfc3339)
+                    .map_err(|_| AppError::BadRequest("Invalid cursor".to_string()))?;
+                builder.push_bind(value);
+            }
+            SortType::Text => {
+                builder.push_bind(value.to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The WHERE clause of a list query; conditions are joined with AND.
+pub struct Filters<'q> {
+    builder: QueryBuilder<'q, Postgres>,
+    empty: bool,
+}
+
+impl<'q> Filters<'q> {
+    /// Starts a new condition and returns the builder to write it with.
+    pub fn and(&mut self) -> &mut QueryBuilder<'q, Postgres> {
+        self.builder.push(if self.empty { " WHERE " } else { " AND " });
+        self.empty = false;
+        &mut self.builder
+    }
+}
+
+// A position in a sorted list: the sort it belongs to plus the sort value
+// (see `SortType::read`) and id of the last item on the page. Sent to clients as
+// base64url JSON, which they treat as opaque.
+#[derive(Serialize, Deserialize)]
+struct Cursor {
+    sort: String,
+    value: String,
+    id: Uuid,
+}
+
+// Position in a list that can only be paged by offset, such as search
+// results ranked by relevance, where no column identifies where a page ends
+#[derive(Serialize, Deserialize)]
+struct OffsetCursor {
+    offset: i64,
+}
+
+fn encode_cursor(cursor: &impl Serialize) -> String {
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(cursor).unwrap_or_default())
+}
+
+fn decode_cursor<C: DeserializeOwned>(raw: &str) -> Result<C, AppError> {
+    URL_SAFE_NO_PAD
+        .decode(raw)
+        .ok()
+        .and_then(|json| serde_json::from_slice(&json).ok())
+        .ok_or(AppError::BadRequest("Invalid cursor".to_string()))
+}
+
+impl<T> Page<T> {
+    /// Builds a page of a list paged by offset. `items` are the rows fetched
+    /// from `offset` with a limit of `limit + 1`; the extra one only tells
+    /// whether there is a next page.
+    pub fn by_offset(mut items: Vec<T>, limit: i64, offset: i64, total: i64) -> Self {
+        let next_cursor = (items.len() as i64 > limit).then(|| {
+            items.truncate(limit as usize);
+            encode_cursor(&OffsetCursor {
+                offset: offset + limit,
+            })
+        });
+        Page {
+            items,
+            next_cursor,
+            total,
+        }
+    }
+}
+
+/// The offset a `Page::by_offset` cursor points at.
+pub fn cursor_offset(raw: &str) -> Result<i64, AppError> {
+    let cursor: OffsetCursor = decode_cursor(raw)?;
+    Ok(cursor.offset)
+}
+
+impl Listing {
+    // Resolves `sort` to its column and type, and whether it's descending
+    fn sort_field(&self, sort: &str) -> Result<(&'static str, SortType, bool), AppError> {
+        let (name, descending) = match sort.strip_prefix('-') {
+            Some(name) => (name, true),
+            None => (sort, false),
+        };
+        self.sort_fields
+            .iter()
+            .find(|(column, _)| *column == name)
+            .map(|(column, sort_type)| (*column, *sort_type, descending))
+            .ok_or_else(|| {
+                let allowed: Vec<_> = self.sort_fields.iter().map(|(column, _)| *column).collect();
+                AppError::BadRequest(format!(
+                    "Cannot sort by {}; use one of: {}",
+                    name,
+                    allowed.join(", ")
+                ))
+            })
+    }
+
+    fn filters<'q>(
+        &self,
+        select: String,
+        query: &ListQuery,
+        extra: &impl Fn(&mut Filters<'q>),
+    ) -> Result<Filters<'q>, AppError> {
+        let mut filters = Filters {
+            builder: QueryBuilder::new(select),
+            empty: true,
+        };
+        if query.created_after.is_some() || query.created_before.is_some() {
+            let column = self.created_column.ok_or(AppError::BadRequest(
+                "This list can't be filtered by creation time".to_string(),
+            ))?;
+            if let Some(after) = query.created_after {
+                filters.and().push(column).push(" >= ").push_bind(after);
+            }
+            if let Some(before) = query.created_before {
+                filters.and().push(column).push(" < ").push_bind(before);
+            }
+        }
+        extra(&mut filters);
+        Ok(filters)
+    }
+
+    /// Runs the list query for one page. `extra` adds the endpoint's own
+    /// conditions (parent id, owner, status...); it is called twice, once for
+    /// the page and once for the total.
+    pub async fn fetch<T>(
+        &self,
+        pool: &PgPool,
+        query: &ListQuery,
+        extra: impl Fn(&mut Filters),
+    ) -> Result<Page<T>, AppError>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+    {
+        let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+        if !(1..=MAX_LIMIT).contains(&limit) {
+            return Err(AppError::BadRequest(format!(
+                "limit must be between 1 and {}",
+                MAX_LIMIT
+            )));
+        }
+        let offset = query.offset.unwrap_or(0);
+        if offset < 0 {
+            return Err(AppError::BadRequest("offset can't be negative".to_string()));
+        }
+        let sort = query.sort.as_deref().unwrap_or(self.default_sort);
+        let (column, sort_type, descending) = self.sort_field(sort)?;
+        let cursor: Option<Cursor> = query.cursor.as_deref().map(decode_cursor).transpose()?;
+        if cursor.as_ref().is_some_and(|cursor| cursor.sort != sort) {
+            return Err(AppError::BadRequest(
+                "The cursor was issued for a different sort".to_string(),
+            ));
+        }
+
+        let count_select = format!("SELECT count(*) FROM {}", self.table);
+        let mut count = self.filters(count_select, query, &extra)?;
+        let total: i64 = count.builder.build_query_scalar().fetch_one(pool).await?;
+
+        let mut page = self.filters(
+            format!("SELECT {} FROM {}", self.columns, self.table),
+            query,
+            &extra,
+        )?;
+        let (direction, comparison) = if descending { ("DESC", "<") } else { ("ASC", ">") };
+        // Ties on the sort column are broken by id, so (column, id) gives
+        // every item a unique position
+        if let Some(cursor) = &cursor {
+            let builder = page.and().push(format!("({}, id) {} (", column, comparison));
+            sort_type.bind(builder, &cursor.value)?;
+            builder.push(", ").push_bind(cursor.id).push(")");
+        }
+        page.builder
+            .push(format!(" ORDER BY {} {}, id {} LIMIT ", column, direction, direction))
+            // One extra row tells whether there is a next page
+            .push_bind(limit + 1);
+        if cursor.is_none() {
+            page.builder.push(" OFFSET ").push_bind(offset);
+        }
+        let mut rows = page.builder.build().fetch_all(pool).await?;
+
+        let next_cursor = if rows.len() as i64 > limit {
+            rows.truncate(limit as usize);
+            let last = &rows[rows.len() - 1];
+            Some(encode_cursor(&Cursor {
+                sort: sort.to_string(),
+                value: sort_type.read(last, column)?,
+                id: last.try_get("id")?,
+            }))
+        } else {
+            None
+        };
+        let items = rows
+            .iter()
+            .map(T::from_row)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Page {
+            items,
+            next_cursor,
+            total,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ITEMS: Listing = Listing {
+        table: "items",
+        columns: "id, name",
+        sort_fields: &[("created_at", SortType::Timestamp), ("name", SortType::Text)],
+        default_sort: "-created_at",
+        created_column: Some("created_at"),
+    };
+
+    #[derive(FromRow)]
+    struct Item {
+        id: Uuid,
+        name: String,
+    }
+
+    fn bad_request(result: Result<impl Sized, AppError>) -> String {
+        match result {
+            Err(AppError::BadRequest(message)) => message,
+            Err(e) => panic!("expected a bad request, got {}", e),
+            Ok(_) => panic!("expected a bad request"),
+        }
+    }
+
+    fn list(sort: &str, limit: i64, cursor: Option<String>) -> ListQuery {
+        ListQuery {
+            limit: Some(limit),
+            cursor,
+            offset: None,
+            sort: Some(sort.to_string()),
+            created_after: None,
+            created_before: None,
+        }
+    }
+
+    #[test]
+    fn cursors_round_trip() {
+        let id = Uuid::new_v4();
+        let raw = encode_cursor(&Cursor {
+            sort: "-created_at".to_string(),
+            value: "2026-10-19T12:00:00Z".to_string(),
+            id,
+        });
+
+        let cursor: Cursor = decode_cursor(&raw).unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(cursor.sort, "-created_at");
+        assert_eq!(cursor.value, "2026-10-19T12:00:00Z");
+        assert_eq!(cursor.id, id);
+    }
+
+    #[test]
+    fn malformed_cursors_are_rejected() {
+        let raw = encode_cursor(&Cursor {
+            sort: "name".to_string(),
+            value: "Acme".to_string(),
+            id: Uuid::new_v4(),
+        });
+        let mut tampered = raw.clone().into_bytes();
+        tampered[0] ^= 1;
+
+        for raw in [
+            String::from_utf8(tampered).unwrap(),
+            raw[..raw.len() / 2].to_string(),
+            "not base64!".to_string(),
+            URL_SAFE_NO_PAD.encode("not json"),
+            URL_SAFE_NO_PAD.encode(r#"{"offset": 20}"#),
+        ] {
+            assert_eq!(bad_request(decode_cursor::<Cursor>(&raw)), "Invalid cursor");
+        }
+    }
+
+    #[test]
+    fn only_whitelisted_fields_sort() {
+        assert!(matches!(
+            ITEMS.sort_field("-created_at"),
+            Ok(("created_at", SortType::Timestamp, true))
+        ));
+        assert!(matches!(
+            ITEMS.sort_field("name"),
+            Ok(("name", SortType::Text, false))
+        ));
+
+        for sort in ["email", "name; DROP TABLE items", "--name", ""] {
+            let message = bad_request(ITEMS.sort_field(sort));
+            assert!(message.ends_with("use one of: created_at, name"), "{}", message);
+        }
+    }
+
+    #[test]
+    fn offset_cursors_point_past_the_page() {
+        let page = Page::by_offset(vec![1, 2, 3], 2, 40, 100);
+        assert_eq!(page.items, [1, 2]);
+        let cursor = page.next_cursor.expect("a next page");
+        assert_eq!(cursor_offset(&cursor).unwrap_or_else(|e| panic!("{}", e)), 42);
+
+        let last = Page::by_offset(vec![1, 2], 2, 42, 100);
+        assert!(last.next_cursor.is_none());
+
+        // A keyset cursor is not an offset cursor.
+        let keyset = encode_cursor(&Cursor {
+            sort: "name".to_string(),
+            value: "Acme".to_string(),
+            id: Uuid::new_v4(),
+        });
+        assert_eq!(bad_request(cursor_offset(&keyset)), "Invalid cursor");
+    }
+
+    #[sqlx::test]
+    async fn cursors_walk_every_item_once(pool: PgPool) {
+        sqlx::query(
+            "CREATE TABLE items (id uuid PRIMARY KEY, name text NOT NULL, created_at timestamptz NOT NULL DEFAULT now())",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        // Ties on the sort column are broken by id.
+        for name in ["b", "a", "b", "c", "b"] {
+            sqlx::query("INSERT INTO items (id, name) VALUES ($1, $2)")
+                .bind(Uuid::new_v4())
+                .bind(name)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        let mut expected: Vec<(String, Uuid)> =
+            sqlx::query_as("SELECT name, id FROM items ORDER BY name DESC, id DESC")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page: Page<Item> = ITEMS
+                .fetch(&pool, &list("-name", 2, cursor), |_| {})
+                .await
+                .unwrap_or_else(|e| panic!("{}", e));
+            assert_eq!(page.total, 5);
+            seen.extend(page.items.into_iter().map(|item| (item.name, item.id)));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(seen, expected);
+
+        // A cursor only continues the sort it was issued for.
+        expected.truncate(2);
+        let first: Page<Item> = ITEMS
+            .fetch(&pool, &list("-name", 2, None), |_| {})
+            .await
+            .unwrap_or_else(|e| panic!("{}", e));
+        let message = bad_request(
+            ITEMS
+                .fetch::<Item>(&pool, &list("name", 2, first.next_cursor), |_| {})
+                .await,
+        );
+        assert_eq!(message, "The cursor was issued for a different sort");
+    }
+}
@@ -0,0 +1,172 @@
+// src/sessions.rs
+use crate::{error::AppError, models::UserRole, tokens};
+use sqlx::PgPool;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+// How long a login lasts without signing in again. Refreshing rotates the
+// token but doesn't extend the session.
+pub const REFRESH_TOKEN_TTL: Duration = Duration::days(30);
+
+/// Starts a session for `user_id`, returning its id and the refresh token.
+pub async fn create(pool: &PgPool, user_id: Uuid) -> Result<(Uuid, String), AppError> {
+    let (refresh_token, hash) = tokens::generate();
+    let expires_at = OffsetDateTime::now_utc() + REFRESH_TOKEN_TTL;
+
+    let session_id = sqlx::query_scalar!(
+        "INSERT INTO sessions (user_id, refresh_token_hash, expires_at) VALUES ($1, $2, $3) RETURNING id",
+        user_id,
+        hash,
+        expires_at
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok((session_id, refresh_token))
+}
+
+/// Swaps a refresh token for a new one on the same session, returning the
+/// user id, the user's current role, the session id and the new token.
+///
+/// A token that was already rotated out means it leaked (or the client
+/// replayed it), so the whole session is revoked.
+pub async fn rotate(
+    pool: &PgPool,
+    refresh_token: &str,
+) -> Result<(Uuid, UserRole, Uuid, String), AppError> {
+    let presented = tokens::hash(refresh_token);
+    let (new_token, new_hash) = tokens::generate();
+
+    let session = sqlx::query!(
+        r#"
+        UPDATE sessions
+        SET previous_token_hash = refresh_token_hash, refresh_token_hash = $1, last_used_at = now()
+        FROM users
+        WHERE sessions.refresh_token_hash = $2
+            AND sessions.revoked_at IS NULL
+            AND sessions.expires_at > now()
+            AND users.id = sessions.user_id
+        RETURNING sessions.id, sessions.user_id, users.role AS "role: UserRole"
+        "#,
+        new_hash,
+        presented
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    match session {
+        Some(session) => Ok((session.user_id, session.role, session.id, new_token)),
+        None => {
+            sqlx::query!(
+                "UPDATE sessions SET revoked_at = now() WHERE previous_token_hash = $1 AND revoked_at IS NULL",
+                presented
+            )
+            .execute(pool)
+            .await?;
+            Err(AppError::Unauthorized)
+        }
+    }
+}
+
+/// Whether an access token's session is still live.
+pub async fn is_active(pool: &PgPool, session_id: Uuid, user_id: Uuid) -> Result<bool, AppError> {
+    let active = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM sessions
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > now()
+        ) AS "active!"
+        "#,
+        session_id,
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(active)
+}
+
+pub async fn revoke(pool: &PgPool, session_id: Uuid) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE sessions SET revoked_at = now() WHERE id = $1 AND revoked_at IS NULL",
+        session_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Revokes every session of `user_id`, logging it out on all devices.
+pub async fn revoke_all(pool: &PgPool, user_id: Uuid) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE sessions SET revoked_at = now() WHERE user_id = $1 AND revoked_at IS NULL",
+        user_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn user(pool: &PgPool) -> Uuid {
+        sqlx::query_scalar("INSERT INTO users (email) VALUES ('user@example.com') RETURNING id")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn rotate_ok(pool: &PgPool, refresh_token: &str) -> (Uuid, String) {
+        let (_, _, session_id, token) = rotate(pool, refresh_token)
+            .await
+            .unwrap_or_else(|e| panic!("{}", e));
+        (session_id, token)
+    }
+
+    async fn active(pool: &PgPool, session_id: Uuid, user_id: Uuid) -> bool {
+        is_active(pool, session_id, user_id)
+            .await
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    #[sqlx::test]
+    async fn rotation_keeps_the_session_and_replaces_the_token(pool: PgPool) {
+        let user_id = user(&pool).await;
+        let (session_id, first) = create(&pool, user_id)
+            .await
+            .unwrap_or_else(|e| panic!("{}", e));
+
+        let (rotated_session, second) = rotate_ok(&pool, &first).await;
+        assert_eq!(rotated_session, session_id);
+        assert_ne!(second, first);
+
+        // The new token works once too, and the session stays live.
+        let (rotated_session, _) = rotate_ok(&pool, &second).await;
+        assert_eq!(rotated_session, session_id);
+        assert!(active(&pool, session_id, user_id).await);
+    }
+
+    #[sqlx::test]
+    async fn replaying_a_rotated_token_revokes_the_session(pool: PgPool) {
+        let user_id = user(&pool).await;
+        let (session_id, first) = create(&pool, user_id)
+            .await
+            .unwrap_or_else(|e| panic!("{}", e));
+        let (_, second) = rotate_ok(&pool, &first).await;
+
+        assert!(matches!(
+            rotate(&pool, &first).await,
+            Err(AppError::Unauthorized)
+        ));
+        assert!(!active(&pool, session_id, user_id).await);
+        // Whoever holds the current token is logged out as well.
+        assert!(matches!(
+            rotate(&pool, &second).await,
+            Err(AppError::Unauthorized)
+        ));
+    }
+}
@@ -0,0 +1,522 @@
+// src/solana.rs
+use crate::error::AppError;
+use axum::async_trait;
+use meraki_sdk::{decode_invest, Pubkey};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::types::BigDecimal;
+use std::{env, str::FromStr, time::Duration};
+
+/// A transaction as seen by the cluster.
+#[derive(Clone)]
+pub struct ConfirmedTransaction {
+    /// False if the transaction landed but one of its instructions failed
+    pub succeeded: bool,
+    /// Every account the transaction referenced, including ones loaded from
+    /// address lookup tables
+    pub account_keys: Vec<Pubkey>,
+    /// Top-level and inner (CPI) instructions
+    pub instructions: Vec<CompiledInstruction>,
+}
+
+#[derive(Clone)]
+pub struct CompiledInstruction {
+    pub program_id_index: usize,
+    pub accounts: Vec<usize>,
+    pub data: Vec<u8>,
+}
+
+/// Read access to a Solana cluster. Handlers get it from the app state as
+/// `State<Arc<dyn SolanaRpc>>`.
+#[async_trait]
+pub trait SolanaRpc: Send + Sync {
+    /// Fetches a confirmed transaction by signature, or `None` if the
+    /// cluster doesn't know it (yet).
+    async fn get_transaction(
+        &self,
+        signature: &str,
+    ) -> Result<Option<ConfirmedTransaction>, AppError>;
+}
+
+/// Talks to an RPC node over JSON-RPC.
+pub struct JsonRpcClient {
+    http: reqwest::Client,
+    url: String,
+}
+
+impl JsonRpcClient {
+    /// Connects to `SOLANA_RPC_URL`.
+    pub fn from_env() -> Result<Self, String> {
+        let url =
+            env::var("SOLANA_RPC_URL").map_err(|_| "SOLANA_RPC_URL must be set".to_string())?;
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| format!("failed to build the HTTP client: {}", e))?;
+
+        Ok(JsonRpcClient { http, url })
+    }
+}
+
+// The parts of a `getTransaction` response (with `"encoding": "json"`) that
+// verification needs
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcTransaction {
+    meta: Option<RpcMeta>,
+    transaction: RpcInnerTransaction,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcMeta {
+    err: Option<serde_json::Value>,
+    #[serde(default)]
+    inner_instructions: Vec<RpcInnerInstructions>,
+    loaded_addresses: Option<RpcLoadedAddresses>,
+}
+
+#[derive(Deserialize)]
+struct RpcInnerInstructions {
+    instructions: Vec<RpcInstruction>,
+}
+
+#[derive(Deserialize)]
+struct RpcLoadedAddresses {
+    writable: Vec<String>,
+    readonly: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RpcInnerTransaction {
+    message: RpcMessage,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcMessage {
+    account_keys: Vec<String>,
+    instructions: Vec<RpcInstruction>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcInstruction {
+    program_id_index: usize,
+    accounts: Vec<usize>,
+    // base58
+    data: String,
+}
+
+#[async_trait]
+impl SolanaRpc for JsonRpcClient {
+    async fn get_transaction(
+        &self,
+        signature: &str,
+    ) -> Result<Option<ConfirmedTransaction>, AppError> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getTransaction",
+            "params": [
+                signature,
+                { "encoding": "json", "commitment": "confirmed", "maxSupportedTransactionVersion": 0 }
+            ]
+        });
+        let response: RpcResponse<RpcTransaction> = self
+            .http
+            .post(&self.url)
+            .json(&request)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(http_failed)?
+            .json()
+            .await
+            .map_err(http_failed)?;
+        if let Some(error) = response.error {
+            return Err(rpc_failed(error.message));
+        }
+
+        response
+            .result
+            .map(RpcTransaction::into_confirmed)
+            .transpose()
+    }
+}
+
+fn rpc_failed(e: impl std::fmt::Display) -> AppError {
+    AppError::Upstream(format!("Solana RPC request failed: {}", e))
+}
+
+// The URL can carry the RPC provider's API key, so it stays out of the error
+fn http_failed(e: reqwest::Error) -> AppError {
+    rpc_failed(e.without_url())
+}
+
+impl RpcTransaction {
+    fn into_confirmed(self) -> Result<ConfirmedTransaction, AppError> {
+        let meta = self
+            .meta
+            .ok_or_else(|| rpc_failed("transaction has no status"))?;
+        let message = self.transaction.message;
+        // Lookup table addresses come after the static keys, writable first
+        let mut keys = message.account_keys;
+        if let Some(loaded) = meta.loaded_addresses {
+            keys.extend(loaded.writable);
+            keys.extend(loaded.readonly);
+        }
+        let account_keys = keys
+            .iter()
+            .map(|key| Pubkey::from_str(key).map_err(rpc_failed))
+            .collect::<Result<Vec<_>, _>>()?;
+        let instructions = message
+            .instructions
+            .into_iter()
+            .chain(
+                meta.inner_instructions
+                    .into_iter()
+                    .flat_map(|inner| inner.instructions),
+            )
+            .map(|instruction| {
+                let data = bs58::decode(&instruction.data)
+                    .into_vec()
+                    .map_err(rpc_failed)?;
+                Ok(CompiledInstruction {
+                    program_id_index: instruction.program_id_index,
+                    accounts: instruction.accounts,
+                    data,
+                })
+            })
+            .collect::<Result<Vec<_>, AppError>>()?;
+
+        Ok(ConfirmedTransaction {
+            succeeded: meta.err.is_none(),
+            account_keys,
+            instructions,
+        })
+    }
+}
+
+/// Checks that `signature` is a successful transaction in which `investor`
+/// put `amount` base units into the on-chain contract at `contract_address`
+/// through the Meraki program.
+pub async fn verify_investment(
+    rpc: &dyn SolanaRpc,
+    signature: &str,
+    contract_address: &str,
+    investor: &str,
+    amount: &BigDecimal,
+) -> Result<(), AppError> {
+    let transaction = rpc
+        .get_transaction(signature)
+        .await?
+        .ok_or(AppError::BadRequest(
+            "Transaction not found; submit it once it is confirmed".to_string(),
+        ))?;
+    if !transaction.succeeded {
+        return Err(AppError::BadRequest(
+            "Transaction failed on-chain".to_string(),
+        ));
+    }
+
+    let key = |index: usize| transaction.account_keys.get(index).copied();
+    let mut invested: u64 = 0;
+    let mut calls = 0;
+    for instruction in &transaction.instructions {
+        if key(instruction.program_id_index) != Some(meraki_sdk::ID) {
+            continue;
+        }
+        let accounts = instruction
+            .accounts
+            .iter()
+            .map(|&index| key(index))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(AppError::BadRequest("Malformed transaction".to_string()))?;
+        let decoded = decode_invest(&instruction.data, &accounts)
+            .map_err(|e| AppError::BadRequest(format!("Malformed invest instruction: {}", e)))?;
+        let Some(decoded) = decoded else {
+            continue;
+        };
+        if decoded.contract.to_string() == contract_address
+            && decoded.investor.to_string() == investor
+        {
+            invested = invested.saturating_add(decoded.amount);
+            calls += 1;
+        }
+    }
+
+    if calls == 0 {
+        return Err(AppError::BadRequest(
+            "Transaction does not invest in this contract from your wallet".to_string(),
+        ));
+    }
+    if BigDecimal::from(invested) != *amount {
+        return Err(AppError::BadRequest(format!(
+            "amountInvested does not match the transaction, which invested {}",
+            invested
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+    use meraki_sdk::invest::INVEST_NATIVE_DISCRIMINATOR;
+
+    /// Answers every signature with the same transaction, or with none.
+    pub struct FakeRpc(pub Option<ConfirmedTransaction>);
+
+    #[async_trait]
+    impl SolanaRpc for FakeRpc {
+        async fn get_transaction(
+            &self,
+            _signature: &str,
+        ) -> Result<Option<ConfirmedTransaction>, AppError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    /// A successful transaction where `investor` calls `invest_native` on
+    /// `contract` directly.
+    pub fn native_investment(
+        contract: Pubkey,
+        investor: Pubkey,
+        amount: u64,
+    ) -> ConfirmedTransaction {
+        ConfirmedTransaction {
+            succeeded: true,
+            account_keys: vec![investor, contract, meraki_sdk::ID],
+            instructions: vec![CompiledInstruction {
+                program_id_index: 2,
+                accounts: vec![1, 0],
+                data: [
+                    INVEST_NATIVE_DISCRIMINATOR.as_slice(),
+                    &amount.to_le_bytes(),
+                ]
+                .concat(),
+            }],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::FakeRpc;
+    use super::*;
+    use meraki_sdk::invest::{INVEST_DISCRIMINATOR, INVEST_NATIVE_DISCRIMINATOR};
+
+    struct Deal {
+        investor: Pubkey,
+        contract: Pubkey,
+        router: Pubkey,
+    }
+
+    // Account keys of the test transactions, by index
+    const INVESTOR: usize = 0;
+    const CONTRACT: usize = 1;
+    const MERAKI: usize = 2;
+    const ROUTER: usize = 3;
+
+    impl Deal {
+        fn new() -> Self {
+            Deal {
+                investor: Pubkey::new_unique(),
+                contract: Pubkey::new_unique(),
+                router: Pubkey::new_unique(),
+            }
+        }
+
+        fn transaction(&self, instructions: Vec<CompiledInstruction>) -> ConfirmedTransaction {
+            ConfirmedTransaction {
+                succeeded: true,
+                account_keys: vec![self.investor, self.contract, meraki_sdk::ID, self.router],
+                instructions,
+            }
+        }
+
+        async fn verify(
+            &self,
+            transaction: Option<ConfirmedTransaction>,
+            amount: u64,
+        ) -> Result<(), AppError> {
+            verify_investment(
+                &FakeRpc(transaction),
+                "signature",
+                &self.contract.to_string(),
+                &self.investor.to_string(),
+                &BigDecimal::from(amount),
+            )
+            .await
+        }
+    }
+
+    fn invest(program: usize, discriminator: [u8; 8], amount: u64) -> CompiledInstruction {
+        CompiledInstruction {
+            program_id_index: program,
+            accounts: vec![CONTRACT, INVESTOR],
+            data: [discriminator.as_slice(), &amount.to_le_bytes()].concat(),
+        }
+    }
+
+    fn rejection(result: Result<(), AppError>) -> String {
+        match result {
+            Err(AppError::BadRequest(message)) => message,
+            Err(other) => panic!("expected a bad request, got {}", other),
+            Ok(()) => panic!("expected the transaction to be rejected"),
+        }
+    }
+
+    #[tokio::test]
+    async fn accepts_a_matching_investment() {
+        let deal = Deal::new();
+        let transaction = deal.transaction(vec![invest(MERAKI, INVEST_NATIVE_DISCRIMINATOR, 500)]);
+
+        assert!(deal.verify(Some(transaction), 500).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn accepts_investments_made_through_another_program() {
+        let deal = Deal::new();
+        // The router's own instruction, then its CPIs into Meraki
+        let router_call = CompiledInstruction {
+            program_id_index: ROUTER,
+            accounts: vec![CONTRACT, INVESTOR, MERAKI],
+            data: vec![1, 2, 3],
+        };
+        let transaction = deal.transaction(vec![
+            router_call,
+            invest(MERAKI, INVEST_DISCRIMINATOR, 300),
+            invest(MERAKI, INVEST_DISCRIMINATOR, 200),
+        ]);
+
+        assert!(deal.verify(Some(transaction), 500).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_signatures() {
+        let deal = Deal::new();
+
+        assert!(rejection(deal.verify(None, 500).await).contains("not found"));
+    }
+
+    #[tokio::test]
+    async fn rejects_failed_transactions() {
+        let deal = Deal::new();
+        let transaction = ConfirmedTransaction {
+            succeeded: false,
+            ..deal.transaction(vec![invest(MERAKI, INVEST_NATIVE_DISCRIMINATOR, 500)])
+        };
+
+        assert!(rejection(deal.verify(Some(transaction), 500).await).contains("failed"));
+    }
+
+    #[tokio::test]
+    async fn rejects_invest_calls_to_another_program() {
+        let deal = Deal::new();
+        let transaction = deal.transaction(vec![invest(ROUTER, INVEST_NATIVE_DISCRIMINATOR, 500)]);
+
+        assert!(rejection(deal.verify(Some(transaction), 500).await).contains("does not invest"));
+    }
+
+    #[tokio::test]
+    async fn rejects_investments_in_another_contract() {
+        let deal = Deal::new();
+        let transaction = deal.transaction(vec![invest(MERAKI, INVEST_NATIVE_DISCRIMINATOR, 500)]);
+        let other = Deal {
+            contract: Pubkey::new_unique(),
+            ..deal
+        };
+
+        assert!(rejection(other.verify(Some(transaction), 500).await).contains("does not invest"));
+    }
+
+    #[tokio::test]
+    async fn rejects_investments_from_another_wallet() {
+        let deal = Deal::new();
+        let transaction = deal.transaction(vec![invest(MERAKI, INVEST_NATIVE_DISCRIMINATOR, 500)]);
+        let other = Deal {
+            investor: Pubkey::new_unique(),
+            ..deal
+        };
+
+        assert!(rejection(other.verify(Some(transaction), 500).await).contains("does not invest"));
+    }
+
+    #[tokio::test]
+    async fn rejects_a_different_amount() {
+        let deal = Deal::new();
+        let transaction = deal.transaction(vec![invest(MERAKI, INVEST_NATIVE_DISCRIMINATOR, 500)]);
+
+        assert!(rejection(deal.verify(Some(transaction), 501).await).contains("invested 500"));
+    }
+
+    #[test]
+    fn flattens_inner_instructions_and_loaded_addresses() {
+        let (investor, contract) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (router, table_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let data = bs58::encode([INVEST_DISCRIMINATOR.as_slice(), &7u64.to_le_bytes()].concat())
+            .into_string();
+        let response: RpcTransaction = serde_json::from_value(json!({
+            "meta": {
+                "err": null,
+                "innerInstructions": [{
+                    "index": 0,
+                    "instructions": [{ "programIdIndex": 4, "accounts": [1, 0], "data": data }]
+                }],
+                "loadedAddresses": {
+                    "writable": [table_key.to_string()],
+                    "readonly": [meraki_sdk::ID.to_string()]
+                }
+            },
+            "transaction": {
+                "message": {
+                    "accountKeys": [investor.to_string(), contract.to_string(), router.to_string()],
+                    "instructions": [{ "programIdIndex": 2, "accounts": [1, 0], "data": "" }]
+                }
+            }
+        }))
+        .unwrap();
+
+        let transaction = response
+            .into_confirmed()
+            .unwrap_or_else(|e| panic!("{}", e));
+        assert!(transaction.succeeded);
+        assert_eq!(
+            transaction.account_keys,
+            vec![investor, contract, router, table_key, meraki_sdk::ID]
+        );
+        assert_eq!(transaction.instructions.len(), 2);
+        assert_eq!(transaction.instructions[1].program_id_index, 4);
+        assert_eq!(&transaction.instructions[1].data[8..], &7u64.to_le_bytes());
+    }
+
+    #[test]
+    fn reports_failed_transactions_as_unsuccessful() {
+        let response: RpcTransaction = serde_json::from_value(json!({
+            "meta": { "err": { "InstructionError": [0, "Custom"] } },
+            "transaction": { "message": { "accountKeys": [], "instructions": [] } }
+        }))
+        .unwrap();
+
+        assert!(
+            !response
+                .into_confirmed()
+                .unwrap_or_else(|e| panic!("{}", e))
+                .succeeded
+        );
+    }
+}
@@ -0,0 +1,97 @@
+// src/tokens.rs
+use crate::error::AppError;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+/// Generates an opaque token, returning it and the hash to store.
+///
+/// Tokens are 256 random bits, so a plain SHA-256 is enough to keep the
+/// stored hashes useless to someone reading the table.
+pub fn generate() -> (String, String) {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let token = URL_SAFE_NO_PAD.encode(bytes);
+    let hash = hash(&token);
+    (token, hash)
+}
+
+pub fn hash(token: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes()))
+}
+
+// --- Single-use tokens sent by email ---
+
+#[derive(Serialize, Deserialize, sqlx::Type, Clone, Copy, Debug)]
+#[sqlx(type_name = "UserTokenPurpose", rename_all = "snake_case")]
+pub enum UserTokenPurpose {
+    VerifyEmail,
+    ResetPassword,
+}
+
+impl UserTokenPurpose {
+    pub fn ttl(self) -> Duration {
+        match self {
+            UserTokenPurpose::VerifyEmail => Duration::days(1),
+            UserTokenPurpose::ResetPassword => Duration::hours(1),
+        }
+    }
+}
+
+/// Issues a token for `purpose`, replacing any unused one the user has for
+/// the same purpose.
+pub async fn issue(
+    pool: &PgPool,
+    user_id: Uuid,
+    purpose: UserTokenPurpose,
+) -> Result<String, AppError> {
+    let (token, token_hash) = generate();
+    let expires_at = OffsetDateTime::now_utc() + purpose.ttl();
+
+    let mut tx = pool.begin().await?;
+    sqlx::query!(
+        "DELETE FROM user_tokens WHERE user_id = $1 AND purpose = $2 AND used_at IS NULL",
+        user_id,
+        purpose as _
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        "INSERT INTO user_tokens (user_id, purpose, token_hash, expires_at) VALUES ($1, $2, $3, $4)",
+        user_id,
+        purpose as _,
+        token_hash,
+        expires_at
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(token)
+}
+
+/// Marks a token as used and returns its user, or `None` if it is unknown,
+/// expired, already used or meant for another purpose.
+pub async fn redeem(
+    pool: &PgPool,
+    token: &str,
+    purpose: UserTokenPurpose,
+) -> Result<Option<Uuid>, AppError> {
+    let user_id = sqlx::query_scalar!(
+        r#"
+        UPDATE user_tokens SET used_at = now()
+        WHERE token_hash = $1 AND purpose = $2 AND used_at IS NULL AND expires_at > now()
+        RETURNING user_id
+        "#,
+        hash(token),
+        purpose as _
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user_id)
+}
@@ -0,0 +1,291 @@
+// src/validation.rs
+use crate::error::{AppError, FieldError};
+use axum::{
+    async_trait,
+    extract::{FromRequest, Request},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use sqlx::types::BigDecimal;
+use std::borrow::Cow;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+/// Like `Json<T>`, but also runs the payload's `#[validate(...)]` rules and
+/// rejects it with 422 and the list of invalid fields. A missing or
+/// wrong-typed field is reported the same way; only a body that isn't JSON
+/// at all is a 400.
+pub struct ValidatedJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<Value>::from_request(req, state)
+            .await
+            .map_err(|rejection| AppError::BadRequest(rejection.body_text()))?;
+        let payload: T = serde_path_to_error::deserialize(value)
+            .map_err(|error| AppError::Validation(vec![data_error(error)]))?;
+
+        payload
+            .validate()
+            .map_err(|errors| AppError::Validation(field_errors(&errors)))?;
+        Ok(ValidatedJson(payload))
+    }
+}
+
+fn field_errors(errors: &ValidationErrors) -> Vec<FieldError> {
+    let mut fields: Vec<FieldError> = errors
+        .field_errors()
+        .into_iter()
+        .flat_map(|(name, errors)| {
+            let field = json_name(name);
+            errors.iter().map(move |error| FieldError {
+                field: field.clone(),
+                code: error.code.to_string(),
+                message: message(error),
+            })
+        })
+        .collect();
+    fields.sort_by(|a, b| a.field.cmp(&b.field));
+    fields
+}
+
+// A field serde couldn't read. Its path is already in wire names; a missing
+// field is reported by its parent, so its own name comes from the message.
+fn data_error(error: serde_path_to_error::Error<serde_json::Error>) -> FieldError {
+    let parent = match error.path().to_string() {
+        root if root == "." => None,
+        path => Some(path),
+    };
+    let message = error.into_inner().to_string();
+    let missing = message
+        .strip_prefix("missing field `")
+        .and_then(|rest| rest.strip_suffix('`'));
+
+    match missing {
+        Some(name) => FieldError {
+            field: parent.map_or(name.to_string(), |parent| format!("{}.{}", parent, name)),
+            code: "required".to_string(),
+            message: "is required".to_string(),
+        },
+        None => FieldError {
+            field: parent.unwrap_or_default(),
+            code: if message.starts_with("invalid type") {
+                "invalid_type".to_string()
+            } else {
+                "invalid_value".to_string()
+            },
+            message,
+        },
+    }
+}
+
+// Payloads are camelCase on the wire, so errors name fields the way the
+// client spelled them rather than by their Rust names
+fn json_name(field: &str) -> String {
+    let mut camel = String::with_capacity(field.len());
+    let mut upper = false;
+    for c in field.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            camel.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+fn message(error: &ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+    let param = |name: &str| error.params.get(name).map(Value::to_string);
+    match (error.code.as_ref(), param("min"), param("max")) {
+        ("email", _, _) => "must be a valid email address".to_string(),
+        ("url", _, _) => "must be a valid URL".to_string(),
+        ("length", Some(min), Some(max)) => {
+            format!("must be between {} and {} characters", min, max)
+        }
+        ("length", Some(min), None) => format!("must be at least {} characters", min),
+        ("length", None, Some(max)) => format!("must be at most {} characters", max),
+        (code, _, _) => format!("is invalid ({})", code),
+    }
+}
+
+fn invalid(code: &'static str, message: &'static str) -> ValidationError {
+    ValidationError::new(code).with_message(Cow::Borrowed(message))
+}
+
+// --- Custom rules, used as `#[validate(custom(function = "..."))]` ---
+
+/// A base58 Solana address (32 bytes). Program-derived addresses are off the
+/// ed25519 curve, so only the length is checked.
+pub fn solana_pubkey(value: &str) -> Result<(), ValidationError> {
+    match bs58::decode(value).into_vec() {
+        Ok(bytes) if bytes.len() == 32 => Ok(()),
+        _ => Err(invalid("solana_pubkey", "must be a base58 Solana address")),
+    }
+}
+
+/// A base58 transaction signature (64 bytes).
+pub fn solana_signature(value: &str) -> Result<(), ValidationError> {
+    match bs58::decode(value).into_vec() {
+        Ok(bytes) if bytes.len() == 64 => Ok(()),
+        _ => Err(invalid(
+            "solana_signature",
+            "must be a base58 transaction signature",
+        )),
+    }
+}
+
+pub fn positive_amount(value: &BigDecimal) -> Result<(), ValidationError> {
+    if *value > BigDecimal::from(0) {
+        Ok(())
+    } else {
+        Err(invalid("positive", "must be greater than zero"))
+    }
+}
+
+/// A share of equity, in percent.
+pub fn percentage(value: &BigDecimal) -> Result<(), ValidationError> {
+    if *value > BigDecimal::from(0) && *value <= BigDecimal::from(100) {
+        Ok(())
+    } else {
+        Err(invalid("percentage", "must be more than 0 and at most 100"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use serde::Deserialize;
+    use std::str::FromStr;
+
+    #[derive(Deserialize, Validate)]
+    #[serde(rename_all = "camelCase")]
+    struct Payload {
+        #[validate(email)]
+        email: String,
+        #[allow(dead_code)]
+        amount_invested: u64,
+    }
+
+    async fn extract(body: &str) -> Result<Payload, AppError> {
+        let request = Request::builder()
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        ValidatedJson::<Payload>::from_request(request, &())
+            .await
+            .map(|ValidatedJson(payload)| payload)
+    }
+
+    // The (field, code) pairs of a 422
+    async fn invalid_fields(body: &str) -> Vec<(String, String)> {
+        match extract(body).await {
+            Err(AppError::Validation(fields)) => {
+                fields.into_iter().map(|f| (f.field, f.code)).collect()
+            }
+            Err(other) => panic!("expected a 422, got {}", other),
+            Ok(_) => panic!("expected a 422"),
+        }
+    }
+
+    fn pair(field: &str, code: &str) -> (String, String) {
+        (field.to_string(), code.to_string())
+    }
+
+    #[tokio::test]
+    async fn valid_bodies_pass() {
+        let payload = extract(r#"{"email": "a@example.com", "amountInvested": 5}"#)
+            .await
+            .unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(payload.email, "a@example.com");
+    }
+
+    #[tokio::test]
+    async fn missing_and_mistyped_fields_are_field_errors() {
+        assert_eq!(
+            invalid_fields(r#"{"email": "a@example.com"}"#).await,
+            [pair("amountInvested", "required")]
+        );
+        assert_eq!(
+            invalid_fields(r#"{"email": "a@example.com", "amountInvested": "five"}"#).await,
+            [pair("amountInvested", "invalid_type")]
+        );
+        assert_eq!(
+            invalid_fields(r#"{"email": "a@example.com", "amountInvested": -5}"#).await,
+            [pair("amountInvested", "invalid_value")]
+        );
+        assert_eq!(
+            invalid_fields(r#"{"email": "not an email", "amountInvested": 5}"#).await,
+            [pair("email", "email")]
+        );
+    }
+
+    #[tokio::test]
+    async fn malformed_json_is_still_a_bad_request() {
+        assert!(matches!(
+            extract(r#"{"email": "#).await,
+            Err(AppError::BadRequest(_))
+        ));
+        // Well-formed JSON of the wrong shape has no field to name.
+        assert_eq!(invalid_fields("[]").await, [pair("", "invalid_value")]);
+    }
+
+    #[test]
+    fn solana_pubkey_needs_32_base58_bytes() {
+        assert!(solana_pubkey(&bs58::encode([7u8; 32]).into_string()).is_ok());
+        assert!(solana_pubkey("11111111111111111111111111111111").is_ok());
+
+        assert!(solana_pubkey(&bs58::encode([7u8; 31]).into_string()).is_err());
+        assert!(solana_pubkey(&bs58::encode([7u8; 64]).into_string()).is_err());
+        // 0, O, I and l are not in the base58 alphabet
+        assert!(solana_pubkey("0OIl0OIl0OIl0OIl0OIl0OIl0OIl0OIl").is_err());
+        assert!(solana_pubkey("").is_err());
+    }
+
+    #[test]
+    fn solana_signature_needs_64_base58_bytes() {
+        assert!(solana_signature(&bs58::encode([9u8; 64]).into_string()).is_ok());
+
+        assert!(solana_signature(&bs58::encode([9u8; 32]).into_string()).is_err());
+        assert!(solana_signature("not a signature").is_err());
+    }
+
+    #[test]
+    fn percentage_is_above_zero_and_at_most_100() {
+        let check = |value: &str| percentage(&BigDecimal::from_str(value).unwrap());
+
+        assert!(check("0.01").is_ok());
+        assert!(check("100").is_ok());
+
+        assert!(check("0").is_err());
+        assert!(check("-5").is_err());
+        assert!(check("100.01").is_err());
+    }
+
+    #[test]
+    fn invalid_values_report_their_rule() {
+        let error = percentage(&BigDecimal::from(0)).unwrap_err();
+
+        assert_eq!(error.code, "percentage");
+        assert_eq!(message(&error), "must be more than 0 and at most 100");
+    }
+
+    #[test]
+    fn json_name_is_camel_case() {
+        assert_eq!(json_name("wallet_public_key"), "walletPublicKey");
+        assert_eq!(json_name("email"), "email");
+    }
+}
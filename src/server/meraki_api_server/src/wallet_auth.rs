@@ -0,0 +1,138 @@
+// src/wallet_auth.rs
+use crate::error::AppError;
+use ed25519_dalek::{Signature, VerifyingKey};
+use rand_core::{OsRng, RngCore};
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime};
+
+// How long a sign-in message can be signed and redeemed
+pub const CHALLENGE_TTL: Duration = Duration::minutes(5);
+
+/// Decodes a base58 Solana address into an ed25519 public key.
+pub fn parse_public_key(wallet_public_key: &str) -> Result<VerifyingKey, AppError> {
+    let invalid = || AppError::BadRequest("Invalid wallet public key".to_string());
+    let bytes: [u8; 32] = bs58::decode(wallet_public_key)
+        .into_vec()
+        .map_err(|_| invalid())?
+        .try_into()
+        .map_err(|_| invalid())?;
+
+    VerifyingKey::from_bytes(&bytes).map_err(|_| invalid())
+}
+
+pub fn generate_nonce() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bs58::encode(bytes).into_string()
+}
+
+/// Builds the message the wallet signs, in the Sign-In With Solana format
+/// wallets display to the user.
+pub fn challenge_message(
+    wallet_public_key: &str,
+    nonce: &str,
+    issued_at: OffsetDateTime,
+    expires_at: OffsetDateTime,
+) -> Result<String, AppError> {
+    let domain = std::env::var("SIWS_DOMAIN").unwrap_or_else(|_| "localhost".to_string());
+    let format = |at: OffsetDateTime| {
+        at.format(&Rfc3339)
+            .map_err(|e| AppError::InternalServerError(format!("Failed to format date: {}", e)))
+    };
+
+    Ok(format!(
+        "{domain} wants you to sign in with your Solana account:\n\
+         {wallet_public_key}\n\
+         \n\
+         Sign in to Meraki.\n\
+         \n\
+         Nonce: {nonce}\n\
+         Issued At: {}\n\
+         Expiration Time: {}",
+        format(issued_at)?,
+        format(expires_at)?,
+    ))
+}
+
+/// Checks a base58 ed25519 signature by `public_key` over `message`.
+pub fn verify_signature(
+    public_key: &VerifyingKey,
+    message: &str,
+    signature: &str,
+) -> Result<(), AppError> {
+    let bytes = bs58::decode(signature)
+        .into_vec()
+        .map_err(|_| AppError::Unauthorized)?;
+    let signature = Signature::from_slice(&bytes).map_err(|_| AppError::Unauthorized)?;
+
+    public_key
+        .verify_strict(message.as_bytes(), &signature)
+        .map_err(|_| AppError::Unauthorized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn wallet(seed: u8) -> (SigningKey, String) {
+        let key = SigningKey::from_bytes(&[seed; 32]);
+        let address = bs58::encode(key.verifying_key().as_bytes()).into_string();
+        (key, address)
+    }
+
+    fn message(address: &str) -> String {
+        let issued_at = OffsetDateTime::UNIX_EPOCH;
+        challenge_message(address, "nonce", issued_at, issued_at + CHALLENGE_TTL)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn sign(key: &SigningKey, message: &str) -> String {
+        bs58::encode(key.sign(message.as_bytes()).to_bytes()).into_string()
+    }
+
+    #[test]
+    fn parses_base58_addresses() {
+        let (key, address) = wallet(1);
+        let parsed = parse_public_key(&address).unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(parsed, key.verifying_key());
+
+        for invalid in ["", "0OIl", &address[..address.len() - 2]] {
+            assert!(matches!(
+                parse_public_key(invalid),
+                Err(AppError::BadRequest(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn accepts_the_wallets_signature() {
+        let (key, address) = wallet(1);
+        let message = message(&address);
+        assert!(message.contains(&address));
+
+        let result = verify_signature(&key.verifying_key(), &message, &sign(&key, &message));
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn rejects_another_wallets_signature() {
+        let (key, address) = wallet(1);
+        let (other, _) = wallet(2);
+        let message = message(&address);
+
+        let result = verify_signature(&key.verifying_key(), &message, &sign(&other, &message));
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+    }
+
+    #[test]
+    fn rejects_a_signature_over_another_message() {
+        let (key, address) = wallet(1);
+        let signature = sign(&key, &message(&address));
+        let (_, other_address) = wallet(2);
+
+        let result = verify_signature(&key.verifying_key(), &message(&other_address), &signature);
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+        let result = verify_signature(&key.verifying_key(), &message(&address), "not base58!");
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+    }
+}